@@ -0,0 +1,277 @@
+use std::io::{self, Write};
+use url::Url;
+
+use crate::json::Value;
+use crate::{OutputOptions, ParseOptions, Records};
+
+/// What the log can't say on its own about the requested URLs.
+#[derive(Debug, Clone, Default)]
+pub struct Hints<'a> {
+    /// Scheme to assume; defaults to https for port 443 and http otherwise.
+    pub scheme: Option<&'a str>,
+    /// Host to assume for formats that don't record the virtual host.
+    pub host: Option<&'a str>,
+}
+
+/// One request from an access log.
+#[derive(Debug, PartialEq)]
+pub struct Entry<'a> {
+    /// The `host:port` virtual host field of Apache's `vhost_combined` format.
+    pub vhost: Option<&'a str>,
+    pub client: &'a str,
+    pub method: &'a str,
+    pub target: &'a str,
+    pub status: &'a str,
+}
+
+/// Prints the URL of every request in an Apache or nginx access log,
+/// rewritten as `parsing` says, exiting 1 if any line wasn't a request.
+pub fn run(log: &str, hints: &Hints<'_>, parsing: &ParseOptions, output: &OutputOptions) {
+    let mut out = io::stdout().lock();
+    if let Ok(false) = write_requests(&mut out, log, hints, parsing, output) {
+        std::process::exit(1);
+    }
+}
+
+/// Writes the URL of each request in `log`, returning whether every line
+/// was one.
+fn write_requests<W: Write>(
+    writer: &mut W,
+    log: &str,
+    hints: &Hints<'_>,
+    parsing: &ParseOptions,
+    output: &OutputOptions,
+) -> io::Result<bool> {
+    let mut records = Records::new(writer, output);
+    let mut read_all = true;
+    for (line, text) in log.lines().enumerate() {
+        if text.trim().is_empty() {
+            continue;
+        }
+        let Some(entry) = parse_line(text) else {
+            eprintln!("Line {}: not a recognized access log entry", line + 1);
+            read_all = false;
+            continue;
+        };
+        let Some(url) = request_url(&entry, hints) else {
+            eprintln!(
+                "Line {}: cannot build a URL for {} (pass --host for logs without a virtual host)",
+                line + 1,
+                entry.target
+            );
+            read_all = false;
+            continue;
+        };
+
+        let mut extra = vec![
+            ("client", Value::from(entry.client)),
+            ("method", Value::from(entry.method)),
+            ("status", Value::from(entry.status)),
+        ];
+        let Some(url) = parsing.prepare(url, &mut extra) else {
+            continue;
+        };
+        records.write(&url, &extra)?;
+    }
+    Ok(read_all)
+}
+
+/// Parses a line in the common, combined, or vhost_combined format. The
+/// formats differ only in whether a `host:port` field leads and whether
+/// referrer and user agent follow, so the fields before the `[time]` tell
+/// them apart.
+pub fn parse_line(line: &str) -> Option<Entry<'_>> {
+    let fields = split_fields(line);
+    let time = fields.iter().position(|f| f.starts_with('['))?;
+    let (vhost, client) = match time {
+        3 => (None, fields[0]),
+        4 => (Some(fields[0]), fields[1]),
+        _ => return None,
+    };
+
+    let request = fields.get(time + 1)?.strip_prefix('"')?.strip_suffix('"')?;
+    let mut parts = request.split_whitespace();
+    let method = parts.next()?;
+    let target = parts.next()?;
+    let status = fields.get(time + 2)?;
+
+    Some(Entry {
+        vhost,
+        client,
+        method,
+        target,
+        status,
+    })
+}
+
+/// Splits on spaces, keeping `[...]` and `"..."` fields (with `\"` escapes) whole.
+fn split_fields(line: &str) -> Vec<&str> {
+    let bytes = line.as_bytes();
+    let mut fields = Vec::new();
+    let mut pos = 0;
+
+    while pos < bytes.len() {
+        if bytes[pos] == b' ' {
+            pos += 1;
+            continue;
+        }
+
+        let start = pos;
+        match bytes[pos] {
+            b'"' => {
+                pos += 1;
+                while pos < bytes.len() && bytes[pos] != b'"' {
+                    pos += if bytes[pos] == b'\\' { 2 } else { 1 };
+                }
+                pos = (pos + 1).min(bytes.len());
+            }
+            b'[' => {
+                pos = line[pos..].find(']').map_or(bytes.len(), |i| pos + i + 1);
+            }
+            _ => {
+                while pos < bytes.len() && bytes[pos] != b' ' {
+                    pos += 1;
+                }
+            }
+        }
+        fields.push(&line[start..pos]);
+    }
+
+    fields
+}
+
+/// Rebuilds the requested URL from the request target and the virtual host.
+pub fn request_url(entry: &Entry<'_>, hints: &Hints<'_>) -> Option<Url> {
+    if let Ok(url) = Url::parse(entry.target) {
+        // Proxies log absolute-form targets.
+        if url.has_host() {
+            return Some(url);
+        }
+    }
+
+    // `--host` is only for formats that don't record the vhost.
+    let (host, port) = match (entry.vhost, hints.host) {
+        (Some(vhost), _) => match vhost.rsplit_once(':') {
+            Some((host, port)) if port.bytes().all(|b| b.is_ascii_digit()) => (host, Some(port)),
+            _ => (vhost, None),
+        },
+        (None, Some(host)) => (host, None),
+        (None, None) => return None,
+    };
+
+    let scheme = hints
+        .scheme
+        .unwrap_or(if port == Some("443") { "https" } else { "http" });
+    let mut url = Url::parse(&format!("{}://{}", scheme, host)).ok()?;
+    if let Some(port) = port.and_then(|p| p.parse().ok()) {
+        url.set_port(Some(port)).ok()?;
+    }
+    url.join(entry.target).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const COMBINED: &str = r#"127.0.0.1 - frank [10/Oct/2000:13:55:36 -0700] "GET /a.gif?x=1 HTTP/1.0" 200 2326 "http://www.example.com/start.html" "Mozilla/4.08 [en] (Win98; I ;Nav)""#;
+    const VHOST: &str = r#"shop.example.com:443 10.0.0.7 - - [10/Oct/2000:13:55:36 -0700] "POST /cart HTTP/1.1" 302 0 "-" "curl/8.0""#;
+
+    #[test]
+    fn test_parse_combined_and_vhost_lines() {
+        let entry = parse_line(COMBINED).unwrap();
+        assert_eq!(entry.vhost, None);
+        assert_eq!(entry.client, "127.0.0.1");
+        assert_eq!(
+            (entry.method, entry.target, entry.status),
+            ("GET", "/a.gif?x=1", "200")
+        );
+
+        let entry = parse_line(VHOST).unwrap();
+        assert_eq!(entry.vhost, Some("shop.example.com:443"));
+        assert_eq!(
+            (entry.method, entry.target, entry.status),
+            ("POST", "/cart", "302")
+        );
+
+        assert!(parse_line("not a log line").is_none());
+    }
+
+    #[test]
+    fn test_request_url() {
+        let hints = Hints::default();
+        let vhost = request_url(&parse_line(VHOST).unwrap(), &hints).unwrap();
+        assert_eq!(vhost.as_str(), "https://shop.example.com/cart");
+
+        let combined = parse_line(COMBINED).unwrap();
+        assert_eq!(request_url(&combined, &hints), None);
+
+        let hints = Hints {
+            scheme: Some("https"),
+            host: Some("www.example.com"),
+        };
+        let url = request_url(&combined, &hints).unwrap();
+        assert_eq!(url.as_str(), "https://www.example.com/a.gif?x=1");
+
+        let vhost = request_url(&parse_line(VHOST).unwrap(), &hints).unwrap();
+        assert_eq!(vhost.as_str(), "https://shop.example.com/cart");
+    }
+
+    #[test]
+    fn test_request_url_vhost_port_and_absolute_target() {
+        let line = r#"api.example.com:8080 ::1 - - [01/Jan/2024:00:00:00 +0000] "GET /v1 HTTP/1.1" 200 5 "-" "-""#;
+        let url = request_url(&parse_line(line).unwrap(), &Hints::default()).unwrap();
+        assert_eq!(url.as_str(), "http://api.example.com:8080/v1");
+
+        let line = r#"1.2.3.4 - - [01/Jan/2024:00:00:00 +0000] "GET http://proxied.example/x HTTP/1.1" 200 5"#;
+        let url = request_url(&parse_line(line).unwrap(), &Hints::default()).unwrap();
+        assert_eq!(url.as_str(), "http://proxied.example/x");
+    }
+
+    #[test]
+    fn test_write_requests_rewritten() {
+        let parsing = ParseOptions {
+            strip: vec!["query"],
+            ..Default::default()
+        };
+        let options = OutputOptions {
+            get: vec!["url".to_string()],
+            ..Default::default()
+        };
+        let hints = Hints {
+            scheme: Some("https"),
+            host: Some("www.example.com"),
+        };
+        let mut output = Vec::new();
+        let log = format!("{}\nnot a log line\n", COMBINED);
+        let read_all = write_requests(&mut output, &log, &hints, &parsing, &options).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "https://www.example.com/a.gif\n"
+        );
+        assert!(!read_all);
+    }
+
+    #[test]
+    fn test_write_requests_json() {
+        let options = OutputOptions {
+            json_output: true,
+            ..Default::default()
+        };
+        let mut output = Vec::new();
+        let read_all = write_requests(
+            &mut output,
+            VHOST,
+            &Hints::default(),
+            &ParseOptions::default(),
+            &options,
+        )
+        .unwrap();
+        assert!(read_all);
+
+        let output_str = String::from_utf8(output).unwrap();
+        assert!(output_str.ends_with(
+            r#","client":"10.0.0.7","method":"POST","status":"302"}
+"#
+        ));
+    }
+}
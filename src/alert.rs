@@ -0,0 +1,235 @@
+use std::collections::HashSet;
+use std::io::Write;
+use std::process::{Command, Stdio};
+use url::Url;
+
+use crate::json::Value;
+use crate::{option_value, redact};
+
+/// One reason a URL stood out.
+#[derive(Debug, PartialEq)]
+pub struct Alert {
+    /// What raised it: `length`, `new-host`, or `secret`.
+    pub kind: &'static str,
+    pub detail: String,
+}
+
+impl Alert {
+    pub fn to_value(&self) -> Value {
+        Value::Object(vec![
+            ("alert".to_string(), Value::from(self.kind)),
+            ("detail".to_string(), Value::from(self.detail.as_str())),
+        ])
+    }
+}
+
+/// The thresholds `--follow` watches for, from the `--alert-*` flags, and
+/// the hosts it has seen so far.
+#[derive(Debug, Default)]
+pub struct Alerts {
+    /// Alert on URLs longer than this many bytes.
+    max_length: Option<usize>,
+    /// Alert the first time each host appears.
+    new_host: bool,
+    /// Alert on passwords and secret-looking parameters.
+    secrets: bool,
+    /// Shell command to run for each URL that raises alerts.
+    exec: Option<String>,
+    /// Print only the URLs that raise alerts.
+    pub only: bool,
+    seen_hosts: HashSet<String>,
+}
+
+impl Alerts {
+    /// Applies `arg` if it is an alert option, taking its value from `rest`.
+    pub fn accept<'a>(&mut self, arg: &str, rest: &mut impl Iterator<Item = &'a String>) -> bool {
+        match arg {
+            "--alert-length" => {
+                let value = option_value(rest, arg);
+                self.max_length = Some(value.parse().unwrap_or_else(|_| {
+                    eprintln!(
+                        "Expected a number of bytes for --alert-length, got {}",
+                        value
+                    );
+                    std::process::exit(1);
+                }));
+            }
+            "--alert-new-host" => self.new_host = true,
+            "--alert-secrets" => self.secrets = true,
+            "--alert-exec" => self.exec = Some(option_value(rest, arg).to_string()),
+            "--alerts-only" => self.only = true,
+            _ => return false,
+        }
+        true
+    }
+
+    /// Whether any flag asked for alerts.
+    pub fn configured(&self) -> bool {
+        self.max_length.is_some() || self.new_host || self.secrets
+    }
+
+    /// Whether a flag that only makes sense with thresholds was given.
+    pub fn needs_thresholds(&self) -> bool {
+        self.exec.is_some() || self.only
+    }
+
+    /// Returns the alerts `url` raises, remembering its host. `words` are
+    /// looked for in parameter keys along with the built-in secret words.
+    pub fn check(&mut self, url: &Url, words: &[String]) -> Vec<Alert> {
+        let mut alerts = Vec::new();
+        if let Some(limit) = self.max_length
+            && url.as_str().len() > limit
+        {
+            alerts.push(Alert {
+                kind: "length",
+                detail: format!("{} bytes, over the limit of {}", url.as_str().len(), limit),
+            });
+        }
+        if let Some(host) = url.host_str()
+            && self.seen_hosts.insert(host.to_string())
+            && self.new_host
+        {
+            alerts.push(Alert {
+                kind: "new-host",
+                detail: format!("first URL for {}", host),
+            });
+        }
+        if self.secrets {
+            let found = redact::secrets(url, words);
+            if !found.is_empty() {
+                alerts.push(Alert {
+                    kind: "secret",
+                    detail: found.join(", "),
+                });
+            }
+        }
+        alerts
+    }
+
+    /// Runs the `--alert-exec` command, if there is one, through the shell,
+    /// with `KURL_URL` and `KURL_ALERTS` (the comma-separated kinds) set,
+    /// `KURL_ID` too when the record has an `id`, and a JSON object of the
+    /// URL, its id, and its alerts on stdin. It runs to the end before the
+    /// next line is read, so alerts arrive in order.
+    pub fn run_exec(&self, url: &Url, id: Option<&str>, alerts: &[Alert]) {
+        let Some(command_line) = &self.exec else {
+            return;
+        };
+        let kinds: Vec<&str> = alerts.iter().map(|alert| alert.kind).collect();
+        let mut event = vec![("url".to_string(), Value::from(url.as_str()))];
+        if let Some(id) = id {
+            event.push(("id".to_string(), Value::from(id)));
+        }
+        event.push((
+            "alerts".to_string(),
+            Value::Array(alerts.iter().map(Alert::to_value).collect()),
+        ));
+        let event = Value::Object(event);
+        let mut command = Command::new("sh");
+        command
+            .arg("-c")
+            .arg(command_line)
+            .env("KURL_URL", url.as_str())
+            .env("KURL_ALERTS", kinds.join(","));
+        if let Some(id) = id {
+            command.env("KURL_ID", id);
+        }
+        let child = command.stdin(Stdio::piped()).spawn();
+        let mut child = match child {
+            Ok(child) => child,
+            Err(e) => {
+                eprintln!("Failed to run --alert-exec command: {}", e);
+                return;
+            }
+        };
+        if let Some(mut stdin) = child.stdin.take() {
+            // A command that ignores its input closes the pipe early.
+            let _ = writeln!(stdin, "{}", event.to_json());
+        }
+        match child.wait() {
+            Ok(status) if !status.success() => {
+                eprintln!("--alert-exec command failed for {}: {}", url, status)
+            }
+            Err(e) => eprintln!("--alert-exec command failed for {}: {}", url, e),
+            Ok(_) => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(alerts: &mut Alerts, url: &str) -> Vec<&'static str> {
+        let url = Url::parse(url).unwrap();
+        alerts
+            .check(&url, &[])
+            .iter()
+            .map(|alert| alert.kind)
+            .collect()
+    }
+
+    #[test]
+    fn test_check() {
+        let mut alerts = Alerts {
+            max_length: Some(40),
+            new_host: true,
+            secrets: true,
+            ..Default::default()
+        };
+        assert_eq!(kinds(&mut alerts, "https://a.example/"), vec!["new-host"]);
+        assert!(kinds(&mut alerts, "https://a.example/b").is_empty());
+        assert_eq!(
+            kinds(
+                &mut alerts,
+                "https://a.example/login?session=abc&next=/home"
+            ),
+            vec!["length", "secret"]
+        );
+        let url = Url::parse("https://a.example/?token=x").unwrap();
+        assert_eq!(
+            alerts.check(&url, &[]),
+            vec![Alert {
+                kind: "secret",
+                detail: "token".to_string()
+            }]
+        );
+
+        // Hosts are remembered even when only other alerts are on.
+        let mut alerts = Alerts {
+            secrets: true,
+            ..Default::default()
+        };
+        assert!(kinds(&mut alerts, "https://b.example/").is_empty());
+        alerts.new_host = true;
+        assert!(kinds(&mut alerts, "https://b.example/").is_empty());
+    }
+
+    #[test]
+    fn test_run_exec() {
+        let path = std::env::temp_dir().join(format!("kurl-alert-{}.txt", std::process::id()));
+        let alerts = Alerts {
+            exec: Some(format!(
+                "printf '%s %s %s ' \"$KURL_ID\" \"$KURL_ALERTS\" \"$KURL_URL\" > '{0}'; cat >> '{0}'",
+                path.display()
+            )),
+            ..Default::default()
+        };
+        let url = Url::parse("https://a.example/").unwrap();
+        let raised = [Alert {
+            kind: "new-host",
+            detail: "first URL for a.example".to_string(),
+        }];
+        alerts.run_exec(&url, None, &raised);
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            " new-host https://a.example/ {\"url\":\"https://a.example/\",\"alerts\":[{\"alert\":\"new-host\",\"detail\":\"first URL for a.example\"}]}\n"
+        );
+        alerts.run_exec(&url, Some("7"), &raised);
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "7 new-host https://a.example/ {\"url\":\"https://a.example/\",\"id\":\"7\",\"alerts\":[{\"alert\":\"new-host\",\"detail\":\"first URL for a.example\"}]}\n"
+        );
+        std::fs::remove_file(&path).unwrap();
+    }
+}
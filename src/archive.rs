@@ -0,0 +1,288 @@
+use std::io::{self, IsTerminal, Write};
+use url::{Position, Url};
+
+use crate::json::Value;
+
+const ARCHIVE_HELP: &str = r#"kurl archive - Wayback Machine links for a URL
+
+USAGE:
+    kurl archive [OPTIONS] [<URL>...]
+    <URLs> | kurl archive [OPTIONS]
+
+OPTIONS:
+    -j, --json          Output as JSON instead of formatted text
+    -h, --help          Show this help message
+
+For each URL, prints the web.archive.org links to save a snapshot of it
+now, to open the latest snapshot, and to list every snapshot. A URL that
+is already a Wayback Machine link, like
+https://web.archive.org/web/20240101120000id_/https://example.com/, is read
+instead: the archived URL, the snapshot's timestamp and time, and any
+modifier after it (`id_` for the original bytes, `im_` for images, and so
+on) are reported, and the links are for the archived URL.
+
+EXAMPLES:
+    kurl archive https://example.com/pricing
+    kurl archive --json 'https://web.archive.org/web/20240101120000/https://example.com/'
+"#;
+
+const WAYBACK: &str = "https://web.archive.org";
+
+/// A Wayback Machine link, taken apart.
+#[derive(Debug, PartialEq)]
+pub struct Snapshot {
+    /// The archived URL.
+    pub original: Url,
+    /// The 1–14 digit `YYYYMMDDhhmmss` timestamp, if the link names one.
+    pub timestamp: Option<String>,
+    /// What follows the timestamp, like `id_` or `im_`.
+    pub modifier: Option<String>,
+}
+
+pub fn run(args: &[String]) {
+    let mut json_output = false;
+    let mut urls = Vec::new();
+    for arg in args {
+        match arg.as_str() {
+            "-h" | "--help" => {
+                println!("{}", ARCHIVE_HELP);
+                return;
+            }
+            "-j" | "--json" => json_output = true,
+            other if other.starts_with('-') => {
+                eprintln!("Unknown option for archive: {}", other);
+                std::process::exit(1);
+            }
+            other => urls.push(other),
+        }
+    }
+
+    let buffer;
+    if urls.is_empty() {
+        if io::stdin().is_terminal() {
+            eprintln!("Usage: kurl archive [--json] <URL>...");
+            std::process::exit(1);
+        }
+        buffer = crate::read_stdin();
+        urls = buffer
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .collect();
+    }
+
+    let mut out = io::stdout().lock();
+    let mut failed = false;
+    for (i, input) in urls.iter().enumerate() {
+        let info = Url::parse(input)
+            .map_err(|e| e.to_string())
+            .and_then(|url| describe(&url));
+        let info = match info {
+            Ok(info) => info,
+            Err(e) => {
+                eprintln!("Failed to read {}: {}", input, e);
+                failed = true;
+                continue;
+            }
+        };
+        let result = if json_output {
+            info.write(&mut out).and_then(|_| writeln!(out))
+        } else if i > 0 {
+            writeln!(out).and_then(|_| write_pretty(&mut out, &info))
+        } else {
+            write_pretty(&mut out, &info)
+        };
+        if result.is_err() {
+            return;
+        }
+    }
+    if failed {
+        std::process::exit(1);
+    }
+}
+
+/// Reads `url` as a Wayback Machine link, returning `None` for other URLs.
+pub fn snapshot(url: &Url) -> Result<Option<Snapshot>, String> {
+    let archive_host = matches!(
+        url.host_str(),
+        Some("web.archive.org" | "wayback.archive.org" | "archive.org" | "www.archive.org")
+    );
+    // Everything after `/web/`, including the archived URL's query and
+    // fragment, which are the link's own.
+    let Some(rest) = url[Position::BeforePath..]
+        .strip_prefix("/web/")
+        .filter(|_| archive_host)
+    else {
+        return Ok(None);
+    };
+    let (stamp, target) = rest.split_once('/').unwrap_or(("", rest));
+    let digits = stamp.bytes().take_while(u8::is_ascii_digit).count();
+    let (timestamp, modifier) = stamp.split_at(digits);
+    let modifier = modifier.trim_start_matches('*');
+    let is_stamp = digits <= 14
+        && modifier
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b == b'_')
+        && (digits > 0 || modifier.is_empty());
+    // `/web/URL` links to the latest snapshot, with no timestamp.
+    let (timestamp, modifier, target) = if is_stamp {
+        (timestamp, modifier, target)
+    } else {
+        ("", "", rest)
+    };
+    if target.is_empty() {
+        return Ok(None);
+    }
+    // The archive assumes http for targets without a scheme.
+    let error = |e: url::ParseError| format!("cannot read the archived URL {:?}: {}", target, e);
+    let original = match Url::parse(target) {
+        Ok(original) if !crate::looks_schemeless(&original) => original,
+        Ok(_) | Err(url::ParseError::RelativeUrlWithoutBase) => {
+            Url::parse(&format!("http://{}", target)).map_err(error)?
+        }
+        Err(e) => return Err(error(e)),
+    };
+    Ok(Some(Snapshot {
+        original,
+        timestamp: (!timestamp.is_empty()).then(|| timestamp.to_string()),
+        modifier: (!modifier.is_empty()).then(|| modifier.to_string()),
+    }))
+}
+
+/// The archived URL, snapshot details if `url` is a Wayback Machine link,
+/// and the Wayback links for the archived URL.
+fn describe(url: &Url) -> Result<Value, String> {
+    let mut fields = Vec::new();
+    let original = match snapshot(url)? {
+        Some(snapshot) => {
+            fields.push(("url", Value::from(snapshot.original.as_str())));
+            if let Some(timestamp) = &snapshot.timestamp {
+                fields.push(("timestamp", Value::from(timestamp.as_str())));
+                if let Some(time) = time(timestamp) {
+                    fields.push(("time", Value::from(time)));
+                }
+            }
+            if let Some(modifier) = snapshot.modifier {
+                fields.push(("modifier", Value::from(modifier)));
+            }
+            snapshot.original
+        }
+        None => {
+            fields.push(("url", Value::from(url.as_str())));
+            url.clone()
+        }
+    };
+    fields.push((
+        "save",
+        Value::from(format!("{}/save/{}", WAYBACK, original)),
+    ));
+    fields.push((
+        "latest",
+        Value::from(format!("{}/web/{}", WAYBACK, original)),
+    ));
+    fields.push((
+        "snapshots",
+        Value::from(format!("{}/web/*/{}", WAYBACK, original)),
+    ));
+    Ok(Value::Object(
+        fields
+            .into_iter()
+            .map(|(name, value)| (name.to_string(), value))
+            .collect(),
+    ))
+}
+
+/// The RFC 3339 time of a full 14-digit timestamp, which is in UTC.
+fn time(timestamp: &str) -> Option<String> {
+    if timestamp.len() != 14 {
+        return None;
+    }
+    let part = |range: std::ops::Range<usize>| &timestamp[range];
+    Some(format!(
+        "{}-{}-{}T{}:{}:{}Z",
+        part(0..4),
+        part(4..6),
+        part(6..8),
+        part(8..10),
+        part(10..12),
+        part(12..14)
+    ))
+}
+
+fn write_pretty<W: Write>(writer: &mut W, info: &Value) -> io::Result<()> {
+    let Value::Object(fields) = info else {
+        return Ok(());
+    };
+    for (name, value) in fields {
+        let tabs = if name.len() < 6 { "\t\t" } else { "\t" };
+        writeln!(writer, "  {}{}: {}", name, tabs, value)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read(url: &str) -> Option<Snapshot> {
+        snapshot(&Url::parse(url).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_snapshot() {
+        let found =
+            read("https://web.archive.org/web/20240101120000id_/https://example.com/a?b=1#c")
+                .unwrap();
+        assert_eq!(found.original.as_str(), "https://example.com/a?b=1#c");
+        assert_eq!(found.timestamp.as_deref(), Some("20240101120000"));
+        assert_eq!(found.modifier.as_deref(), Some("id_"));
+
+        let found = read("http://web.archive.org/web/2019/example.com").unwrap();
+        assert_eq!(found.original.as_str(), "http://example.com/");
+        assert_eq!(found.timestamp.as_deref(), Some("2019"));
+        assert_eq!(found.modifier, None);
+
+        let found = read("https://web.archive.org/web/*/https://example.com/*").unwrap();
+        assert_eq!(found.original.as_str(), "https://example.com/*");
+        assert_eq!(found.timestamp, None);
+
+        let found = read("https://web.archive.org/web/https://example.com/").unwrap();
+        assert_eq!(found.original.as_str(), "https://example.com/");
+        assert_eq!((found.timestamp, found.modifier), (None, None));
+
+        assert_eq!(
+            read("https://web.archive.org/save/https://example.com/"),
+            None
+        );
+        assert_eq!(read("https://web.archive.org/web/"), None);
+        assert_eq!(read("https://example.com/web/2020/https://a.com/"), None);
+        assert!(
+            snapshot(&Url::parse("https://web.archive.org/web/2020/https://[").unwrap()).is_err()
+        );
+    }
+
+    #[test]
+    fn test_describe() {
+        let mut output = Vec::new();
+        let info = describe(
+            &Url::parse("https://web.archive.org/web/20240101120000/https://example.com/").unwrap(),
+        )
+        .unwrap();
+        write_pretty(&mut output, &info).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "  url\t\t: https://example.com/\n  \
+             timestamp\t: 20240101120000\n  \
+             time\t\t: 2024-01-01T12:00:00Z\n  \
+             save\t\t: https://web.archive.org/save/https://example.com/\n  \
+             latest\t: https://web.archive.org/web/https://example.com/\n  \
+             snapshots\t: https://web.archive.org/web/*/https://example.com/\n"
+        );
+
+        let info = describe(&Url::parse("https://example.com/pricing").unwrap()).unwrap();
+        assert_eq!(
+            info.to_json(),
+            r#"{"url":"https://example.com/pricing","save":"https://web.archive.org/save/https://example.com/pricing","latest":"https://web.archive.org/web/https://example.com/pricing","snapshots":"https://web.archive.org/web/*/https://example.com/pricing"}"#
+        );
+    }
+}
@@ -0,0 +1,189 @@
+use crate::json::{self, Value};
+
+const ASSERT_HELP: &str = r#"kurl assert - check a URL's components against expected JSON
+
+USAGE:
+    kurl assert <URL> --expect <FILE>
+
+OPTIONS:
+    -e, --expect <FILE> JSON object of expected components ("-" for stdin)
+    -h, --help          Show this help message
+
+Only the fields present in the expected object are compared, so it can list
+as few components as the test cares about. A field set to null must be absent.
+Exits 0 when everything matches and 1 with a list of differences otherwise.
+
+EXAMPLES:
+    kurl assert "https://example.com:8080/?q=1" --expect expected.json
+    echo '{"host":"example.com","port":null}' | kurl assert "$URL" --expect -
+"#;
+
+pub fn run(args: &[String]) {
+    let mut url_arg = None;
+    let mut expect = None;
+
+    let mut rest = args.iter();
+    while let Some(arg) = rest.next() {
+        match arg.as_str() {
+            "-h" | "--help" => {
+                println!("{}", ASSERT_HELP);
+                return;
+            }
+            "-e" | "--expect" => expect = Some(crate::option_value(&mut rest, arg)),
+            other if url_arg.is_none() && !other.starts_with('-') => url_arg = Some(other),
+            other => {
+                eprintln!("Unknown option for assert: {}", other);
+                std::process::exit(1);
+            }
+        }
+    }
+
+    let (Some(url_arg), Some(expect)) = (url_arg, expect) else {
+        eprintln!("Usage: kurl assert <URL> --expect <FILE>");
+        std::process::exit(1);
+    };
+
+    let text = if expect == "-" {
+        crate::read_stdin()
+    } else {
+        std::fs::read_to_string(expect).unwrap_or_else(|e| {
+            eprintln!("Failed to read {}: {}", expect, e);
+            std::process::exit(1);
+        })
+    };
+    let expected = json::parse(&text).unwrap_or_else(|e| {
+        eprintln!("Failed to parse {}: {}", expect, e);
+        std::process::exit(1);
+    });
+    if !matches!(expected, Value::Object(_)) {
+        eprintln!("Expected components in {} must be a JSON object", expect);
+        std::process::exit(1);
+    }
+
+    let url = crate::parse_url_or_exit(url_arg);
+    let mismatches = compare(
+        &expected,
+        &crate::components(&url, crate::OUTPUT_VERSION, crate::query::Syntax::default()),
+    );
+    if mismatches.is_empty() {
+        return;
+    }
+
+    println!("{} does not match {}:", url, expect);
+    for mismatch in &mismatches {
+        println!("  {}", mismatch);
+    }
+    std::process::exit(1);
+}
+
+/// A field whose actual value differs from the expected one.
+#[derive(Debug, PartialEq)]
+pub struct Mismatch {
+    /// Dotted path to the field, such as `query.page`.
+    pub path: String,
+    pub expected: Value,
+    pub actual: Option<Value>,
+}
+
+impl std::fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let expected = match &self.expected {
+            Value::Null => "no value".to_string(),
+            value => value.to_json(),
+        };
+        let actual = match &self.actual {
+            Some(value) => value.to_json(),
+            None => "nothing".to_string(),
+        };
+        write!(f, "{}: expected {}, got {}", self.path, expected, actual)
+    }
+}
+
+/// Compares the fields named in `expected` against `actual`, descending into
+/// nested objects so that, for example, each query parameter is checked alone.
+pub fn compare(expected: &Value, actual: &Value) -> Vec<Mismatch> {
+    let mut mismatches = Vec::new();
+    compare_at(expected, actual, "", &mut mismatches);
+    mismatches
+}
+
+fn compare_at(expected: &Value, actual: &Value, prefix: &str, out: &mut Vec<Mismatch>) {
+    let Value::Object(fields) = expected else {
+        return;
+    };
+
+    for (key, expected) in fields {
+        let path = if prefix.is_empty() {
+            key.clone()
+        } else {
+            format!("{}.{}", prefix, key)
+        };
+
+        match (expected, actual.get(key)) {
+            (Value::Null, None) => {}
+            (Value::Object(_), Some(actual @ Value::Object(_))) => {
+                compare_at(expected, actual, &path, out)
+            }
+            (expected, Some(actual)) if expected == actual => {}
+            (expected, actual) => out.push(Mismatch {
+                path,
+                expected: expected.clone(),
+                actual: actual.cloned(),
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use url::Url;
+
+    fn compare_url(url: &str, expected: &str) -> Vec<String> {
+        let url = Url::parse(url).unwrap();
+        let expected = json::parse(expected).unwrap();
+        compare(
+            &expected,
+            &crate::components(&url, crate::OUTPUT_VERSION, crate::query::Syntax::default()),
+        )
+        .iter()
+        .map(ToString::to_string)
+        .collect()
+    }
+
+    #[test]
+    fn test_compare_matching_subset() {
+        let mismatches = compare_url(
+            "https://example.com:8080/a?q=1&page=2",
+            r#"{"host":"example.com","port":8080,"query":{"page":"2"},"fragment":null}"#,
+        );
+        assert!(mismatches.is_empty());
+    }
+
+    #[test]
+    fn test_compare_reports_each_difference() {
+        let mismatches = compare_url(
+            "https://www.example.com/a?page=3#top",
+            r#"{"host":"example.com","port":443,"query":{"page":"2","q":"x"},"fragment":null}"#,
+        );
+        assert_eq!(
+            mismatches,
+            vec![
+                r#"host: expected "example.com", got "www.example.com""#,
+                "port: expected 443, got nothing",
+                r#"query.page: expected "2", got "3""#,
+                r#"query.q: expected "x", got nothing"#,
+                r#"fragment: expected no value, got "top""#,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_compare_object_against_scalar() {
+        let mismatches = compare_url("https://example.com/", r#"{"query":{"a":"1"}}"#);
+        assert_eq!(
+            mismatches,
+            vec![r#"query: expected {"a":"1"}, got nothing"#]
+        );
+    }
+}
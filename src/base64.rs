@@ -0,0 +1,123 @@
+/// The two base64 alphabets of RFC 4648.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Alphabet {
+    /// `+` and `/`, as in MIME and most tokens.
+    Standard,
+    /// `-` and `_`, which URLs carry without escaping.
+    Url,
+}
+
+impl Alphabet {
+    pub fn name(self) -> &'static str {
+        match self {
+            Alphabet::Standard => "base64",
+            Alphabet::Url => "base64url",
+        }
+    }
+}
+
+/// Values shorter than this are too likely to be words or IDs that happen
+/// to decode.
+const MIN_LEN: usize = 8;
+
+/// Decodes `text` in whichever alphabet it uses, with or without padding,
+/// or `None` if it isn't canonical base64: mixed alphabets, misplaced
+/// padding, an impossible length, or stray bits in the last character.
+pub fn decode(text: &str) -> Option<(Vec<u8>, Alphabet)> {
+    let unpadded = text.trim_end_matches('=');
+    let padding = text.len() - unpadded.len();
+    if padding > 2 || (padding > 0 && !text.len().is_multiple_of(4)) || unpadded.len() % 4 == 1 {
+        return None;
+    }
+    let standard = unpadded.contains(['+', '/']);
+    let url = unpadded.contains(['-', '_']);
+    let alphabet = match (standard, url) {
+        (true, true) => return None,
+        (false, true) => Alphabet::Url,
+        _ => Alphabet::Standard,
+    };
+
+    let mut bytes = Vec::with_capacity(unpadded.len() * 3 / 4);
+    let mut bits = 0u32;
+    let mut count = 0;
+    for c in unpadded.bytes() {
+        let value = match c {
+            b'A'..=b'Z' => c - b'A',
+            b'a'..=b'z' => c - b'a' + 26,
+            b'0'..=b'9' => c - b'0' + 52,
+            b'+' | b'-' => 62,
+            b'/' | b'_' => 63,
+            _ => return None,
+        };
+        bits = bits << 6 | u32::from(value);
+        count += 6;
+        if count >= 8 {
+            count -= 8;
+            bytes.push((bits >> count) as u8);
+            bits &= (1 << count) - 1;
+        }
+    }
+    (bits == 0).then_some((bytes, alphabet))
+}
+
+/// The text `value` decodes to, if it looks like base64: long enough, and
+/// decoding to UTF-8 with no control characters but line breaks and tabs.
+/// Spaces are read as `+`, since form decoding turns an unescaped `+` in a
+/// query into a space.
+pub fn decode_text(value: &str) -> Option<(String, Alphabet)> {
+    if value.len() < MIN_LEN {
+        return None;
+    }
+    let (bytes, alphabet) = decode(&value.replace(' ', "+"))?;
+    let text = String::from_utf8(bytes).ok()?;
+    let printable = text
+        .chars()
+        .all(|c| !c.is_control() || matches!(c, '\n' | '\r' | '\t'));
+    printable.then_some((text, alphabet))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode() {
+        for (encoded, decoded) in [
+            ("", ""),
+            ("Zg==", "f"),
+            ("Zm8=", "fo"),
+            ("Zm9v", "foo"),
+            ("Zm9vYg", "foob"),
+            ("Zm9vYmE=", "fooba"),
+            ("Zm9vYmFy", "foobar"),
+        ] {
+            let (bytes, _) = decode(encoded).unwrap();
+            assert_eq!(bytes, decoded.as_bytes(), "{}", encoded);
+        }
+        assert_eq!(
+            decode("-_-_").unwrap(),
+            (vec![0xfb, 0xff, 0xbf], Alphabet::Url)
+        );
+        assert_eq!(decode("+/+/").unwrap().1, Alphabet::Standard);
+        for bad in ["+/-_", "Zg=", "Zg===", "Z", "Zm9v!", "Zh==", "Zm=v"] {
+            assert_eq!(decode(bad), None, "{}", bad);
+        }
+    }
+
+    #[test]
+    fn test_decode_text() {
+        assert_eq!(
+            decode_text("aHR0cHM6Ly9leGFtcGxlLmNvbS8_YT0x"),
+            Some(("https://example.com/?a=1".to_string(), Alphabet::Url))
+        );
+        // `+` read back from a query's space.
+        assert_eq!(decode_text("eyJhIjoiPj4 In0=").unwrap().0, r#"{"a":">>>"}"#);
+        assert_eq!(
+            decode_text("Zm9vYmFy"),
+            Some(("foobar".to_string(), Alphabet::Standard))
+        );
+        assert_eq!(decode_text("Zm9v"), None);
+        assert_eq!(decode_text("deadbeefcafe"), None);
+        assert_eq!(decode_text("AAECAwQFBgc="), None);
+    }
+}
@@ -0,0 +1,142 @@
+use std::io::{self, Write};
+use url::Url;
+
+use crate::json::Value;
+use crate::{OutputOptions, ParseOptions, Records, html};
+
+/// A bookmark from a Netscape-format export, as written by Chrome and Firefox.
+#[derive(Debug, PartialEq)]
+pub struct Bookmark {
+    pub href: String,
+    pub title: String,
+    /// Enclosing folder names, outermost first.
+    pub folders: Vec<String>,
+}
+
+/// Prints every bookmark in the export, with its title and folder path,
+/// rewritten as `parsing` says.
+pub fn run(export: &str, parsing: &ParseOptions, output: &OutputOptions) {
+    let mut out = io::stdout().lock();
+    let _ = write_bookmarks(&mut out, export, parsing, output);
+}
+
+fn write_bookmarks<W: Write>(
+    writer: &mut W,
+    export: &str,
+    parsing: &ParseOptions,
+    output: &OutputOptions,
+) -> io::Result<()> {
+    let mut records = Records::new(writer, output);
+    for bookmark in bookmarks(export) {
+        let Ok(url) = Url::parse(&bookmark.href) else {
+            eprintln!("Skipping bookmark with invalid URL: {}", bookmark.href);
+            continue;
+        };
+
+        let mut extra = vec![
+            ("title", Value::from(bookmark.title)),
+            ("folder", Value::from(bookmark.folders.join("/"))),
+        ];
+        let Some(url) = parsing.prepare(url, &mut extra) else {
+            continue;
+        };
+        records.write(&url, &extra)?;
+    }
+    Ok(())
+}
+
+/// Walks the nested `<DL>` lists of a bookmark export. Each `<H3>` names the
+/// folder whose `<DL>` follows it.
+pub fn bookmarks(export: &str) -> Vec<Bookmark> {
+    let mut found = Vec::new();
+    let mut folders: Vec<Option<String>> = Vec::new();
+    let mut pending_folder = None;
+
+    for tag in html::tags(export) {
+        if tag.is("h3") {
+            pending_folder = Some(tag.text_after(export).into_owned());
+        } else if tag.is("dl") {
+            folders.push(pending_folder.take());
+        } else if tag.closes("dl") {
+            folders.pop();
+        } else if tag.is("a") {
+            let Some(href) = tag.attr("href") else {
+                continue;
+            };
+            found.push(Bookmark {
+                href: html::decode_entities(href.value).trim().to_string(),
+                title: tag.text_after(export).into_owned(),
+                folders: folders.iter().flatten().cloned().collect(),
+            });
+        }
+    }
+
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXPORT: &str = r#"<!DOCTYPE NETSCAPE-Bookmark-file-1>
+<META HTTP-EQUIV="Content-Type" CONTENT="text/html; charset=UTF-8">
+<TITLE>Bookmarks</TITLE>
+<H1>Bookmarks</H1>
+<DL><p>
+    <DT><H3 ADD_DATE="1700000000">Bookmarks bar</H3>
+    <DL><p>
+        <DT><A HREF="https://docs.rs/url" ADD_DATE="1700000001">url - Rust</A>
+        <DT><H3>Work &amp; Play</H3>
+        <DL><p>
+            <DT><A HREF="https://example.com/?a=1&amp;b=2">Example</A>
+        </DL><p>
+    </DL><p>
+    <DT><A HREF="https://top.example.org/">Top level</A>
+</DL><p>
+"#;
+
+    #[test]
+    fn test_bookmarks_folders_and_titles() {
+        let found = bookmarks(EXPORT);
+        assert_eq!(found.len(), 3);
+        assert_eq!(found[0].title, "url - Rust");
+        assert_eq!(found[0].folders, vec!["Bookmarks bar"]);
+        assert_eq!(found[1].href, "https://example.com/?a=1&b=2");
+        assert_eq!(found[1].folders, vec!["Bookmarks bar", "Work & Play"]);
+        assert_eq!(found[2].title, "Top level");
+        assert!(found[2].folders.is_empty());
+    }
+
+    #[test]
+    fn test_write_bookmarks_json() {
+        let options = OutputOptions {
+            json_output: true,
+            ..Default::default()
+        };
+        let mut output = Vec::new();
+        write_bookmarks(&mut output, EXPORT, &ParseOptions::default(), &options).unwrap();
+
+        let output_str = String::from_utf8(output).unwrap();
+        let lines: Vec<&str> = output_str.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[1].ends_with(r#","title":"Example","folder":"Bookmarks bar/Work & Play"}"#));
+    }
+
+    #[test]
+    fn test_write_bookmarks_allow_file() {
+        let parsing = ParseOptions {
+            allow: Some(crate::hostmap::HostList::parse("example.com\n").unwrap()),
+            ..Default::default()
+        };
+        let options = OutputOptions {
+            get: vec!["url".to_string()],
+            ..Default::default()
+        };
+        let mut output = Vec::new();
+        write_bookmarks(&mut output, EXPORT, &parsing, &options).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "https://example.com/?a=1&b=2\n"
+        );
+    }
+}
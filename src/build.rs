@@ -0,0 +1,421 @@
+use std::io::{self, IsTerminal, Write};
+use url::Url;
+
+use crate::json::{self, Value};
+use crate::{OutputOptions, Records};
+
+const BUILD_HELP: &str = r#"kurl build - make a URL from its components
+
+USAGE:
+    kurl build [OPTIONS] --host <HOST>
+    <JSON> | kurl build --from-json [OPTIONS]
+
+OPTIONS:
+        --scheme <NAME>     Scheme of the URL (default: https)
+        --user <NAME>       Username
+        --password <TEXT>   Password
+        --host <HOST>       Host name or IP address
+        --port <N>          Port, left out when it is the scheme's default
+        --path <PATH>       Path; a missing leading `/` is added
+        --query <KEY=VALUE> Add a query parameter; repeat for several, in order
+        --fragment <TEXT>   Fragment, without the `#`
+        --from-json         Build a URL from each JSON object on stdin, in the
+                            structure `kurl --json` prints, instead
+    -j, --json              Print the URL's components as JSON
+        --get <FIELD>       Print only FIELD of the URL, as with `kurl --get`
+    -h, --help              Show this help message
+
+Each component is percent-encoded as its place in the URL requires, so
+values can be given as they are: a space in the path becomes %20, and a `&`
+or `=` in a query value is encoded rather than starting another parameter.
+Query parameters are encoded like HTML forms, with spaces as `+`, and
+`--query KEY` without `=` adds a bare key. Hosts are checked, and
+international ones converted to punycode, as when parsing.
+
+`--from-json` reads the objects `kurl --json` prints, one after another or
+pretty-printed, and uses their scheme, user, password, host, port, path,
+query, and fragment fields, ignoring the rest, so URLs can be taken apart,
+changed with jq, and put back together. A query value may be an array, for a
+repeated key, or null, for a bare key. Objects that don't make a URL are
+reported and skipped.
+
+EXAMPLES:
+    kurl build --host example.com --path /a/b --query k=v --query x=y --fragment top
+    kurl build --scheme ws --host localhost --port 8080 --path socket
+    kurl build --host example.com --query 'q=R&D notes' --get query.q
+    kurl --json "$URL" | jq '.query.page = "2"' | kurl build --from-json
+"#;
+
+/// The components of a URL to build, as given; `None` leaves one out.
+#[derive(Debug, Default, PartialEq)]
+pub struct Parts {
+    pub scheme: Option<String>,
+    pub user: Option<String>,
+    pub password: Option<String>,
+    pub host: Option<String>,
+    pub port: Option<u16>,
+    pub path: Option<String>,
+    /// Query parameters in order; keys without a value have `None`.
+    pub query: Vec<(String, Option<String>)>,
+    pub fragment: Option<String>,
+}
+
+pub fn run(args: &[String]) {
+    let mut parts = Parts::default();
+    let mut from_json = false;
+    let mut output = OutputOptions::default();
+
+    let mut rest = args.iter();
+    while let Some(arg) = rest.next() {
+        match arg.as_str() {
+            "-h" | "--help" => {
+                println!("{}", BUILD_HELP);
+                return;
+            }
+            flag @ ("--scheme" | "--user" | "--password" | "--host" | "--path" | "--fragment") => {
+                let value = Some(crate::option_value(&mut rest, flag).to_string());
+                match flag {
+                    "--scheme" => parts.scheme = value,
+                    "--user" => parts.user = value,
+                    "--password" => parts.password = value,
+                    "--host" => parts.host = value,
+                    "--path" => parts.path = value,
+                    _ => parts.fragment = value,
+                }
+            }
+            "--port" => {
+                let value = crate::option_value(&mut rest, arg);
+                parts.port = Some(value.parse().unwrap_or_else(|_| {
+                    eprintln!("Expected a port number for --port, got {}", value);
+                    std::process::exit(1);
+                }));
+            }
+            "--from-json" => from_json = true,
+            "--query" => {
+                let pair = crate::option_value(&mut rest, arg);
+                parts.query.push(match pair.split_once('=') {
+                    Some((key, value)) => (key.to_string(), Some(value.to_string())),
+                    None => (pair.to_string(), None),
+                });
+            }
+            other if output.accept(other, &mut rest) => {}
+            other => {
+                eprintln!("Unknown option for build: {}", other);
+                std::process::exit(1);
+            }
+        }
+    }
+
+    let mut out = io::stdout().lock();
+    if !from_json {
+        let url = build(&parts).unwrap_or_else(|e| {
+            eprintln!("Failed to build URL: {}", e);
+            std::process::exit(1);
+        });
+        if let Err(e) = write_built(&mut out, &[Ok(url)], &output) {
+            eprintln!("Failed to write output: {}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    if parts != Parts::default() {
+        eprintln!("--from-json takes every component from its input; change them with jq first");
+        std::process::exit(1);
+    }
+    if io::stdin().is_terminal() {
+        eprintln!("Usage: <JSON> | kurl build --from-json");
+        std::process::exit(1);
+    }
+    let values = json::parse_stream(&crate::read_stdin()).unwrap_or_else(|e| {
+        eprintln!("Failed to parse JSON: {}", e);
+        std::process::exit(1);
+    });
+    let urls: Vec<Result<Url, String>> = values
+        .iter()
+        .map(|value| from_value(value).and_then(|parts| build(&parts)))
+        .collect();
+    match write_built(&mut out, &urls, &output) {
+        Ok(true) => {}
+        Ok(false) => std::process::exit(1),
+        Err(e) => {
+            eprintln!("Failed to write output: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Writes each built URL, or its components with `--json` or `--get`, and
+/// reports the ones that failed, returning whether all of them were built.
+fn write_built<W: Write>(
+    writer: &mut W,
+    urls: &[Result<Url, String>],
+    output: &OutputOptions,
+) -> io::Result<bool> {
+    let mut records = Records::new(writer, output);
+    let mut built_all = true;
+    for (i, url) in urls.iter().enumerate() {
+        match url {
+            Ok(url) if output.json_output || !output.get.is_empty() => records.write(url, &[])?,
+            Ok(url) => writeln!(records.writer, "{}", url)?,
+            Err(e) => {
+                eprintln!("Failed to build URL {}: {}", i + 1, e);
+                built_all = false;
+            }
+        }
+    }
+    Ok(built_all)
+}
+
+/// Reads the components of a URL from an object in the structure that
+/// `kurl --json` prints.
+pub fn from_value(value: &Value) -> Result<Parts, String> {
+    let Value::Object(fields) = value else {
+        return Err(format!("expected an object, got {}", value.to_json()));
+    };
+    let text = |name: &str| match value.get(name) {
+        None | Some(Value::Null) => Ok(None),
+        Some(Value::String(text)) => Ok(Some(text.clone())),
+        Some(other) => Err(format!(
+            "{} must be a string, got {}",
+            name,
+            other.to_json()
+        )),
+    };
+    let port = match value.get("port") {
+        None | Some(Value::Null) => None,
+        Some(Value::Number(port) | Value::String(port)) => Some(
+            port.parse()
+                .map_err(|_| format!("invalid port: {}", port))?,
+        ),
+        Some(other) => return Err(format!("port must be a number, got {}", other.to_json())),
+    };
+    let mut query = Vec::new();
+    match value.get("query") {
+        None | Some(Value::Null) => {}
+        Some(Value::Object(pairs)) => {
+            for (key, value) in pairs {
+                let values = match value {
+                    Value::Array(values) => values.as_slice(),
+                    value => std::slice::from_ref(value),
+                };
+                for value in values {
+                    query.push((key.clone(), query_value(key, value)?));
+                }
+            }
+        }
+        Some(other) => {
+            return Err(format!("query must be an object, got {}", other.to_json()));
+        }
+    }
+    if fields.is_empty() {
+        return Err("empty object".to_string());
+    }
+    Ok(Parts {
+        scheme: text("scheme")?,
+        user: text("user")?,
+        password: text("password")?,
+        host: text("host")?,
+        port,
+        path: text("path")?,
+        query,
+        fragment: text("fragment")?,
+    })
+}
+
+/// The text of one query value; null stands for a key without `=`.
+fn query_value(key: &str, value: &Value) -> Result<Option<String>, String> {
+    match value {
+        Value::Null => Ok(None),
+        Value::String(text) | Value::Number(text) => Ok(Some(text.clone())),
+        Value::Bool(flag) => Ok(Some(flag.to_string())),
+        other => Err(format!(
+            "query.{} must be a string, number, or null, got {}",
+            key,
+            other.to_json()
+        )),
+    }
+}
+
+/// Builds a URL from `parts` with the URL crate's setters, which encode each
+/// component for its place.
+pub fn build(parts: &Parts) -> Result<Url, String> {
+    let scheme = parts.scheme.as_deref().unwrap_or("https");
+    let mut url = match Url::parse(&format!("{}://x", scheme)) {
+        Ok(url) if !scheme.contains([':', '/']) => url,
+        _ => return Err(format!("invalid scheme: {}", scheme)),
+    };
+    match parts.host.as_deref() {
+        Some(host) => url
+            .set_host(Some(host))
+            .map_err(|e| format!("invalid host {:?}: {}", host, e))?,
+        // Without a host, `mailto:` and the like have a plain path, not `//`.
+        None => {
+            url = Url::parse(&format!("{}:", scheme))
+                .map_err(|_| format!("{} URLs need a host", scheme))?
+        }
+    }
+
+    let needs_host = |component: &str| format!("a URL without a host can't have a {}", component);
+    if let Some(user) = &parts.user {
+        url.set_username(user).map_err(|()| needs_host("user"))?;
+    }
+    if let Some(password) = &parts.password {
+        url.set_password(Some(password))
+            .map_err(|()| needs_host("password"))?;
+    }
+    if let Some(port) = parts.port {
+        url.set_port(Some(port)).map_err(|()| needs_host("port"))?;
+    }
+    if let Some(path) = &parts.path {
+        url.set_path(path);
+    }
+    if !parts.query.is_empty() {
+        let mut pairs = url.query_pairs_mut();
+        for (key, value) in &parts.query {
+            match value {
+                Some(value) => pairs.append_pair(key, value),
+                None => pairs.append_key_only(key),
+            };
+        }
+    }
+    if let Some(fragment) = &parts.fragment {
+        url.set_fragment(Some(fragment));
+    }
+    Ok(url)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn built(parts: Parts) -> Result<String, String> {
+        build(&parts).map(String::from)
+    }
+
+    fn some(text: &str) -> Option<String> {
+        Some(text.to_string())
+    }
+
+    #[test]
+    fn test_build() {
+        assert_eq!(
+            built(Parts {
+                host: some("Example.COM"),
+                path: some("/a b/c"),
+                query: vec![
+                    ("k".to_string(), some("v")),
+                    ("q".to_string(), some("R&D = 100%")),
+                    ("flag".to_string(), None),
+                ],
+                fragment: some("top"),
+                ..Default::default()
+            }),
+            Ok("https://example.com/a%20b/c?k=v&q=R%26D+%3D+100%25&flag#top".to_string())
+        );
+        assert_eq!(
+            built(Parts {
+                scheme: some("ws"),
+                user: some("bot"),
+                password: some("p@ss"),
+                host: some("localhost"),
+                port: Some(8080),
+                path: some("socket"),
+                ..Default::default()
+            }),
+            Ok("ws://bot:p%40ss@localhost:8080/socket".to_string())
+        );
+        assert_eq!(
+            built(Parts {
+                host: some("example.com"),
+                port: Some(443),
+                ..Default::default()
+            }),
+            Ok("https://example.com/".to_string())
+        );
+        assert_eq!(
+            built(Parts {
+                scheme: some("file"),
+                path: some("/etc/hosts"),
+                ..Default::default()
+            }),
+            Ok("file:///etc/hosts".to_string())
+        );
+        assert_eq!(
+            built(Parts {
+                scheme: some("mailto"),
+                path: some("a@example.com"),
+                query: vec![("subject".to_string(), some("Hi there"))],
+                ..Default::default()
+            }),
+            Ok("mailto:a@example.com?subject=Hi+there".to_string())
+        );
+    }
+
+    #[test]
+    fn test_from_value() {
+        let record = json::parse(
+            r#"{"scheme":"https","user":"u","host":"example.com","port":8443,"path":"/a%20b/c","fragment":"top","site":"https://stale.example","query":{"x":["1","2"],"y":"R&D","n":3,"flag":null}}"#,
+        )
+        .unwrap();
+        let parts = from_value(&record).unwrap();
+        assert_eq!(parts.port, Some(8443));
+        assert_eq!(
+            build(&parts).unwrap().as_str(),
+            "https://u@example.com:8443/a%20b/c?x=1&x=2&y=R%26D&n=3&flag#top"
+        );
+
+        // What kurl prints comes back as the same URL.
+        let url = Url::parse("https://example.com/docs/caf%C3%A9?q=a+b&q=%2B#s").unwrap();
+        let record = crate::record(
+            &url,
+            Vec::new(),
+            crate::OUTPUT_VERSION,
+            crate::query::Syntax::default(),
+        );
+        assert_eq!(build(&from_value(&record).unwrap()).unwrap(), url);
+
+        for bad in [
+            "[]",
+            "{}",
+            r#"{"host":1}"#,
+            r#"{"host":"a.com","port":"x"}"#,
+            r#"{"host":"a.com","query":"a=1"}"#,
+            r#"{"host":"a.com","query":{"a":{}}}"#,
+        ] {
+            assert!(from_value(&json::parse(bad).unwrap()).is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn test_build_errors() {
+        assert_eq!(
+            built(Parts::default()),
+            Err("https URLs need a host".to_string())
+        );
+        assert!(
+            built(Parts {
+                host: some("exa mple.com"),
+                ..Default::default()
+            })
+            .unwrap_err()
+            .starts_with("invalid host")
+        );
+        assert!(
+            built(Parts {
+                scheme: some("https:"),
+                host: some("example.com"),
+                ..Default::default()
+            })
+            .is_err()
+        );
+        assert!(
+            built(Parts {
+                scheme: some("file"),
+                port: Some(80),
+                ..Default::default()
+            })
+            .is_err()
+        );
+    }
+}
@@ -0,0 +1,333 @@
+use std::io::{self, IsTerminal, Write};
+
+const CANONICALIZE_HELP: &str = r#"kurl canonicalize - canonicalize URLs for threat list lookups
+
+USAGE:
+    kurl canonicalize --safe-browsing [<URL>...]
+    <URLs> | kurl canonicalize --safe-browsing
+
+OPTIONS:
+        --safe-browsing     Use the Google Safe Browsing algorithm
+    -h, --help              Show this help message
+
+Safe Browsing lists are matched against URLs canonicalized its own way,
+which differs from the WHATWG parsing the rest of kurl uses, so a URL has
+to go through exactly these steps before it is hashed or compared:
+
+    1. Remove tabs, carriage returns, and line feeds, surrounding
+       whitespace, and the fragment, and assume http:// without a scheme.
+    2. Percent-unescape the URL over and over until no escapes are left.
+    3. Drop any credentials and the port. Remove leading
+       and trailing dots from the host, collapse runs of dots, read it as an
+       IPv4 address if it is one (in any of the forms inet_aton accepts,
+       like 3279880203 or 0xc3.0177.0.11), and lowercase it.
+    4. Resolve `.` and `..` segments and collapse runs of slashes in the
+       path, but not the query.
+    5. Percent-escape control characters, spaces, non-ASCII bytes, `#`, and
+       `%`, with uppercase hex.
+
+The result isn't always a valid URL, as in http://%01%80.com/, so it is
+printed as text. For RFC 3986 normalization, use `kurl normalize`.
+
+EXAMPLES:
+    kurl canonicalize --safe-browsing 'http://www.GOOgle.com.../a/./b/../c#top'
+    kurl canonicalize --safe-browsing http://3279880203/blah
+    cat urls.txt | kurl canonicalize --safe-browsing
+"#;
+
+pub fn run(args: &[String]) {
+    let mut safe_browsing = false;
+    let mut urls = Vec::new();
+
+    for arg in args {
+        match arg.as_str() {
+            "-h" | "--help" => {
+                println!("{}", CANONICALIZE_HELP);
+                return;
+            }
+            "--safe-browsing" => safe_browsing = true,
+            other if other.starts_with("--") => {
+                eprintln!("Unknown option for canonicalize: {}", other);
+                std::process::exit(1);
+            }
+            other => urls.push(other),
+        }
+    }
+
+    let usage = "Usage: kurl canonicalize --safe-browsing [<URL>...]";
+    if !safe_browsing {
+        eprintln!("{}", usage);
+        std::process::exit(1);
+    }
+
+    let buffer;
+    if urls.is_empty() {
+        if io::stdin().is_terminal() {
+            eprintln!("{}", usage);
+            std::process::exit(1);
+        }
+        buffer = crate::read_stdin();
+        urls = buffer
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .collect();
+    }
+
+    let mut out = io::stdout().lock();
+    for url in urls {
+        if writeln!(out, "{}", safe_browsing_url(url)).is_err() {
+            return;
+        }
+    }
+}
+
+/// Canonicalizes `input` as the Safe Browsing API requires.
+pub fn safe_browsing_url(input: &str) -> String {
+    let mut bytes: Vec<u8> = input
+        .trim_matches(|c: char| c.is_ascii_whitespace())
+        .bytes()
+        .filter(|b| !matches!(b, b'\t' | b'\r' | b'\n'))
+        .collect();
+    if let Some(hash) = bytes.iter().position(|&b| b == b'#') {
+        bytes.truncate(hash);
+    }
+    let bytes = unescape_fully(bytes);
+
+    let (scheme, rest) = match find(&bytes, b"://") {
+        Some(i) => (bytes[..i].to_ascii_lowercase(), &bytes[i + 3..]),
+        None => (b"http".to_vec(), &bytes[..]),
+    };
+    let authority_end = rest
+        .iter()
+        .position(|&b| b == b'/' || b == b'?')
+        .unwrap_or(rest.len());
+    let (authority, rest) = rest.split_at(authority_end);
+    let (path, query) = match rest.iter().position(|&b| b == b'?') {
+        Some(i) => (&rest[..i], Some(&rest[i..])),
+        None => (rest, None),
+    };
+
+    let authority = match authority.iter().rposition(|&b| b == b'@') {
+        Some(at) => &authority[at + 1..],
+        None => authority,
+    };
+    // Lists hold hosts without ports, so any port goes.
+    let host = match authority.iter().rposition(|&b| b == b':') {
+        Some(colon) if authority[colon + 1..].iter().all(u8::is_ascii_digit) => &authority[..colon],
+        _ => authority,
+    };
+
+    let mut canonical = scheme;
+    canonical.extend_from_slice(b"://");
+    canonical.extend(host_of(host));
+    canonical.extend(path_of(path));
+    if let Some(query) = query {
+        canonical.extend_from_slice(query);
+    }
+    escape(&canonical)
+}
+
+/// Percent-unescapes `bytes` until no `%XX` escapes are left, so that
+/// `%2525` ends up as `%`.
+fn unescape_fully(mut bytes: Vec<u8>) -> Vec<u8> {
+    loop {
+        let mut unescaped = Vec::with_capacity(bytes.len());
+        let mut i = 0;
+        while i < bytes.len() {
+            let hex = |offset: usize| {
+                bytes
+                    .get(i + offset)
+                    .and_then(|&b| (b as char).to_digit(16))
+            };
+            match (bytes[i], hex(1), hex(2)) {
+                (b'%', Some(high), Some(low)) => {
+                    unescaped.push((high * 16 + low) as u8);
+                    i += 3;
+                }
+                (b, _, _) => {
+                    unescaped.push(b);
+                    i += 1;
+                }
+            }
+        }
+        if unescaped.len() == bytes.len() {
+            return bytes;
+        }
+        bytes = unescaped;
+    }
+}
+
+/// The canonical host: dots trimmed and collapsed, IPv4 addresses in
+/// dotted decimal, and lowercase.
+fn host_of(host: &[u8]) -> Vec<u8> {
+    let labels: Vec<&[u8]> = host
+        .split(|&b| b == b'.')
+        .filter(|label| !label.is_empty())
+        .collect();
+    let host = labels.join(&b'.');
+    if let Some(address) = std::str::from_utf8(&host).ok().and_then(ipv4) {
+        return address.to_string().into_bytes();
+    }
+    host.to_ascii_lowercase()
+}
+
+/// Reads `host` as an IPv4 address the way inet_aton does: up to four
+/// parts in decimal, octal (with a leading 0), or hex (with 0x), the last
+/// of which fills the remaining bytes.
+fn ipv4(host: &str) -> Option<std::net::Ipv4Addr> {
+    let parts: Vec<&str> = host.split('.').collect();
+    if parts.len() > 4 {
+        return None;
+    }
+    let numbers = parts
+        .iter()
+        .map(|part| {
+            let (digits, radix) =
+                if let Some(hex) = part.strip_prefix("0x").or_else(|| part.strip_prefix("0X")) {
+                    (hex, 16)
+                } else if part.len() > 1 && part.starts_with('0') {
+                    (&part[1..], 8)
+                } else {
+                    (*part, 10)
+                };
+            match digits {
+                // inet_aton reads a bare `0x` as zero.
+                "" if radix == 16 => Some(0),
+                // Unlike inet_aton, from_str_radix takes a sign.
+                _ if digits.starts_with('+') => None,
+                _ => u32::from_str_radix(digits, radix).ok(),
+            }
+        })
+        .collect::<Option<Vec<u32>>>()?;
+    let (last, leading) = numbers.split_last()?;
+    if leading.iter().any(|&n| n > 255) {
+        return None;
+    }
+    let last_bits = 8 * (4 - leading.len() as u32);
+    if last_bits < 32 && *last >> last_bits != 0 {
+        return None;
+    }
+    let address = leading
+        .iter()
+        .enumerate()
+        .fold(*last, |address, (i, &n)| address | n << (24 - 8 * i as u32));
+    Some(std::net::Ipv4Addr::from(address))
+}
+
+/// The canonical path: `.` and `..` segments resolved and runs of slashes
+/// collapsed, keeping a trailing slash. An empty path is `/`.
+fn path_of(path: &[u8]) -> Vec<u8> {
+    let mut segments: Vec<&[u8]> = Vec::new();
+    let mut trailing_slash = false;
+    for segment in path.split(|&b| b == b'/') {
+        trailing_slash = matches!(segment, b"" | b"." | b"..");
+        match segment {
+            b"" | b"." => {}
+            b".." => {
+                segments.pop();
+            }
+            segment => segments.push(segment),
+        }
+    }
+    let mut canonical = b"/".to_vec();
+    canonical.extend(segments.join(&b'/'));
+    if trailing_slash && !segments.is_empty() {
+        canonical.push(b'/');
+    }
+    canonical
+}
+
+/// Percent-escapes bytes at or below space, at or above DEL, `#`, and `%`.
+fn escape(bytes: &[u8]) -> String {
+    let mut escaped = String::with_capacity(bytes.len());
+    for &b in bytes {
+        if b <= b' ' || b >= 0x7f || b == b'#' || b == b'%' {
+            escaped.push_str(&format!("%{:02X}", b));
+        } else {
+            escaped.push(b as char);
+        }
+    }
+    escaped
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_safe_browsing_url() {
+        // The examples from the Safe Browsing API documentation.
+        for (input, expected) in [
+            ("http://host/%25%32%35", "http://host/%25"),
+            ("http://host/%25%32%35%25%32%35", "http://host/%25%25"),
+            ("http://host/%2525252525252525", "http://host/%25"),
+            ("http://host/asdf%25%32%35asd", "http://host/asdf%25asd"),
+            (
+                "http://host/%%%25%32%35asd%%",
+                "http://host/%25%25%25asd%25%25",
+            ),
+            ("http://www.google.com/", "http://www.google.com/"),
+            (
+                "http://%31%36%38%2e%31%38%38%2e%39%39%2e%32%36/%2E%73%65%63%75%72%65/%77%77%77%2E%65%62%61%79%2E%63%6F%6D/",
+                "http://168.188.99.26/.secure/www.ebay.com/",
+            ),
+            (
+                "http://host%23.com/%257Ea%2521b%2540c%2523d%2524e%25f%255E00%252611%252A22%252833%252944_55%252B",
+                "http://host%23.com/~a!b@c%23d$e%25f^00&11*22(33)44_55+",
+            ),
+            ("http://3279880203/blah", "http://195.127.0.11/blah"),
+            ("http://www.google.com/blah/..", "http://www.google.com/"),
+            ("www.google.com/", "http://www.google.com/"),
+            ("www.google.com", "http://www.google.com/"),
+            ("http://www.evil.com/blah#frag", "http://www.evil.com/blah"),
+            ("http://www.GOOgle.com/", "http://www.google.com/"),
+            ("http://www.google.com.../", "http://www.google.com/"),
+            (
+                "http://www.google.com/foo\tbar\rbaz\n2",
+                "http://www.google.com/foobarbaz2",
+            ),
+            ("http://www.google.com/q?", "http://www.google.com/q?"),
+            ("http://www.google.com/q?r?s", "http://www.google.com/q?r?s"),
+            ("http://evil.com/foo#bar#baz", "http://evil.com/foo"),
+            ("http://evil.com/foo;", "http://evil.com/foo;"),
+            ("http://evil.com/foo?bar;", "http://evil.com/foo?bar;"),
+            ("http://\u{1}\u{80}.com/", "http://%01%C2%80.com/"),
+            ("http://notrailingslash.com", "http://notrailingslash.com/"),
+            ("http://www.gotaword.com:80/", "http://www.gotaword.com/"),
+            ("http://www.gotaport.com:1234/", "http://www.gotaport.com/"),
+            ("  http://www.google.com/  ", "http://www.google.com/"),
+            ("http:// leadingspace.com/", "http://%20leadingspace.com/"),
+            ("%20leadingspace.com/", "http://%20leadingspace.com/"),
+            ("https://www.securesite.com/", "https://www.securesite.com/"),
+            ("http://host.com/ab%23cd", "http://host.com/ab%23cd"),
+            (
+                "http://host.com//twoslashes?more//slashes",
+                "http://host.com/twoslashes?more//slashes",
+            ),
+        ] {
+            assert_eq!(safe_browsing_url(input), expected, "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_safe_browsing_authority() {
+        assert_eq!(
+            safe_browsing_url("HTTP://user:pw@Example.com:8080/a/./b/../c"),
+            "http://example.com/a/c"
+        );
+        assert_eq!(
+            safe_browsing_url("http://0xc3.0177.0.11:/"),
+            "http://195.127.0.11/"
+        );
+        assert_eq!(safe_browsing_url("http://10.1/"), "http://10.0.0.1/");
+        assert_eq!(safe_browsing_url("http://1.2.3.256/"), "http://1.2.3.256/");
+        assert_eq!(safe_browsing_url("http://08.1.1.1/"), "http://08.1.1.1/");
+        assert_eq!(safe_browsing_url("http://+1/"), "http://+1/");
+    }
+}
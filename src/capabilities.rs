@@ -0,0 +1,174 @@
+use std::io::{self, Write};
+use url::Url;
+
+use crate::json::Value;
+
+const CAPABILITIES_HELP: &str = r#"kurl capabilities - describe what this build of kurl supports
+
+USAGE:
+    kurl capabilities [--json]
+
+OPTIONS:
+    -j, --json          Output as JSON instead of formatted text
+    -h, --help          Show this help message
+"#;
+
+const INPUTS: &[&str] = &[
+    "url",
+    "ndjson",
+    "csv",
+    "har",
+    "html",
+    "bookmarks",
+    "accesslog",
+    "history",
+];
+const OUTPUTS: &[&str] = &["text", "json", "fields", "fzf"];
+/// How `fields` output can be printed, from `--get-format`.
+const GET_FORMATS: &[&str] = &["tsv", "json", "env"];
+const ID_FIELDS: &[&str] = &["uuid", "hash", "seq"];
+const GROUP_BY: &[&str] = &["site"];
+const BROWSERS: &[&str] = &["firefox", "chrome"];
+
+/// Optional cargo features compiled into this build.
+const FEATURES: &[&str] = &[
+    #[cfg(feature = "tui")]
+    "tui",
+];
+
+pub fn run(args: &[String]) {
+    let mut json_output = false;
+    for arg in args {
+        match arg.as_str() {
+            "-h" | "--help" => {
+                println!("{}", CAPABILITIES_HELP);
+                return;
+            }
+            "-j" | "--json" => json_output = true,
+            other => {
+                eprintln!("Unknown option for capabilities: {}", other);
+                std::process::exit(1);
+            }
+        }
+    }
+
+    let mut out = io::stdout().lock();
+    let _ = write_capabilities(&mut out, json_output);
+}
+
+fn write_capabilities<W: Write>(writer: &mut W, json_output: bool) -> io::Result<()> {
+    let capabilities = capabilities();
+    if json_output {
+        capabilities.write(writer)?;
+        return writeln!(writer);
+    }
+
+    let Value::Object(fields) = &capabilities else {
+        unreachable!()
+    };
+    for (name, value) in fields {
+        match value {
+            Value::Array(items) if name == "schemes" => {
+                writeln!(writer, "{}:", name)?;
+                for scheme in items {
+                    let name = scheme.get("name").unwrap();
+                    match scheme.get("default_port") {
+                        Some(Value::Null) | None => writeln!(writer, "  {}", name)?,
+                        Some(port) => writeln!(writer, "  {} (port {})", name, port)?,
+                    }
+                }
+            }
+            Value::Array(items) if items.is_empty() => writeln!(writer, "{}: none", name)?,
+            value => writeln!(writer, "{}: {}", name, value)?,
+        }
+    }
+    Ok(())
+}
+
+pub fn capabilities() -> Value {
+    let names = |names: &[&str]| Value::Array(names.iter().map(|&n| Value::from(n)).collect());
+    let schemes = crate::scheme::SPECIAL
+        .iter()
+        .map(|&scheme| {
+            let port = Url::parse(&format!("{}://example.com/", scheme))
+                .ok()
+                .and_then(|url| url.port_or_known_default());
+            Value::Object(vec![
+                ("name".to_string(), Value::from(scheme)),
+                (
+                    "default_port".to_string(),
+                    port.map_or(Value::Null, Value::from),
+                ),
+            ])
+        })
+        .collect();
+
+    Value::Object(vec![
+        ("version".to_string(), Value::from(crate::VERSION)),
+        (
+            "commands".to_string(),
+            Value::Array(
+                crate::help::COMMANDS
+                    .iter()
+                    .filter(|command| command.name != "tui" || cfg!(feature = "tui"))
+                    .map(|command| Value::from(command.name))
+                    .collect(),
+            ),
+        ),
+        ("inputs".to_string(), names(INPUTS)),
+        ("outputs".to_string(), names(OUTPUTS)),
+        ("get_formats".to_string(), names(GET_FORMATS)),
+        (
+            "output_versions".to_string(),
+            Value::Array((1..=crate::OUTPUT_VERSION).map(Value::from).collect()),
+        ),
+        ("id_fields".to_string(), names(ID_FIELDS)),
+        ("group_by".to_string(), names(GROUP_BY)),
+        ("browsers".to_string(), names(BROWSERS)),
+        ("schemes".to_string(), Value::Array(schemes)),
+        ("features".to_string(), names(FEATURES)),
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{group, history, id};
+
+    #[test]
+    fn test_listed_names_are_accepted() {
+        assert!(ID_FIELDS.iter().all(|n| id::IdField::parse(n).is_some()));
+        assert!(
+            GET_FORMATS
+                .iter()
+                .all(|n| crate::GetFormat::parse(n).is_some())
+        );
+        assert!(GROUP_BY.iter().all(|n| group::GroupBy::parse(n).is_some()));
+        assert!(
+            BROWSERS
+                .iter()
+                .all(|n| history::Browser::parse(n).is_some())
+        );
+    }
+
+    #[test]
+    fn test_capabilities_json() {
+        let mut output = Vec::new();
+        write_capabilities(&mut output, true).unwrap();
+
+        let value = crate::json::parse(&String::from_utf8(output).unwrap()).unwrap();
+        let Some(Value::Array(schemes)) = value.get("schemes") else {
+            panic!("schemes should be an array");
+        };
+        assert_eq!(
+            schemes[1].to_json(),
+            r#"{"name":"https","default_port":443}"#
+        );
+        assert_eq!(
+            schemes[5].to_json(),
+            r#"{"name":"file","default_port":null}"#
+        );
+        let features = FEATURES.iter().map(|&f| Value::from(f)).collect();
+        assert_eq!(value.get("features"), Some(&Value::Array(features)));
+    }
+}
@@ -0,0 +1,170 @@
+use std::io::{self, IsTerminal};
+
+use crate::OutputOptions;
+use crate::query;
+use crate::transform::{self, TransformOptions};
+
+const CLEAN_HELP: &str = r#"kurl clean - remove tracking parameters from URLs
+
+USAGE:
+    kurl clean [OPTIONS] [<URL>...]
+    <URLs> | kurl clean [OPTIONS]
+
+OPTIONS:
+        --param-list <FILE> Also remove the parameters listed in FILE, one
+                            name per line, with a trailing * for a prefix
+        --keep <NAME>       Keep NAME parameters even if a list matches them
+    -j, --json              Print each result's components as JSON
+        --dry-run           Report how many URLs would change without printing them
+        --show-diff         Print a diff of the URLs that would change
+        --verify-idempotent Fail, printing nothing, unless a second pass leaves
+                            every result unchanged
+    -h, --help              Show this help message
+
+The bundled list covers the common campaign and click identifiers, such as
+utm_*, fbclid, gclid, msclkid, mc_eid, and igshid. Names match
+case-insensitively, and other parameters keep their order and encoding.
+
+EXAMPLES:
+    kurl clean 'https://example.com/post?id=7&utm_source=news&fbclid=abc'
+    cat links.txt | kurl clean --param-list extra.txt --keep ref
+"#;
+
+/// The tracking parameters bundled with kurl.
+const TRACKING_PARAMS: &str = include_str!("../data/tracking_params.txt");
+
+pub fn run(args: &[String]) {
+    let mut params = ParamList::parse(TRACKING_PARAMS);
+    let mut keep = Vec::new();
+    let mut urls = Vec::new();
+    let mut options = TransformOptions::default();
+    let mut output = OutputOptions::default();
+
+    let mut rest = args.iter();
+    while let Some(arg) = rest.next() {
+        match arg.as_str() {
+            "-h" | "--help" => {
+                println!("{}", CLEAN_HELP);
+                return;
+            }
+            flag @ ("--param-list" | "--keep") => {
+                let Some(value) = rest.next() else {
+                    eprintln!("Missing value for {}", flag);
+                    std::process::exit(1);
+                };
+                if flag == "--keep" {
+                    keep.push(value.to_ascii_lowercase());
+                    continue;
+                }
+                match std::fs::read_to_string(value) {
+                    Ok(text) => params.extend(ParamList::parse(&text)),
+                    Err(e) => {
+                        eprintln!("Failed to read {}: {}", value, e);
+                        std::process::exit(1);
+                    }
+                }
+            }
+            other if options.accept(other) => {}
+            other if output.accept(other, &mut rest) => {}
+            other if other.starts_with('-') => {
+                eprintln!("Unknown option for clean: {}", other);
+                std::process::exit(1);
+            }
+            other => urls.push(other),
+        }
+    }
+
+    let mut buffer = String::new();
+    if urls.is_empty() {
+        if io::stdin().is_terminal() {
+            eprintln!("Usage: kurl clean [--param-list FILE] [--keep NAME] [<URL>...]");
+            std::process::exit(1);
+        }
+        buffer = crate::read_stdin();
+        urls.push("-");
+    }
+    let inputs = crate::batch_inputs(&urls, &buffer);
+
+    transform::run(&inputs, &options, &output, |url| {
+        let mut url = url.clone();
+        query::remove(&mut url, |key| {
+            let key = key.to_ascii_lowercase();
+            params.matches(&key) && !keep.contains(&key)
+        });
+        Ok(url)
+    });
+}
+
+/// Parameter names to remove, in lowercase.
+#[derive(Debug, Default)]
+struct ParamList {
+    names: Vec<String>,
+    /// Prefixes from names ending in `*`.
+    prefixes: Vec<String>,
+}
+
+impl ParamList {
+    /// Reads one name per line, skipping blank lines and `#` comments.
+    fn parse(text: &str) -> ParamList {
+        let mut list = ParamList::default();
+        for line in text.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let name = line.to_ascii_lowercase();
+            match name.strip_suffix('*') {
+                Some(prefix) => list.prefixes.push(prefix.to_string()),
+                None => list.names.push(name),
+            }
+        }
+        list
+    }
+
+    fn extend(&mut self, other: ParamList) {
+        self.names.extend(other.names);
+        self.prefixes.extend(other.prefixes);
+    }
+
+    /// Whether the lowercase name `key` is on the list.
+    fn matches(&self, key: &str) -> bool {
+        self.names.iter().any(|name| name == key)
+            || self
+                .prefixes
+                .iter()
+                .any(|prefix| key.starts_with(prefix.as_str()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bundled_list() {
+        let list = ParamList::parse(TRACKING_PARAMS);
+        for key in [
+            "utm_source",
+            "utm_content",
+            "fbclid",
+            "gclid",
+            "mc_eid",
+            "igshid",
+        ] {
+            assert!(list.matches(key), "{}", key);
+        }
+        assert!(list.matches("hsctatracking"));
+        for key in ["id", "q", "page", "utm", "ref"] {
+            assert!(!list.matches(key), "{}", key);
+        }
+    }
+
+    #[test]
+    fn test_custom_list() {
+        let mut list = ParamList::parse("# ours\n\n  Ref \nsession_*\n");
+        assert!(list.matches("ref"));
+        assert!(list.matches("session_id"));
+        assert!(!list.matches("fbclid"));
+        list.extend(ParamList::parse(TRACKING_PARAMS));
+        assert!(list.matches("fbclid"));
+    }
+}
@@ -0,0 +1,265 @@
+use url::Url;
+
+use crate::regex::Regex;
+
+/// Fields a condition can test, besides `query.NAME`.
+const FIELDS: &[&str] = &[
+    "scheme", "user", "password", "host", "port", "path", "query", "fragment",
+];
+
+/// A test on a URL's components, like `host == "old.example.com"`.
+///
+/// Comparisons are `FIELD OP VALUE`, where OP is `==`, `!=`, `=~` (matches a
+/// regex), or `!~`, and VALUE is a quoted string or a bare word. They combine
+/// with `&&`, `||`, and parentheses. Missing components compare as empty, and
+/// `port` is the effective port, so `port == 443` holds for any https URL
+/// without one.
+#[derive(Debug, Clone)]
+pub enum Condition {
+    Compare { field: String, test: Test },
+    And(Box<Condition>, Box<Condition>),
+    Or(Box<Condition>, Box<Condition>),
+}
+
+#[derive(Debug, Clone)]
+pub enum Test {
+    Equals(String, bool),
+    Matches(Regex, bool),
+}
+
+impl Condition {
+    pub fn parse(text: &str) -> Result<Condition, String> {
+        let tokens = tokenize(text)?;
+        let mut parser = Parser { tokens, pos: 0 };
+        let condition = parser.or()?;
+        match parser.tokens.get(parser.pos) {
+            None => Ok(condition),
+            Some(token) => Err(format!("unexpected {} in condition", token)),
+        }
+    }
+
+    pub fn matches(&self, url: &Url) -> bool {
+        match self {
+            Condition::Compare { field, test } => {
+                let value = field_value(url, field);
+                match test {
+                    Test::Equals(expected, equal) => (value == *expected) == *equal,
+                    Test::Matches(regex, matching) => regex.is_match(&value) == *matching,
+                }
+            }
+            Condition::And(a, b) => a.matches(url) && b.matches(url),
+            Condition::Or(a, b) => a.matches(url) || b.matches(url),
+        }
+    }
+}
+
+fn field_value(url: &Url, field: &str) -> String {
+    if let Some(name) = field.strip_prefix("query.") {
+        return url
+            .query_pairs()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.into_owned())
+            .unwrap_or_default();
+    }
+    match field {
+        "scheme" => url.scheme().to_string(),
+        "user" => url.username().to_string(),
+        "password" => url.password().unwrap_or_default().to_string(),
+        "host" => url.host_str().unwrap_or_default().to_string(),
+        "port" => url
+            .port_or_known_default()
+            .map(|port| port.to_string())
+            .unwrap_or_default(),
+        "path" => url.path().to_string(),
+        "query" => url.query().unwrap_or_default().to_string(),
+        "fragment" => url.fragment().unwrap_or_default().to_string(),
+        _ => String::new(),
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum Token {
+    Word(String),
+    Quoted(String),
+    Operator(&'static str),
+}
+
+impl std::fmt::Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Token::Word(word) => write!(f, "{}", word),
+            Token::Quoted(text) => write!(f, "{:?}", text),
+            Token::Operator(op) => write!(f, "'{}'", op),
+        }
+    }
+}
+
+const OPERATORS: &[&str] = &["==", "!=", "=~", "!~", "&&", "||", "(", ")"];
+
+fn tokenize(text: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut rest = text.trim_start();
+    while let Some(c) = rest.chars().next() {
+        if let Some(op) = OPERATORS.iter().find(|op| rest.starts_with(**op)) {
+            tokens.push(Token::Operator(op));
+            rest = &rest[op.len()..];
+        } else if c == '"' || c == '\'' {
+            let mut value = String::new();
+            let mut chars = rest[1..].char_indices();
+            let end = loop {
+                match chars.next() {
+                    None => return Err(format!("unterminated string in condition: {}", rest)),
+                    Some((i, q)) if q == c => break i + 2,
+                    // Only quotes and backslashes are escaped, so regexes keep
+                    // escapes like \d.
+                    Some((_, '\\')) if c == '"' => match chars.next() {
+                        Some((_, escaped @ ('"' | '\\'))) => value.push(escaped),
+                        Some((_, escaped)) => {
+                            value.push('\\');
+                            value.push(escaped);
+                        }
+                        None => return Err("trailing '\\' in condition".to_string()),
+                    },
+                    Some((_, other)) => value.push(other),
+                }
+            };
+            tokens.push(Token::Quoted(value));
+            rest = &rest[end..];
+        } else {
+            let end = rest
+                .find(|c: char| c.is_whitespace() || "=!&|()\"'".contains(c))
+                .unwrap_or(rest.len());
+            if end == 0 {
+                return Err(format!("unexpected {:?} in condition", c));
+            }
+            tokens.push(Token::Word(rest[..end].to_string()));
+            rest = &rest[end..];
+        }
+        rest = rest.trim_start();
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn eat(&mut self, op: &str) -> bool {
+        if matches!(self.tokens.get(self.pos), Some(Token::Operator(o)) if *o == op) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn or(&mut self) -> Result<Condition, String> {
+        let mut condition = self.and()?;
+        while self.eat("||") {
+            condition = Condition::Or(Box::new(condition), Box::new(self.and()?));
+        }
+        Ok(condition)
+    }
+
+    fn and(&mut self) -> Result<Condition, String> {
+        let mut condition = self.primary()?;
+        while self.eat("&&") {
+            condition = Condition::And(Box::new(condition), Box::new(self.primary()?));
+        }
+        Ok(condition)
+    }
+
+    fn primary(&mut self) -> Result<Condition, String> {
+        if self.eat("(") {
+            let condition = self.or()?;
+            if !self.eat(")") {
+                return Err("missing ')' in condition".to_string());
+            }
+            return Ok(condition);
+        }
+
+        let field = match self.tokens.get(self.pos) {
+            Some(Token::Word(field))
+                if FIELDS.contains(&field.as_str()) || field.starts_with("query.") =>
+            {
+                field.clone()
+            }
+            Some(token) => {
+                return Err(format!(
+                    "expected a field ({}, or query.NAME), got {}",
+                    FIELDS.join(", "),
+                    token
+                ));
+            }
+            None => return Err("expected a field at the end of the condition".to_string()),
+        };
+        self.pos += 1;
+
+        let op = match self.tokens.get(self.pos) {
+            Some(Token::Operator(op @ ("==" | "!=" | "=~" | "!~"))) => *op,
+            _ => return Err(format!("expected ==, !=, =~, or !~ after {}", field)),
+        };
+        self.pos += 1;
+        let value = match self.tokens.get(self.pos) {
+            Some(Token::Word(value) | Token::Quoted(value)) => value.clone(),
+            _ => return Err(format!("expected a value after {} {}", field, op)),
+        };
+        self.pos += 1;
+
+        let test = match op {
+            "==" => Test::Equals(value, true),
+            "!=" => Test::Equals(value, false),
+            "=~" => Test::Matches(Regex::new(&value)?, true),
+            _ => Test::Matches(Regex::new(&value)?, false),
+        };
+        Ok(Condition::Compare { field, test })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn holds(condition: &str, url: &str) -> bool {
+        Condition::parse(condition)
+            .unwrap()
+            .matches(&Url::parse(url).unwrap())
+    }
+
+    #[test]
+    fn test_comparisons() {
+        let url = "https://old.example.com/api/v1?token=abc";
+        assert!(holds(r#"host == "old.example.com""#, url));
+        assert!(holds("host != new.example.com", url));
+        assert!(holds("port == 443 && scheme == https", url));
+        assert!(holds(r"path =~ '^/api/v\d+'", url));
+        assert!(holds("path !~ ^/admin", url));
+        assert!(holds("query.token == abc && fragment == ''", url));
+        assert!(holds(
+            "host == a.example || (path =~ v1 && query.page == '')",
+            url
+        ));
+        assert!(!holds("host == a.example || path =~ v2", url));
+        assert!(holds(r#"host == "say \"hi\"" || user == """#, url));
+        assert!(holds(r#"path =~ "v\d""#, url));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        for condition in [
+            "",
+            "host",
+            "host ==",
+            "colour == red",
+            "host = x",
+            "(host == x",
+            "host == 'x",
+            "host == x y",
+            "path =~ (",
+        ] {
+            assert!(Condition::parse(condition).is_err(), "{}", condition);
+        }
+    }
+}
@@ -0,0 +1,399 @@
+use std::path::PathBuf;
+
+use crate::json::Value;
+
+/// Finds the user's config file: `$KURL_CONFIG`, or `kurl/config.toml` in the
+/// platform's config directory.
+fn path() -> Option<PathBuf> {
+    if let Some(path) = std::env::var_os("KURL_CONFIG") {
+        return Some(PathBuf::from(path));
+    }
+    let dir = std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("APPDATA").map(PathBuf::from))
+        .or_else(|| std::env::var_os("HOME").map(|h| PathBuf::from(h).join(".config")))?;
+    Some(dir.join("kurl").join("config.toml"))
+}
+
+/// The directory kurl caches downloads in: `kurl` in `$XDG_CACHE_HOME`,
+/// `%LOCALAPPDATA%`, or `~/.cache`.
+pub fn cache_dir() -> Option<PathBuf> {
+    let dir = std::env::var_os("XDG_CACHE_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("LOCALAPPDATA").map(PathBuf::from))
+        .or_else(|| std::env::var_os("HOME").map(|h| PathBuf::from(h).join(".cache")))?;
+    Some(dir.join("kurl"))
+}
+
+/// Loads the config file as an object, which is empty when there is no file.
+/// A file that exists but can't be read or parsed is an error, so that
+/// settings never silently go missing.
+pub fn load() -> Value {
+    let Some(path) = path() else {
+        return Value::Object(Vec::new());
+    };
+    let text = match std::fs::read_to_string(&path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Value::Object(Vec::new()),
+        Err(e) => {
+            eprintln!("Failed to read {}: {}", path.display(), e);
+            std::process::exit(1);
+        }
+    };
+    parse(&text).unwrap_or_else(|e| {
+        eprintln!("Failed to parse {}: {}", path.display(), e);
+        std::process::exit(1);
+    })
+}
+
+/// Parses the subset of TOML that kurl's files use: tables, arrays of
+/// tables, dotted keys, basic and literal strings, numbers, booleans, arrays,
+/// and inline tables. Multi-line strings and dates are not supported.
+pub fn parse(text: &str) -> Result<Value, String> {
+    let mut parser = Parser {
+        text,
+        bytes: text.as_bytes(),
+        pos: 0,
+    };
+    let mut root = Vec::new();
+    let mut table: Vec<String> = Vec::new();
+
+    loop {
+        parser.skip_blank_lines();
+        match parser.peek() {
+            None => return Ok(Value::Object(root)),
+            Some(b'[') => {
+                parser.pos += 1;
+                let array = parser.eat(b'[');
+                table = parser.key()?;
+                parser.expect(b']')?;
+                if array {
+                    parser.expect(b']')?;
+                    let (last, parent) = table.split_last().unwrap();
+                    let parent = table_mut(&mut root, parent)?;
+                    match entry(parent, last, || Value::Array(Vec::new())) {
+                        Value::Array(tables) => tables.push(Value::Object(Vec::new())),
+                        _ => return Err(parser.error(&format!("{} is not an array", last))),
+                    }
+                } else {
+                    table_mut(&mut root, &table)?;
+                }
+            }
+            Some(_) => {
+                let key = parser.key()?;
+                parser.expect(b'=')?;
+                let value = parser.value()?;
+                let (last, parent) = key.split_last().unwrap();
+                let path: Vec<String> = table.iter().chain(parent).cloned().collect();
+                let fields = table_mut(&mut root, &path)?;
+                if fields.iter().any(|(k, _)| k == last) {
+                    return Err(parser.error(&format!("duplicate key {}", last)));
+                }
+                fields.push((last.clone(), value));
+            }
+        }
+        parser.end_of_line()?;
+    }
+}
+
+/// Returns the value under `key`, inserting `default()` if there is none.
+fn entry<'a>(
+    fields: &'a mut Vec<(String, Value)>,
+    key: &str,
+    default: impl FnOnce() -> Value,
+) -> &'a mut Value {
+    let index = match fields.iter().position(|(k, _)| k == key) {
+        Some(index) => index,
+        None => {
+            fields.push((key.to_string(), default()));
+            fields.len() - 1
+        }
+    };
+    &mut fields[index].1
+}
+
+/// Walks to the table at `path`, creating missing tables and descending into
+/// the newest element of arrays of tables.
+fn table_mut<'a>(
+    mut fields: &'a mut Vec<(String, Value)>,
+    path: &[String],
+) -> Result<&'a mut Vec<(String, Value)>, String> {
+    for key in path {
+        let mut value = entry(fields, key, || Value::Object(Vec::new()));
+        if let Value::Array(tables) = value {
+            value = tables
+                .last_mut()
+                .ok_or_else(|| format!("{} is not a table", key))?;
+        }
+        match value {
+            Value::Object(inner) => fields = inner,
+            _ => return Err(format!("{} is not a table", key)),
+        }
+    }
+    Ok(fields)
+}
+
+struct Parser<'a> {
+    text: &'a str,
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn error(&self, message: &str) -> String {
+        let line = self.text[..self.pos].matches('\n').count() + 1;
+        format!("{} on line {}", message, line)
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.bytes.get(self.pos).copied()
+    }
+
+    fn skip_spaces(&mut self) {
+        while matches!(self.peek(), Some(b' ' | b'\t')) {
+            self.pos += 1;
+        }
+    }
+
+    fn skip_comment(&mut self) {
+        if self.peek() == Some(b'#') {
+            while !matches!(self.peek(), None | Some(b'\n')) {
+                self.pos += 1;
+            }
+        }
+    }
+
+    /// Skips whitespace, newlines, and comments.
+    fn skip_blank_lines(&mut self) {
+        loop {
+            self.skip_spaces();
+            self.skip_comment();
+            match self.peek() {
+                Some(b'\n' | b'\r') => self.pos += 1,
+                _ => return,
+            }
+        }
+    }
+
+    fn end_of_line(&mut self) -> Result<(), String> {
+        self.skip_spaces();
+        self.skip_comment();
+        match self.peek() {
+            None | Some(b'\n' | b'\r') => Ok(()),
+            Some(_) => Err(self.error("expected the end of the line")),
+        }
+    }
+
+    fn eat(&mut self, b: u8) -> bool {
+        self.skip_spaces();
+        if self.peek() == Some(b) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, b: u8) -> Result<(), String> {
+        if self.eat(b) {
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected '{}'", b as char)))
+        }
+    }
+
+    /// Parses a possibly dotted key into its parts.
+    fn key(&mut self) -> Result<Vec<String>, String> {
+        let mut parts = Vec::new();
+        loop {
+            self.skip_spaces();
+            let part = match self.peek() {
+                Some(b'"') => self.basic_string()?,
+                Some(b'\'') => self.literal_string()?,
+                _ => {
+                    let start = self.pos;
+                    while self
+                        .peek()
+                        .is_some_and(|b| b.is_ascii_alphanumeric() || matches!(b, b'_' | b'-'))
+                    {
+                        self.pos += 1;
+                    }
+                    if start == self.pos {
+                        return Err(self.error("expected a key"));
+                    }
+                    self.text[start..self.pos].to_string()
+                }
+            };
+            parts.push(part);
+            if !self.eat(b'.') {
+                return Ok(parts);
+            }
+        }
+    }
+
+    fn value(&mut self) -> Result<Value, String> {
+        self.skip_spaces();
+        match self.peek() {
+            Some(b'"') => self.basic_string().map(Value::String),
+            Some(b'\'') => self.literal_string().map(Value::String),
+            Some(b'[') => {
+                self.pos += 1;
+                let mut items = Vec::new();
+                loop {
+                    self.skip_blank_lines();
+                    if self.eat(b']') {
+                        return Ok(Value::Array(items));
+                    }
+                    items.push(self.value()?);
+                    self.skip_blank_lines();
+                    if !self.eat(b',') {
+                        self.skip_blank_lines();
+                        self.expect(b']')?;
+                        return Ok(Value::Array(items));
+                    }
+                }
+            }
+            Some(b'{') => {
+                self.pos += 1;
+                let mut fields = Vec::new();
+                if self.eat(b'}') {
+                    return Ok(Value::Object(fields));
+                }
+                loop {
+                    let key = self.key()?;
+                    self.expect(b'=')?;
+                    let value = self.value()?;
+                    let (last, parent) = key.split_last().unwrap();
+                    table_mut(&mut fields, parent)?.push((last.clone(), value));
+                    if !self.eat(b',') {
+                        self.expect(b'}')?;
+                        return Ok(Value::Object(fields));
+                    }
+                }
+            }
+            _ => {
+                let start = self.pos;
+                while self.peek().is_some_and(|b| {
+                    b.is_ascii_alphanumeric() || matches!(b, b'+' | b'-' | b'.' | b'_')
+                }) {
+                    self.pos += 1;
+                }
+                match &self.text[start..self.pos] {
+                    "true" => Ok(Value::Bool(true)),
+                    "false" => Ok(Value::Bool(false)),
+                    number if number.replace('_', "").parse::<f64>().is_ok() => {
+                        Ok(Value::Number(number.replace('_', "")))
+                    }
+                    _ => {
+                        self.pos = start;
+                        Err(self.error("expected a value"))
+                    }
+                }
+            }
+        }
+    }
+
+    fn literal_string(&mut self) -> Result<String, String> {
+        self.pos += 1;
+        let rest = &self.text[self.pos..];
+        let end = rest
+            .find(['\'', '\n'])
+            .filter(|&i| rest.as_bytes()[i] == b'\'')
+            .ok_or_else(|| self.error("unterminated string"))?;
+        self.pos += end + 1;
+        Ok(rest[..end].to_string())
+    }
+
+    fn basic_string(&mut self) -> Result<String, String> {
+        self.pos += 1;
+        let mut out = String::new();
+        loop {
+            let rest = &self.text[self.pos..];
+            let Some(special) = rest.find(['"', '\\', '\n']) else {
+                return Err(self.error("unterminated string"));
+            };
+            out.push_str(&rest[..special]);
+            self.pos += special;
+
+            match self.bytes[self.pos] {
+                b'"' => {
+                    self.pos += 1;
+                    return Ok(out);
+                }
+                b'\n' => return Err(self.error("unterminated string")),
+                _ => {}
+            }
+
+            self.pos += 1;
+            let escaped = match self.peek() {
+                Some(b'"') => '"',
+                Some(b'\\') => '\\',
+                Some(b'n') => '\n',
+                Some(b't') => '\t',
+                Some(b'r') => '\r',
+                Some(b'u') => {
+                    let code = self
+                        .text
+                        .get(self.pos + 1..self.pos + 5)
+                        .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                        .and_then(char::from_u32)
+                        .ok_or_else(|| self.error("invalid \\u escape"))?;
+                    self.pos += 4;
+                    code
+                }
+                _ => return Err(self.error("invalid escape")),
+            };
+            out.push(escaped);
+            self.pos += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_tables_and_values() {
+        let text = r#"
+# kurl settings
+lenient = true
+default.scheme = 'https'
+
+[rewrite]
+hosts = ["a.example", "b.example",]   # trailing comma
+limit = 1_000
+
+[[rule]]
+match = { host = "old.example" }
+set = "x\"yé"
+
+[[rule]]
+match.host = "other.example"
+"#;
+        let value = parse(text).unwrap();
+        assert_eq!(
+            value.to_json(),
+            r#"{"lenient":true,"default":{"scheme":"https"},"rewrite":{"hosts":["a.example","b.example"],"limit":1000},"rule":[{"match":{"host":"old.example"},"set":"x\"yé"},{"match":{"host":"other.example"}}]}"#
+        );
+    }
+
+    #[test]
+    fn test_parse_multiline_array() {
+        let value = parse("list = [\n  1, # one\n  2\n]\n").unwrap();
+        assert_eq!(value.to_json(), r#"{"list":[1,2]}"#);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(
+            parse("a = 1\na = 2").unwrap_err(),
+            "duplicate key a on line 2"
+        );
+        assert_eq!(parse("a = nope").unwrap_err(), "expected a value on line 1");
+        assert!(parse("a = \"open\nb = 1").is_err());
+        assert!(parse("a = 1 b = 2").is_err());
+        assert!(parse("[t\nx = 1").is_err());
+        assert!(parse("a = 1\n[a]").is_err());
+    }
+}
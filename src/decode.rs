@@ -0,0 +1,121 @@
+use std::io::{self, IsTerminal, Write};
+
+use crate::edit;
+use crate::json::Value;
+
+const DECODE_HELP: &str = r#"kurl decode - percent-decode text
+
+USAGE:
+    kurl decode [OPTIONS] <STRING>...
+    <lines> | kurl decode [OPTIONS]
+
+OPTIONS:
+        --repeat        Keep decoding until the text stops changing, and report
+                        how many layers of encoding there were on stderr
+    -j, --json          Print each result as {"input", "decoded", "layers"}
+    -h, --help          Show this help message
+
+Decodes every `%XX` escape, leaving malformed ones as they are and replacing
+bytes that aren't UTF-8. `+` is left alone, since it only means a space in
+form-encoded queries.
+
+EXAMPLES:
+    kurl decode 'caf%C3%A9%20cr%C3%A8me'
+    kurl decode --repeat 'https%253A%252F%252Fexample.com%252Fnext'
+"#;
+
+pub fn run(args: &[String]) {
+    let mut repeat = false;
+    let mut json_output = false;
+    let mut inputs = Vec::new();
+    for arg in args {
+        match arg.as_str() {
+            "-h" | "--help" => {
+                println!("{}", DECODE_HELP);
+                return;
+            }
+            "--repeat" => repeat = true,
+            "-j" | "--json" => json_output = true,
+            // Text may start with `-`, but never with `--`.
+            other if other.starts_with("--") => {
+                eprintln!("Unknown option for decode: {}", other);
+                std::process::exit(1);
+            }
+            other => inputs.push(other),
+        }
+    }
+
+    let buffer;
+    if inputs.is_empty() {
+        if io::stdin().is_terminal() {
+            eprintln!("Usage: kurl decode [--repeat] <STRING>...");
+            std::process::exit(1);
+        }
+        buffer = crate::read_stdin();
+        inputs = buffer.lines().collect();
+    }
+
+    let mut out = io::stdout().lock();
+    for input in inputs {
+        let (decoded, layers) = decode(input, repeat);
+        let result = if json_output {
+            Value::Object(vec![
+                ("input".to_string(), Value::from(input)),
+                ("decoded".to_string(), Value::from(decoded.as_str())),
+                ("layers".to_string(), Value::from(layers)),
+            ])
+            .write(&mut out)
+            .and_then(|_| writeln!(out))
+        } else {
+            if repeat {
+                eprintln!("{} layer(s) of encoding in {}", layers, input);
+            }
+            writeln!(out, "{}", decoded)
+        };
+        if result.is_err() {
+            return;
+        }
+    }
+}
+
+/// Decodes `text` once, or with `repeat` until decoding changes nothing,
+/// returning the result and how many decodings changed it.
+fn decode(text: &str, repeat: bool) -> (String, u64) {
+    let mut decoded = text.to_string();
+    let mut layers = 0;
+    loop {
+        let next = edit::percent_decode(&decoded);
+        if next == decoded {
+            break;
+        }
+        decoded = next;
+        layers += 1;
+        if !repeat {
+            break;
+        }
+    }
+    (decoded, layers)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode() {
+        let triple = "https%25253A%25252F%25252Fexample.com%25252F%25253Fq%25253Da%252520b";
+        assert_eq!(
+            decode(triple, false),
+            (
+                "https%253A%252F%252Fexample.com%252F%253Fq%253Da%2520b".to_string(),
+                1
+            )
+        );
+        assert_eq!(
+            decode(triple, true),
+            ("https://example.com/?q=a b".to_string(), 3)
+        );
+        assert_eq!(decode("100%", true), ("100%".to_string(), 0));
+        assert_eq!(decode("a+b%2Bc%zz", true), ("a+b+c%zz".to_string(), 1));
+    }
+}
@@ -0,0 +1,240 @@
+use std::io::{self, Write};
+use url::Url;
+
+use crate::json::Value;
+
+const DIFF_HELP: &str = r#"kurl diff - compare two URLs component by component
+
+USAGE:
+    kurl diff [OPTIONS] <URL1> <URL2>
+
+OPTIONS:
+    -j, --json          Output the differences as JSON
+    -h, --help          Show this help message
+
+Lists the scheme, credentials, host, port, path, each query parameter, and
+the fragment, marking components that changed with `~`, ones only the first
+URL has with `-`, and ones only the second has with `+`. Query parameters
+pair up by key, so reordering them is no difference. Exits 0 if the URLs are
+the same and 1 if they differ, like diff(1).
+
+EXAMPLES:
+    kurl diff "https://a.example.com/cb?state=1&sig=x" "https://a.example.com/cb?sig=y&state=1"
+    kurl diff --json "$requested" "$redirected"
+"#;
+
+pub fn run(args: &[String]) {
+    let mut json_output = false;
+    let mut urls = Vec::new();
+    for arg in args {
+        match arg.as_str() {
+            "-h" | "--help" => {
+                println!("{}", DIFF_HELP);
+                return;
+            }
+            "-j" | "--json" => json_output = true,
+            other if other.starts_with('-') => {
+                eprintln!("Unknown option for diff: {}", other);
+                std::process::exit(1);
+            }
+            other => urls.push(other),
+        }
+    }
+
+    let [left, right] = urls[..] else {
+        eprintln!("Usage: kurl diff [--json] <URL1> <URL2>");
+        std::process::exit(1);
+    };
+    let (left, right) = (
+        crate::parse_url_or_exit(left),
+        crate::parse_url_or_exit(right),
+    );
+
+    let rows = compare(&left, &right);
+    let mut out = io::stdout().lock();
+    let _ = if json_output {
+        to_value(&rows).write(&mut out).and_then(|_| writeln!(out))
+    } else {
+        write_pretty(&mut out, &rows)
+    };
+    if rows.iter().any(|row| row.left != row.right) {
+        std::process::exit(1);
+    }
+}
+
+/// One component of either URL, with its value in each.
+#[derive(Debug, PartialEq)]
+struct Row {
+    component: String,
+    left: Option<String>,
+    right: Option<String>,
+}
+
+/// Lines up the components of two URLs. Query parameters pair up by key and
+/// then by position among those with that key, so reordering alone is no
+/// difference; repeated keys are labelled `query.KEY[N]`.
+fn compare(left: &Url, right: &Url) -> Vec<Row> {
+    let mut rows = Vec::new();
+    let mut row = |component: &str, left: Option<String>, right: Option<String>| {
+        if left.is_some() || right.is_some() {
+            rows.push(Row {
+                component: component.to_string(),
+                left,
+                right,
+            });
+        }
+    };
+    let user = |url: &Url| Some(url.username().to_string()).filter(|user| !user.is_empty());
+    let port = |url: &Url| url.port().map(|port| port.to_string());
+
+    row(
+        "scheme",
+        Some(left.scheme().into()),
+        Some(right.scheme().into()),
+    );
+    row("user", user(left), user(right));
+    row(
+        "password",
+        left.password().map(Into::into),
+        right.password().map(Into::into),
+    );
+    row(
+        "host",
+        left.host_str().map(Into::into),
+        right.host_str().map(Into::into),
+    );
+    row("port", port(left), port(right));
+    row("path", Some(left.path().into()), Some(right.path().into()));
+
+    let (left_params, right_params) = (params(left), params(right));
+    let mut keys: Vec<&String> = Vec::new();
+    for (key, _) in left_params.iter().chain(&right_params) {
+        if !keys.contains(&key) {
+            keys.push(key);
+        }
+    }
+    for key in keys {
+        let values = |params: &[(String, String)]| -> Vec<String> {
+            params
+                .iter()
+                .filter(|(k, _)| k == key)
+                .map(|(_, v)| v.clone())
+                .collect()
+        };
+        let (left_values, right_values) = (values(&left_params), values(&right_params));
+        let count = left_values.len().max(right_values.len());
+        for i in 0..count {
+            let component = if count == 1 {
+                format!("query.{}", key)
+            } else {
+                format!("query.{}[{}]", key, i + 1)
+            };
+            row(
+                &component,
+                left_values.get(i).cloned(),
+                right_values.get(i).cloned(),
+            );
+        }
+    }
+
+    row(
+        "fragment",
+        left.fragment().map(Into::into),
+        right.fragment().map(Into::into),
+    );
+    rows
+}
+
+fn params(url: &Url) -> Vec<(String, String)> {
+    url.query_pairs()
+        .map(|(key, value)| (key.into_owned(), value.into_owned()))
+        .collect()
+}
+
+fn write_pretty<W: Write>(writer: &mut W, rows: &[Row]) -> io::Result<()> {
+    let width = rows
+        .iter()
+        .map(|row| row.component.len())
+        .max()
+        .unwrap_or(0);
+    for row in rows {
+        let name = &row.component;
+        match (&row.left, &row.right) {
+            (Some(left), Some(right)) if left == right => {
+                writeln!(writer, "  {:width$} : {}", name, left)?
+            }
+            (Some(left), Some(right)) => {
+                writeln!(writer, "~ {:width$} : {} -> {}", name, left, right)?
+            }
+            (Some(left), None) => writeln!(writer, "- {:width$} : {}", name, left)?,
+            (None, Some(right)) => writeln!(writer, "+ {:width$} : {}", name, right)?,
+            (None, None) => {}
+        }
+    }
+    Ok(())
+}
+
+/// The differences only, as `{"equal": ..., "differences": [...]}`, with
+/// `null` for a side that lacks the component.
+fn to_value(rows: &[Row]) -> Value {
+    let side = |value: &Option<String>| value.as_deref().map_or(Value::Null, Value::from);
+    let differences: Vec<Value> = rows
+        .iter()
+        .filter(|row| row.left != row.right)
+        .map(|row| {
+            Value::Object(vec![
+                ("component".to_string(), Value::from(row.component.as_str())),
+                ("left".to_string(), side(&row.left)),
+                ("right".to_string(), side(&row.right)),
+            ])
+        })
+        .collect();
+    Value::Object(vec![
+        ("equal".to_string(), Value::from(differences.is_empty())),
+        ("differences".to_string(), Value::Array(differences)),
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rows(left: &str, right: &str) -> Vec<Row> {
+        compare(&Url::parse(left).unwrap(), &Url::parse(right).unwrap())
+    }
+
+    #[test]
+    fn test_pretty() {
+        let rows = rows(
+            "https://a.example.com/cb?state=1&sig=x&t=1&t=2#top",
+            "https://b.example.com:8443/cb?sig=y&state=1&t=1&new=",
+        );
+        let mut output = Vec::new();
+        write_pretty(&mut output, &rows).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "  scheme      : https\n\
+             ~ host        : a.example.com -> b.example.com\n\
+             + port        : 8443\n\
+             \x20 path        : /cb\n\
+             \x20 query.state : 1\n\
+             ~ query.sig   : x -> y\n\
+             \x20 query.t[1]  : 1\n\
+             - query.t[2]  : 2\n\
+             + query.new   : \n\
+             - fragment    : top\n"
+        );
+    }
+
+    #[test]
+    fn test_json() {
+        assert_eq!(
+            to_value(&rows("https://a.com/?x=1&y=2", "https://a.com/?y=2&x=1")).to_json(),
+            r#"{"equal":true,"differences":[]}"#
+        );
+        assert_eq!(
+            to_value(&rows("https://u@a.com/", "http://a.com/")).to_json(),
+            r#"{"equal":false,"differences":[{"component":"scheme","left":"https","right":"http"},{"component":"user","left":"u","right":null}]}"#
+        );
+    }
+}
@@ -0,0 +1,257 @@
+use url::Url;
+
+use crate::scheme;
+
+/// Components that `set` can change, in the order they appear in a URL.
+pub const SETTABLE: &[&str] = &[
+    "scheme", "user", "password", "host", "port", "path", "query", "fragment",
+];
+
+/// Components that `strip` can remove.
+pub const STRIPPABLE: &[&str] = &["credentials", "port", "query", "fragment"];
+
+/// Sets one component with the URL crate's setters, which percent-encode the
+/// value as that component requires. An empty value clears optional parts.
+pub fn set(url: &mut Url, component: &str, value: &str) -> Result<(), String> {
+    let optional = if value.is_empty() { None } else { Some(value) };
+    let result = match component {
+        "scheme" => url.set_scheme(value),
+        "user" => url.set_username(value),
+        "password" => url.set_password(optional),
+        "host" => {
+            return url
+                .set_host(optional)
+                .map_err(|e| format!("cannot set host to {:?}: {}", value, e));
+        }
+        "port" => match optional.map(str::parse::<u16>) {
+            Some(Err(_)) => return Err(format!("invalid port: {}", value)),
+            Some(Ok(port)) => url.set_port(Some(port)),
+            None => url.set_port(None),
+        },
+        "path" => {
+            url.set_path(value);
+            Ok(())
+        }
+        "query" => {
+            url.set_query(optional);
+            Ok(())
+        }
+        "fragment" => {
+            url.set_fragment(optional);
+            Ok(())
+        }
+        other => {
+            return Err(format!(
+                "unknown component: {} (expected {})",
+                other,
+                SETTABLE.join(", ")
+            ));
+        }
+    };
+    result.map_err(|()| format!("cannot set {} to {:?} on {}", component, value, url))
+}
+
+/// Changes the scheme, carrying the port over: an explicit port that was the
+/// old scheme's default is dropped, so `ldap://h:389` becomes `ldaps://h` on
+/// 636, while other ports stay. Unlike `set`, it also crosses between
+/// special schemes like https and others, by parsing the URL again.
+pub fn switch_scheme(url: &mut Url, name: &str) -> Result<(), String> {
+    if url.cannot_be_a_base() {
+        return Err(format!(
+            "cannot switch the scheme of {}, which has no host",
+            url
+        ));
+    }
+    if url.port().is_some() && url.port() == scheme::default_port(url.scheme()) {
+        let _ = url.set_port(None);
+    }
+    if url.set_scheme(name).is_ok() {
+        return Ok(());
+    }
+    let rest = &url.as_str()[url.scheme().len()..];
+    *url = Url::parse(&format!("{}{}", name, rest))
+        .map_err(|e| format!("cannot switch {} to {}: {}", url, name, e))?;
+    Ok(())
+}
+
+/// Removes one component, leaving the URL unchanged if it has none.
+pub fn strip(url: &mut Url, component: &str) -> Result<(), String> {
+    match component {
+        // These only fail for URLs that cannot have credentials or a port,
+        // which therefore have none to strip.
+        "credentials" => {
+            let _ = url.set_username("");
+            let _ = url.set_password(None);
+        }
+        "port" => {
+            let _ = url.set_port(None);
+        }
+        "query" => url.set_query(None),
+        "fragment" => url.set_fragment(None),
+        other => {
+            return Err(format!(
+                "unknown component: {} (expected {})",
+                other,
+                STRIPPABLE.join(", ")
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Encodes `path` the way `url` would store it, with a leading slash and no
+/// trailing one, so prefixes compare with stored paths.
+fn encoded_prefix(url: &Url, path: &str) -> String {
+    let mut scratch = url.clone();
+    scratch.set_path(path.trim_end_matches('/'));
+    scratch.path().trim_end_matches('/').to_string()
+}
+
+/// Puts `prefix` in front of the path, so `/api` turns `/users` into
+/// `/api/users`, however either is slashed.
+pub fn prefix_path(url: &mut Url, prefix: &str) -> Result<(), String> {
+    if url.cannot_be_a_base() {
+        return Err(format!("cannot add a path prefix to {}", url));
+    }
+    let prefix = encoded_prefix(url, prefix);
+    let path = format!("{}{}", prefix, url.path());
+    url.set_path(&path);
+    Ok(())
+}
+
+/// Removes `prefix` from the start of the path if it is there as whole
+/// segments, so `/legacy` turns `/legacy/a` into `/a` but leaves
+/// `/legacy-app` alone.
+pub fn strip_path_prefix(url: &mut Url, prefix: &str) {
+    if url.cannot_be_a_base() {
+        return;
+    }
+    let prefix = encoded_prefix(url, prefix);
+    if prefix.is_empty() {
+        return;
+    }
+    let rest = match url.path().strip_prefix(prefix.as_str()) {
+        Some("") => "/".to_string(),
+        Some(rest) if rest.starts_with('/') => rest.to_string(),
+        _ => return,
+    };
+    url.set_path(&rest);
+}
+
+/// Decodes the `%XX` escapes in `text`, replacing invalid UTF-8 and leaving
+/// malformed escapes as they are.
+pub fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .filter(|h| h.iter().all(u8::is_ascii_hexdigit))
+            .and_then(|h| std::str::from_utf8(h).ok())
+            .and_then(|h| u8::from_str_radix(h, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_set_components() {
+        let mut url = Url::parse("http://example.com/a?b=c").unwrap();
+        set(&mut url, "scheme", "https").unwrap();
+        set(&mut url, "host", "api.example.com").unwrap();
+        set(&mut url, "port", "8443").unwrap();
+        set(&mut url, "path", "/v1/a b").unwrap();
+        set(&mut url, "fragment", "top").unwrap();
+        set(&mut url, "query", "").unwrap();
+        assert_eq!(url.as_str(), "https://api.example.com:8443/v1/a%20b#top");
+
+        assert_eq!(
+            set(&mut url, "port", "http"),
+            Err("invalid port: http".to_string())
+        );
+        assert!(set(&mut url, "scheme", "mailto").is_err());
+        assert!(set(&mut url, "colour", "red").is_err());
+    }
+
+    #[test]
+    fn test_switch_scheme() {
+        let switched = |url: &str, name: &str| {
+            let mut url = Url::parse(url).unwrap();
+            switch_scheme(&mut url, name).map(|()| url.to_string())
+        };
+        let ok = |url: &str| Ok(url.to_string());
+        assert_eq!(
+            switched("http://example.com:443/a", "https"),
+            ok("https://example.com/a")
+        );
+        assert_eq!(
+            switched("http://example.com:8080/a", "https"),
+            ok("https://example.com:8080/a")
+        );
+        assert_eq!(
+            switched("ldap://dir.example:389/o=x", "ldaps"),
+            ok("ldaps://dir.example/o=x")
+        );
+        assert_eq!(
+            switched("redis://cache.example:6380/0", "rediss"),
+            ok("rediss://cache.example:6380/0")
+        );
+        assert_eq!(
+            switched("https://example.com/a", "git+https"),
+            ok("git+https://example.com/a")
+        );
+        assert_eq!(
+            switched("made-up://example.com:80/a", "http"),
+            ok("http://example.com/a")
+        );
+        assert!(switched("mailto:a@example.com", "https").is_err());
+    }
+
+    #[test]
+    fn test_strip_components() {
+        let mut url = Url::parse("https://u:p@example.com:8443/a?b=c#d").unwrap();
+        for component in STRIPPABLE {
+            strip(&mut url, component).unwrap();
+        }
+        assert_eq!(url.as_str(), "https://example.com/a");
+
+        let mut url = Url::parse("mailto:a@example.com").unwrap();
+        strip(&mut url, "credentials").unwrap();
+        assert_eq!(url.as_str(), "mailto:a@example.com");
+    }
+
+    #[test]
+    fn test_path_prefixes() {
+        let mut url = Url::parse("https://example.com/legacy/users?id=1").unwrap();
+        strip_path_prefix(&mut url, "/legacy/");
+        prefix_path(&mut url, "api/v2 beta/").unwrap();
+        assert_eq!(url.as_str(), "https://example.com/api/v2%20beta/users?id=1");
+        strip_path_prefix(&mut url, "/api/v2 beta");
+        assert_eq!(url.as_str(), "https://example.com/users?id=1");
+
+        let mut url = Url::parse("https://example.com/legacy").unwrap();
+        strip_path_prefix(&mut url, "/legacy");
+        assert_eq!(url.path(), "/");
+        prefix_path(&mut url, "/api").unwrap();
+        assert_eq!(url.path(), "/api/");
+
+        let mut url = Url::parse("https://example.com/legacy-app/").unwrap();
+        strip_path_prefix(&mut url, "/legacy");
+        assert_eq!(url.path(), "/legacy-app/");
+        assert!(prefix_path(&mut Url::parse("mailto:a@b.c").unwrap(), "/x").is_err());
+    }
+}
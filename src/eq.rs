@@ -0,0 +1,149 @@
+use url::Url;
+
+use crate::normalize::{self, Steps, TrailingSlash};
+
+const EQ_HELP: &str = r#"kurl eq - test whether two URLs are equivalent
+
+USAGE:
+    kurl eq [OPTIONS] <URL1> <URL2>
+
+OPTIONS:
+        --ignore-query-order    Treat reordered query parameters as equal
+        --ignore-fragment       Ignore fragments
+        --ignore-case           Compare paths, queries, and fragments ignoring case
+        --ignore-trailing-slash Treat `/docs/` and `/docs` as equal
+        --ignore-default-port   Treat `:443` on https (or any scheme's default
+                                port) as no port
+    -h, --help                  Show this help message
+
+Exits 0 if the URLs are equivalent and 1 if not, printing nothing, so it fits
+in shell conditionals. Both URLs are normalized as `kurl normalize` does before
+comparing, so host case, percent-encoding case, and encoded unreserved
+characters never matter; the options loosen the comparison further.
+
+EXAMPLES:
+    kurl eq "https://Example.com/a%7eb" "https://example.com/a~b"
+    if kurl eq --ignore-query-order --ignore-fragment "$expected" "$actual"; then ...
+"#;
+
+/// What to ignore when comparing.
+#[derive(Debug, Default)]
+struct Ignore {
+    query_order: bool,
+    fragment: bool,
+    case: bool,
+    trailing_slash: bool,
+    default_port: bool,
+}
+
+pub fn run(args: &[String]) {
+    let mut ignore = Ignore::default();
+    let mut urls = Vec::new();
+    for arg in args {
+        match arg.as_str() {
+            "-h" | "--help" => {
+                println!("{}", EQ_HELP);
+                return;
+            }
+            "--ignore-query-order" => ignore.query_order = true,
+            "--ignore-fragment" => ignore.fragment = true,
+            "--ignore-case" => ignore.case = true,
+            "--ignore-trailing-slash" => ignore.trailing_slash = true,
+            "--ignore-default-port" => ignore.default_port = true,
+            other if other.starts_with('-') => {
+                eprintln!("Unknown option for eq: {}", other);
+                std::process::exit(1);
+            }
+            other => urls.push(other),
+        }
+    }
+
+    let [left, right] = urls[..] else {
+        eprintln!("Usage: kurl eq [OPTIONS] <URL1> <URL2>");
+        std::process::exit(1);
+    };
+    let (left, right) = (
+        crate::parse_url_or_exit(left),
+        crate::parse_url_or_exit(right),
+    );
+    if !equivalent(&left, &right, &ignore) {
+        std::process::exit(1);
+    }
+}
+
+fn equivalent(left: &Url, right: &Url, ignore: &Ignore) -> bool {
+    canonical(left, ignore) == canonical(right, ignore)
+}
+
+/// The form of `url` that equivalent URLs share.
+fn canonical(url: &Url, ignore: &Ignore) -> String {
+    let steps = Steps {
+        default_port: ignore.default_port,
+        trailing_slash: ignore.trailing_slash.then_some(TrailingSlash::Remove),
+        force_trailing_slash: true,
+        ..Default::default()
+    };
+    let mut url = normalize::normalize(url, &steps);
+    if ignore.query_order {
+        crate::query::sort(&mut url);
+    }
+    if ignore.fragment {
+        url.set_fragment(None);
+    }
+    if ignore.case {
+        url.as_str().to_lowercase()
+    } else {
+        url.into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn eq(left: &str, right: &str, ignore: &Ignore) -> bool {
+        equivalent(
+            &Url::parse(left).unwrap(),
+            &Url::parse(right).unwrap(),
+            ignore,
+        )
+    }
+
+    #[test]
+    fn test_equivalent() {
+        let strict = Ignore::default();
+        assert!(eq(
+            "https://Example.com/a%7eb%2f",
+            "https://example.com:443/a~b%2F",
+            &strict
+        ));
+        assert!(eq("foo://A.com/x", "foo://a.com/x", &strict));
+        assert!(!eq("foo://a.com:99/", "foo://a.com/", &strict));
+        assert!(!eq(
+            "https://a.com/?x=1&y=2",
+            "https://a.com/?y=2&x=1",
+            &strict
+        ));
+        assert!(!eq("https://a.com/#a", "https://a.com/", &strict));
+        assert!(!eq("https://a.com/Docs", "https://a.com/docs", &strict));
+        assert!(!eq("https://a.com/docs/", "https://a.com/docs", &strict));
+        assert!(!eq("ssh://a.com:22/", "ssh://a.com/", &strict));
+
+        let loose = Ignore {
+            query_order: true,
+            fragment: true,
+            case: true,
+            trailing_slash: true,
+            default_port: true,
+        };
+        assert!(eq(
+            "https://a.com/?x=1&y=2",
+            "https://a.com/?y=2&x=1",
+            &loose
+        ));
+        assert!(eq("https://a.com/#a", "https://a.com/", &loose));
+        assert!(eq("https://a.com/Docs/", "https://a.com/docs", &loose));
+        assert!(eq("ssh://a.com:22/", "ssh://a.com/", &loose));
+        assert!(!eq("https://a.com/?x=1", "https://a.com/?x=2", &loose));
+    }
+}
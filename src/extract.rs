@@ -0,0 +1,417 @@
+use std::borrow::Cow;
+use std::io::{self, IsTerminal, Write};
+use url::Url;
+
+use crate::json::Value;
+use crate::{OutputOptions, Records, config, html, lint};
+
+const EXTRACT_HELP: &str = r#"kurl extract - find URLs in free-form text
+
+USAGE:
+    <text> | kurl extract [OPTIONS]
+    kurl extract [OPTIONS] <FILE>...
+
+OPTIONS:
+    -p, --parse         Parse each URL and print its components
+    -j, --json          Print each parsed URL as a JSON line (implies --parse)
+        --id-field <KIND>
+                        Add an `id` to each parsed record: uuid, hash, or seq
+        --lint          Check each URL and report what is wrong with it
+        --diagnostics   Print one `file:line:column: severity: message` line
+                        per lint finding, or a JSON object with --json
+                        (implies --lint)
+        --allow-scheme <LIST>
+                        Report URLs whose scheme is not in the comma-separated
+                        list when linting
+        --rule <NAME=SEVERITY>
+                        Set a lint rule to error, warning, or off (see `kurl
+                        lint --list-rules`)
+    -h, --help          Show this help message
+
+Meta refresh tags in piped HTML are reported with their delay as a
+`refresh_delay` field.
+
+EXAMPLES:
+    cat server.log | kurl extract
+    pbpaste | kurl extract --json
+    curl -s https://example.com | kurl extract --json
+    kurl extract --diagnostics README.md docs/*.md
+"#;
+
+/// Text to extract from, with the name diagnostics refer to it by.
+struct Source {
+    name: String,
+    text: String,
+}
+
+pub fn run(args: &[String]) {
+    let mut parse = false;
+    let mut lint = false;
+    let mut lint_options = lint::LintOptions::from_config(&config::load());
+    let mut diagnostics = false;
+    let mut output = OutputOptions::default();
+    let mut files = Vec::new();
+
+    let mut rest = args.iter();
+    while let Some(arg) = rest.next() {
+        match arg.as_str() {
+            "-h" | "--help" => {
+                println!("{}", EXTRACT_HELP);
+                return;
+            }
+            "-p" | "--parse" => parse = true,
+            "-j" | "--json" => {
+                parse = true;
+                output.json_output = true;
+            }
+            "--lint" => lint = true,
+            "--diagnostics" => diagnostics = true,
+            other if output.accept(other, &mut rest) => {}
+            other if lint_options.accept(other, &mut rest) => {}
+            other if other.starts_with('-') => {
+                eprintln!("Unknown option for extract: {}", other);
+                std::process::exit(1);
+            }
+            file => files.push(file),
+        }
+    }
+
+    let sources: Vec<Source> = if files.is_empty() {
+        if io::stdin().is_terminal() {
+            eprintln!("Usage: kurl extract [--parse] [--json] < text");
+            eprintln!("   or: kurl extract [--parse] [--json] <FILE>...");
+            std::process::exit(1);
+        }
+        vec![Source {
+            name: "<stdin>".to_string(),
+            text: crate::read_stdin(),
+        }]
+    } else {
+        files
+            .iter()
+            .map(|&file| Source {
+                name: file.to_string(),
+                text: std::fs::read_to_string(file).unwrap_or_else(|e| {
+                    eprintln!("Failed to read {}: {}", file, e);
+                    std::process::exit(1);
+                }),
+            })
+            .collect()
+    };
+
+    let mut out = io::stdout().lock();
+    if diagnostics {
+        for source in &sources {
+            let _ = write_diagnostics(&mut out, source, &lint_options, output.json_output);
+        }
+        return;
+    }
+
+    let lint = lint.then_some(&lint_options);
+    let mut records = Records::new(&mut out, &output);
+    for source in &sources {
+        let _ = write_extracted(&mut records, &source.text, parse, lint);
+    }
+}
+
+fn write_extracted<W: Write>(
+    records: &mut Records<'_, W>,
+    text: &str,
+    parse: bool,
+    lint: Option<&lint::LintOptions>,
+) -> io::Result<()> {
+    for found in collect(text) {
+        let findings = match lint {
+            Some(options) => lint::check(&found.url, options),
+            None => Vec::new(),
+        };
+        let Ok(url) = Url::parse(&found.url) else {
+            // Without --parse there is no record to build, so a lint report
+            // can still say why the URL is broken.
+            if lint.is_some() && !parse {
+                writeln!(records.writer, "{}", found.url)?;
+                for finding in &findings {
+                    writeln!(records.writer, "  {}", finding)?;
+                }
+            }
+            continue;
+        };
+
+        let mut extra = Vec::new();
+        if let Some(delay) = found.refresh_delay {
+            extra.push(("refresh_delay", Value::from(delay)));
+        }
+        if lint.is_some() {
+            let lints = findings.iter().map(|f| Value::from(f.to_string()));
+            extra.push(("lint", Value::Array(lints.collect())));
+        }
+
+        if parse {
+            records.write(&url, &extra)?;
+        } else {
+            writeln!(records.writer, "{}", found.url)?;
+            for finding in &findings {
+                writeln!(records.writer, "  {}", finding)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Writes every lint finding in `source` with its line and column, in the
+/// format compilers use so that editors can jump to each one.
+fn write_diagnostics<W: Write>(
+    writer: &mut W,
+    source: &Source,
+    options: &lint::LintOptions,
+    json: bool,
+) -> io::Result<()> {
+    for found in collect(&source.text) {
+        let before = &source.text[..found.offset];
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+
+        for finding in lint::check(&found.url, options) {
+            let diagnostic = lint::Diagnostic {
+                file: &source.name,
+                line: before.matches('\n').count() + 1,
+                column: before[line_start..].chars().count() + 1,
+                url: &found.url,
+                finding,
+            };
+            if json {
+                diagnostic.to_value().write(writer)?;
+                writeln!(writer)?;
+            } else {
+                writeln!(writer, "{}", diagnostic)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+struct Found<'a> {
+    offset: usize,
+    url: Cow<'a, str>,
+    refresh_delay: Option<u32>,
+}
+
+/// Collects URL tokens and meta refresh targets in source order. A URL that
+/// sits inside a refresh tag's `content` is reported once, as the refresh.
+fn collect(text: &str) -> Vec<Found<'_>> {
+    let refreshes = html::meta_refreshes(text);
+
+    let mut found: Vec<Found> = find_urls(text)
+        .into_iter()
+        .filter(|&(offset, _)| {
+            !refreshes
+                .iter()
+                .any(|r| r.span.0 <= offset && offset < r.span.1)
+        })
+        .map(|(offset, token)| Found {
+            offset,
+            url: Cow::Borrowed(token),
+            refresh_delay: None,
+        })
+        .collect();
+
+    for refresh in refreshes {
+        if let Some(url) = refresh.url {
+            found.push(Found {
+                offset: refresh.span.0,
+                url: Cow::Owned(url),
+                refresh_delay: Some(refresh.delay),
+            });
+        }
+    }
+
+    found.sort_by_key(|f| f.offset);
+    found
+}
+
+/// Finds every `scheme://...` token in `text`, returning each with its byte offset.
+///
+/// Trailing punctuation is dropped, as are closing brackets that have no matching
+/// opening bracket inside the token, so `(see https://a.example/x).` yields
+/// `https://a.example/x` while `https://en.wikipedia.org/wiki/Rust_(film)` is kept whole.
+pub fn find_urls(text: &str) -> Vec<(usize, &str)> {
+    let bytes = text.as_bytes();
+    let mut found = Vec::new();
+    let mut pos = 0;
+
+    while let Some(offset) = text[pos..].find("://") {
+        let sep = pos + offset;
+        let rest = sep + 3;
+
+        let mut start = sep;
+        while start > 0 && is_scheme_byte(bytes[start - 1]) {
+            start -= 1;
+        }
+        while start < sep && !bytes[start].is_ascii_alphabetic() {
+            start += 1;
+        }
+
+        let end = text[rest..]
+            .find(is_terminator)
+            .map_or(text.len(), |i| rest + i);
+        let token = trim_trailing(&text[start..end]);
+
+        if start < sep && token.len() > rest - start {
+            found.push((start, token));
+        }
+        pos = end.max(rest);
+    }
+
+    found
+}
+
+fn is_scheme_byte(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || matches!(b, b'+' | b'-' | b'.')
+}
+
+fn is_terminator(c: char) -> bool {
+    c.is_whitespace() || c.is_control() || matches!(c, '<' | '>' | '"' | '`')
+}
+
+fn trim_trailing(mut token: &str) -> &str {
+    while let Some(last) = token.chars().last() {
+        let trim = match last {
+            '.' | ',' | ';' | ':' | '!' | '?' | '\'' | '*' => true,
+            ')' => unbalanced(token, '(', ')'),
+            ']' => unbalanced(token, '[', ']'),
+            '}' => unbalanced(token, '{', '}'),
+            _ => false,
+        };
+        if !trim {
+            break;
+        }
+        token = &token[..token.len() - last.len_utf8()];
+    }
+    token
+}
+
+fn unbalanced(token: &str, open: char, close: char) -> bool {
+    token.matches(close).count() > token.matches(open).count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn urls(text: &str) -> Vec<&str> {
+        find_urls(text).into_iter().map(|(_, u)| u).collect()
+    }
+
+    fn json_options() -> OutputOptions {
+        OutputOptions {
+            json_output: true,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_find_urls_in_prose() {
+        let text = "See https://example.com/a, and also http://foo.org/b?x=1.";
+        assert_eq!(
+            urls(text),
+            vec!["https://example.com/a", "http://foo.org/b?x=1"]
+        );
+    }
+
+    #[test]
+    fn test_find_urls_wrapping_brackets() {
+        let text = "(https://example.com/x) <https://example.com/y> [https://example.com/z]";
+        assert_eq!(
+            urls(text),
+            vec![
+                "https://example.com/x",
+                "https://example.com/y",
+                "https://example.com/z"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_find_urls_keeps_balanced_parens() {
+        let text = "read (https://en.wikipedia.org/wiki/Rust_(film)).";
+        assert_eq!(
+            urls(text),
+            vec!["https://en.wikipedia.org/wiki/Rust_(film)"]
+        );
+    }
+
+    #[test]
+    fn test_find_urls_markdown_link() {
+        let text = "[docs](https://docs.rs/url) and **https://crates.io**";
+        assert_eq!(urls(text), vec!["https://docs.rs/url", "https://crates.io"]);
+    }
+
+    #[test]
+    fn test_find_urls_offsets_and_empty_tokens() {
+        let found = find_urls("x ftp://h/f :// http://");
+        assert_eq!(found, vec![(2, "ftp://h/f")]);
+    }
+
+    fn extract_json(text: &str, lint: bool) -> String {
+        let options = json_options();
+        let lint_options = lint::LintOptions::default();
+        let mut output = Vec::new();
+        let mut records = Records::new(&mut output, &options);
+        write_extracted(&mut records, text, true, lint.then_some(&lint_options)).unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn test_write_extracted_json_lines() {
+        let output_str = extract_json("a https://a.com b https://b.com", false);
+
+        let lines: Vec<&str> = output_str.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].contains("\"host\":\"a.com\""));
+        assert!(lines[1].contains("\"host\":\"b.com\""));
+    }
+
+    #[test]
+    fn test_write_extracted_meta_refresh() {
+        let html = r#"<a href="https://a.com/">a</a>
+<meta http-equiv="refresh" content="5; url='https://b.com/next'">"#;
+        let output_str = extract_json(html, false);
+        let lines: Vec<&str> = output_str.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(!lines[0].contains("refresh_delay"));
+        assert!(lines[1].contains("\"host\":\"b.com\""));
+        assert!(lines[1].contains("\"path\":\"/next\""));
+        assert!(lines[1].ends_with(",\"refresh_delay\":5}"));
+    }
+
+    #[test]
+    fn test_write_extracted_lint_field() {
+        let output_str = extract_json("ok https://a.com/ bad http://b.com/", true);
+        let lines: Vec<&str> = output_str.lines().collect();
+        assert!(lines[0].ends_with(",\"lint\":[]}"));
+        assert!(lines[1].ends_with(
+            r#","lint":["warning: uses unencrypted http; prefer https [insecure-scheme]"]}"#
+        ));
+    }
+
+    #[test]
+    fn test_write_diagnostics_positions() {
+        let source = Source {
+            name: "notes.md".to_string(),
+            text: "fine https://a.com/\n  é http://b.com/ and\nhttps://:x@[::1/".to_string(),
+        };
+        let mut output = Vec::new();
+        write_diagnostics(&mut output, &source, &Default::default(), false).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "notes.md:2:5: warning: uses unencrypted http; prefer https [insecure-scheme]\n\
+             notes.md:3:1: error: cannot be parsed: invalid IPv6 address [invalid-url]\n"
+        );
+
+        let mut output = Vec::new();
+        write_diagnostics(&mut output, &source, &Default::default(), true).unwrap();
+        let first = String::from_utf8(output).unwrap();
+        assert!(first.starts_with(
+            r#"{"file":"notes.md","line":2,"column":5,"severity":"warning","message":"#
+        ));
+    }
+}
@@ -0,0 +1,217 @@
+use std::io::{self, IsTerminal, Write};
+
+const DEFANG_HELP: &str = r#"kurl defang - make URLs safe to paste into reports
+
+USAGE:
+    kurl defang <URL>...
+    <lines> | kurl defang
+
+OPTIONS:
+    -h, --help          Show this help message
+
+Rewrites http and https as hxxp and hxxps, ftp as fxp, and the dots of the
+host (or of a bare domain, IP address, or email address) as `[.]`, so the
+URL can't be clicked or auto-linked. Text that is already defanged comes out
+the same, and `kurl refang` undoes it.
+
+EXAMPLES:
+    kurl defang https://login.evil.example/reset?id=1
+    cat iocs.txt | kurl defang
+"#;
+
+const REFANG_HELP: &str = r#"kurl refang - restore defanged URLs
+
+USAGE:
+    kurl refang <TEXT>...
+    <lines> | kurl refang
+
+OPTIONS:
+    -h, --help          Show this help message
+
+Undoes the common ways of defanging: hxxp, hXXp, and h**p schemes, fxp for
+ftp, `[.]`, `(.)`, `{.}`, `[dot]`, and `\.` for dots, `[:]` and `[://]`,
+`[/]`, and `[@]`, `(@)`, and `[at]` for `@`, in any case. Everything else is
+left alone, so whole lines of a threat report can be refanged and piped into
+kurl or `kurl extract`.
+
+EXAMPLES:
+    kurl refang 'hxxps://login[.]evil[.]example/reset'
+    cat report.txt | kurl refang | kurl extract --parse
+"#;
+
+/// What `kurl defang` and `kurl refang` do.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Mode {
+    Defang,
+    Refang,
+}
+
+/// Defanged spellings and what they stand for. Longer spellings come first,
+/// so `[://]` isn't taken for `[:]`.
+const SPELLINGS: &[(&str, &str)] = &[
+    ("[://]", "://"),
+    ("[dot]", "."),
+    ("(dot)", "."),
+    ("{dot}", "."),
+    ("[.]", "."),
+    ("(.)", "."),
+    ("{.}", "."),
+    ("\\.", "."),
+    ("[:]", ":"),
+    ("[/]", "/"),
+    ("[at]", "@"),
+    ("(at)", "@"),
+    ("[@]", "@"),
+    ("(@)", "@"),
+    ("{@}", "@"),
+];
+
+/// Defanged schemes, matched at the start of a word before `:` or `[:`.
+const SCHEMES: &[(&str, &str)] = &[("hxxp", "http"), ("h**p", "http"), ("fxp", "ftp")];
+
+pub fn run(args: &[String], mode: Mode) {
+    let (help, name) = match mode {
+        Mode::Defang => (DEFANG_HELP, "defang"),
+        Mode::Refang => (REFANG_HELP, "refang"),
+    };
+    let mut inputs = Vec::new();
+    for arg in args {
+        match arg.as_str() {
+            "-h" | "--help" => {
+                println!("{}", help);
+                return;
+            }
+            other if other.starts_with("--") => {
+                eprintln!("Unknown option for {}: {}", name, other);
+                std::process::exit(1);
+            }
+            other => inputs.push(other),
+        }
+    }
+
+    let buffer;
+    if inputs.is_empty() {
+        if io::stdin().is_terminal() {
+            eprintln!("Usage: kurl {} <URL>...", name);
+            std::process::exit(1);
+        }
+        buffer = crate::read_stdin();
+        inputs = buffer.lines().collect();
+    }
+
+    let mut out = io::stdout().lock();
+    for input in inputs {
+        let converted = match mode {
+            Mode::Defang => defang(input),
+            Mode::Refang => refang(input),
+        };
+        if writeln!(out, "{}", converted).is_err() {
+            return;
+        }
+    }
+}
+
+/// Defangs the scheme and the dots of the host of `url`, refanging it first
+/// so that defanging twice changes nothing.
+fn defang(url: &str) -> String {
+    let url = refang(url);
+    let (scheme, rest) = match url.split_once("://") {
+        Some((scheme, rest))
+            if scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+                && scheme
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || "+-.".contains(c)) =>
+        {
+            (Some(scheme), rest)
+        }
+        _ => (None, url.as_str()),
+    };
+
+    let mut defanged = String::with_capacity(url.len() + 8);
+    if let Some(scheme) = scheme {
+        let lowercase = scheme.to_ascii_lowercase();
+        defanged.push_str(match lowercase.as_str() {
+            "http" => "hxxp",
+            "https" => "hxxps",
+            "ftp" => "fxp",
+            "ftps" => "fxps",
+            _ => scheme,
+        });
+        defanged.push_str("://");
+    }
+    let end = rest.find(['/', '?', '#']).unwrap_or(rest.len());
+    defanged.push_str(&rest[..end].replace('.', "[.]"));
+    defanged.push_str(&rest[end..]);
+    defanged
+}
+
+/// Replaces every defanged spelling in `text` with what it stands for.
+fn refang(text: &str) -> String {
+    let mut refanged = String::with_capacity(text.len());
+    let mut rest = text;
+    'scan: while let Some(c) = rest.chars().next() {
+        let at_word_start = !refanged.ends_with(|c: char| c.is_alphanumeric());
+        for (spelling, replacement) in SCHEMES {
+            let Some(after) = strip_prefix_ignore_case(rest, spelling) else {
+                continue;
+            };
+            let after_s = strip_prefix_ignore_case(after, "s").unwrap_or(after);
+            if at_word_start && (after_s.starts_with(':') || after_s.starts_with("[:")) {
+                refanged.push_str(replacement);
+                rest = after;
+                continue 'scan;
+            }
+        }
+        for (spelling, replacement) in SPELLINGS {
+            if let Some(after) = strip_prefix_ignore_case(rest, spelling) {
+                refanged.push_str(replacement);
+                rest = after;
+                continue 'scan;
+            }
+        }
+        refanged.push(c);
+        rest = &rest[c.len_utf8()..];
+    }
+    refanged
+}
+
+fn strip_prefix_ignore_case<'t>(text: &'t str, prefix: &str) -> Option<&'t str> {
+    let head = text.get(..prefix.len())?;
+    head.eq_ignore_ascii_case(prefix)
+        .then(|| &text[prefix.len()..])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_defang() {
+        assert_eq!(
+            defang("https://login.evil.example:8443/a.php?x=1.2"),
+            "hxxps://login[.]evil[.]example:8443/a.php?x=1.2"
+        );
+        assert_eq!(defang("HTTP://10.0.0.1/"), "hxxp://10[.]0[.]0[.]1/");
+        assert_eq!(defang("ftp://files.example/"), "fxp://files[.]example/");
+        assert_eq!(defang("evil.example/path.html"), "evil[.]example/path.html");
+        assert_eq!(defang("bob@mail.example"), "bob@mail[.]example");
+        assert_eq!(
+            defang("hxxps://login[.]evil[.]example/"),
+            "hxxps://login[.]evil[.]example/"
+        );
+    }
+
+    #[test]
+    fn test_refang() {
+        assert_eq!(
+            refang("hXXps[://]login[.]evil(.)example{dot}com/a"),
+            "https://login.evil.example.com/a"
+        );
+        assert_eq!(
+            refang("see h**p[:]//bob[at]mail\\.example and fxp://files[DOT]example"),
+            "see http://bob@mail.example and ftp://files.example"
+        );
+        assert_eq!(refang("shxxp: fxps"), "shxxp: fxps");
+        assert_eq!(refang("café [.] fine"), "café . fine");
+    }
+}
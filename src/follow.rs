@@ -0,0 +1,207 @@
+use crate::alert::{Alert, Alerts};
+use crate::{OutputOptions, ParseOptions, Records, json};
+use std::fs::File;
+use std::io::{self, BufRead, Read, Seek, SeekFrom, Write};
+use std::time::Duration;
+
+/// How long to wait before checking a followed file for new data.
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Prints each URL as its line arrives, flushing after every record. Reads
+/// stdin until it closes, or follows `path` forever from its current end.
+pub fn run(path: Option<&str>, parsing: &ParseOptions, output: &OutputOptions, mut alerts: Alerts) {
+    let mut out = io::stdout().lock();
+    let mut records = Records::new(&mut out, output);
+    let mut line = 0;
+
+    // Stop quietly once stdout goes away, e.g. when piped into `head`.
+    let _ = match path {
+        Some(path) => {
+            let mut follower = Follower::open(path).unwrap_or_else(|e| {
+                eprintln!("Failed to open {}: {}", path, e);
+                std::process::exit(1);
+            });
+            loop {
+                let lines = follower.poll().unwrap_or_else(|e| {
+                    eprintln!("Failed to read {}: {}", path, e);
+                    std::process::exit(1);
+                });
+                if lines.is_empty() {
+                    std::thread::sleep(POLL_INTERVAL);
+                }
+                if let Err(e) = lines.iter().try_for_each(|text| {
+                    line += 1;
+                    write_line(&mut records, parsing, &mut alerts, line, text)
+                }) {
+                    break Err(e);
+                }
+            }
+        }
+        None => io::stdin().lock().lines().try_for_each(|text| {
+            line += 1;
+            write_line(&mut records, parsing, &mut alerts, line, &text?)
+        }),
+    };
+}
+
+/// Writes the URL on one input line, with an `alerts` field if it raises
+/// any, reporting (but not stopping at) lines that don't parse.
+fn write_line<W: Write>(
+    records: &mut Records<'_, W>,
+    parsing: &ParseOptions,
+    alerts: &mut Alerts,
+    line: usize,
+    text: &str,
+) -> io::Result<()> {
+    let text = text.trim();
+    if text.is_empty() {
+        return Ok(());
+    }
+    match parsing.parse(text) {
+        Ok((url, mut extra)) => {
+            if parsing.filter(&url, &mut extra) {
+                return Ok(());
+            }
+            let raised = alerts.check(&url, &parsing.redact_keys);
+            if raised.is_empty() && alerts.only {
+                return Ok(());
+            }
+            // The `--alert-exec` command hears the id the record will have.
+            let id = records.next_id(&url);
+            if !raised.is_empty() {
+                let tags = raised.iter().map(Alert::to_value).collect();
+                extra.push(("alerts", json::Value::Array(tags)));
+                alerts.run_exec(&url, id.as_deref(), &raised);
+            }
+            records.write_with_id(&url, id, &extra)?;
+            records.writer.flush()
+        }
+        Err(e) => {
+            eprintln!("Failed to parse URL on line {}: {}", line, e);
+            Ok(())
+        }
+    }
+}
+
+/// Reads lines appended to a file, like `tail -f`.
+struct Follower {
+    file: File,
+    pos: u64,
+    /// The start of a line whose newline hasn't been written yet.
+    partial: String,
+}
+
+impl Follower {
+    fn open(path: &str) -> io::Result<Follower> {
+        let mut file = File::open(path)?;
+        let pos = file.seek(SeekFrom::End(0))?;
+        Ok(Follower {
+            file,
+            pos,
+            partial: String::new(),
+        })
+    }
+
+    /// Returns the lines completed since the last poll, starting over if the
+    /// file was truncated, as when a log is rotated in place.
+    fn poll(&mut self) -> io::Result<Vec<String>> {
+        if self.file.metadata()?.len() < self.pos {
+            self.pos = self.file.seek(SeekFrom::Start(0))?;
+            self.partial.clear();
+        }
+
+        let mut bytes = Vec::new();
+        self.pos += self.file.read_to_end(&mut bytes)? as u64;
+        self.partial.push_str(&String::from_utf8_lossy(&bytes));
+
+        let Some(end) = self.partial.rfind('\n') else {
+            return Ok(Vec::new());
+        };
+        let rest = self.partial.split_off(end + 1);
+        let complete = std::mem::replace(&mut self.partial, rest);
+        Ok(complete.lines().map(str::to_string).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_follower_reads_appended_lines() {
+        let path = std::env::temp_dir().join(format!("kurl-follow-{}.log", std::process::id()));
+        std::fs::write(&path, "https://old.example/\n").unwrap();
+        let mut follower = Follower::open(path.to_str().unwrap()).unwrap();
+        let mut log = std::fs::OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .unwrap();
+
+        assert!(follower.poll().unwrap().is_empty());
+        write!(log, "https://a.example/\nhttps://b.").unwrap();
+        assert_eq!(follower.poll().unwrap(), vec!["https://a.example/"]);
+        writeln!(log, "example/").unwrap();
+        assert_eq!(follower.poll().unwrap(), vec!["https://b.example/"]);
+
+        std::fs::write(&path, "https://c.example/\n").unwrap();
+        assert_eq!(follower.poll().unwrap(), vec!["https://c.example/"]);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_write_line_skips_bad_urls() {
+        let options = OutputOptions {
+            json_output: true,
+            ..Default::default()
+        };
+        let mut output = Vec::new();
+        let mut records = Records::new(&mut output, &options);
+        let parsing = ParseOptions::default();
+        let mut alerts = Alerts::default();
+        write_line(&mut records, &parsing, &mut alerts, 1, "not a url").unwrap();
+        write_line(&mut records, &parsing, &mut alerts, 2, "  ").unwrap();
+        write_line(
+            &mut records,
+            &parsing,
+            &mut alerts,
+            3,
+            "https://example.com/",
+        )
+        .unwrap();
+
+        let output_str = String::from_utf8(output).unwrap();
+        assert_eq!(output_str.lines().count(), 1);
+        assert!(output_str.starts_with(r#"{"scheme":"https","host":"example.com""#));
+    }
+
+    #[test]
+    fn test_write_line_tags_alerts() {
+        let options = OutputOptions {
+            json_output: true,
+            ..Default::default()
+        };
+        let mut output = Vec::new();
+        let mut records = Records::new(&mut output, &options);
+        let parsing = ParseOptions::default();
+        let mut alerts = Alerts::default();
+        let args = ["--alert-secrets".to_string(), "--alerts-only".to_string()];
+        for arg in &args {
+            assert!(alerts.accept(arg, &mut [].iter()));
+        }
+        write_line(&mut records, &parsing, &mut alerts, 1, "https://a.example/").unwrap();
+        write_line(
+            &mut records,
+            &parsing,
+            &mut alerts,
+            2,
+            "https://a.example/?api_key=k",
+        )
+        .unwrap();
+
+        let output_str = String::from_utf8(output).unwrap();
+        assert_eq!(output_str.lines().count(), 1);
+        assert!(
+            output_str.ends_with("\"alerts\":[{\"alert\":\"secret\",\"detail\":\"api_key\"}]}\n")
+        );
+    }
+}
@@ -0,0 +1,215 @@
+/// A shell-style glob: `*` matches any run of characters but the separator,
+/// `**` any run at all, `?` one character but the separator, `[a-z]` and
+/// `[!0-9]` one character in or out of a class, and `{a,b}` either
+/// alternative. Without a separator, `*` and `**` are the same.
+#[derive(Debug, Clone)]
+pub struct Glob {
+    /// The pattern with its braces expanded, one token list per alternative.
+    alternatives: Vec<Vec<Token>>,
+    separator: Option<char>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Char(char),
+    /// `?`
+    One,
+    /// `*`
+    Star,
+    /// `**`
+    DoubleStar,
+    Class {
+        ranges: Vec<(char, char)>,
+        negated: bool,
+    },
+}
+
+impl Glob {
+    pub fn new(pattern: &str, separator: Option<char>) -> Result<Glob, String> {
+        let alternatives = expand(pattern)?
+            .iter()
+            .map(|pattern| tokens(pattern))
+            .collect::<Result<_, _>>()?;
+        Ok(Glob {
+            alternatives,
+            separator,
+        })
+    }
+
+    pub fn matches(&self, text: &str) -> bool {
+        let text: Vec<char> = text.chars().collect();
+        self.alternatives
+            .iter()
+            .any(|tokens| self.match_at(tokens, &text))
+    }
+
+    fn match_at(&self, tokens: &[Token], text: &[char]) -> bool {
+        let Some((token, rest)) = tokens.split_first() else {
+            return text.is_empty();
+        };
+        let crosses = |c: &char| Some(*c) == self.separator;
+        match token {
+            Token::Star | Token::DoubleStar => {
+                let anything = *token == Token::DoubleStar || self.separator.is_none();
+                // `a/**/b` matches `a/b` too, as in .gitignore.
+                if *token == Token::DoubleStar
+                    && let Some(separator) = self.separator
+                    && rest.first() == Some(&Token::Char(separator))
+                    && self.match_at(&rest[1..], text)
+                {
+                    return true;
+                }
+                let end = if anything {
+                    text.len()
+                } else {
+                    text.iter().position(crosses).unwrap_or(text.len())
+                };
+                (0..=end).any(|skip| self.match_at(rest, &text[skip..]))
+            }
+            Token::One => {
+                text.first().is_some_and(|c| !crosses(c)) && self.match_at(rest, &text[1..])
+            }
+            Token::Char(expected) => {
+                text.first() == Some(expected) && self.match_at(rest, &text[1..])
+            }
+            Token::Class { ranges, negated } => text.first().is_some_and(|&c| {
+                let inside = ranges.iter().any(|&(low, high)| low <= c && c <= high);
+                inside != *negated && !crosses(&c) && self.match_at(rest, &text[1..])
+            }),
+        }
+    }
+}
+
+/// Expands the `{a,b}` groups of `pattern`, nested ones included, into every
+/// pattern they stand for.
+fn expand(pattern: &str) -> Result<Vec<String>, String> {
+    let Some(open) = pattern.find('{') else {
+        if pattern.contains('}') {
+            return Err(format!("unmatched }} in {}", pattern));
+        }
+        return Ok(vec![pattern.to_string()]);
+    };
+    let mut depth = 0;
+    let mut close = None;
+    let mut commas = Vec::new();
+    for (i, c) in pattern[open..].char_indices() {
+        match c {
+            '{' => depth += 1,
+            '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    close = Some(open + i);
+                    break;
+                }
+            }
+            ',' if depth == 1 => commas.push(open + i),
+            _ => {}
+        }
+    }
+    let Some(close) = close else {
+        return Err(format!("unclosed {{ in {}", pattern));
+    };
+    let mut bounds = vec![open];
+    bounds.extend(commas);
+    bounds.push(close);
+    let mut patterns = Vec::new();
+    for window in bounds.windows(2) {
+        let choice = &pattern[window[0] + 1..window[1]];
+        let joined = format!("{}{}{}", &pattern[..open], choice, &pattern[close + 1..]);
+        patterns.extend(expand(&joined)?);
+    }
+    Ok(patterns)
+}
+
+fn tokens(pattern: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = pattern.chars().peekable();
+    while let Some(c) = chars.next() {
+        let token = match c {
+            '*' if chars.peek() == Some(&'*') => {
+                chars.next();
+                Token::DoubleStar
+            }
+            '*' => Token::Star,
+            '?' => Token::One,
+            '\\' => Token::Char(chars.next().unwrap_or('\\')),
+            '[' => {
+                let negated = chars.next_if(|&c| c == '!' || c == '^').is_some();
+                let mut ranges = Vec::new();
+                loop {
+                    match chars.next() {
+                        None => return Err(format!("unclosed [ in {}", pattern)),
+                        Some(']') if !ranges.is_empty() => break,
+                        Some(low) => {
+                            let high = match chars.next_if_eq(&'-') {
+                                Some(_) => match chars.next() {
+                                    Some(']') | None => {
+                                        ranges.push((low, low));
+                                        ranges.push(('-', '-'));
+                                        break;
+                                    }
+                                    Some(high) => high,
+                                },
+                                None => low,
+                            };
+                            ranges.push((low, high));
+                        }
+                    }
+                }
+                Token::Class { ranges, negated }
+            }
+            c => Token::Char(c),
+        };
+        tokens.push(token);
+    }
+    Ok(tokens)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn host(pattern: &str, text: &str) -> bool {
+        Glob::new(pattern, None).unwrap().matches(text)
+    }
+
+    fn path(pattern: &str, text: &str) -> bool {
+        Glob::new(pattern, Some('/')).unwrap().matches(text)
+    }
+
+    #[test]
+    fn test_host_globs() {
+        assert!(host("*.example.com", "api.example.com"));
+        assert!(host("*.example.com", "a.b.example.com"));
+        assert!(!host("*.example.com", "example.com"));
+        assert!(!host("*.example.com", "example.com.evil.net"));
+        assert!(host("{www,api}.example.{com,net}", "api.example.net"));
+        assert!(!host("{www,api}.example.{com,net}", "cdn.example.net"));
+        assert!(host("cdn?.example.com", "cdn1.example.com"));
+        assert!(host("node-[0-9][0-9].internal", "node-07.internal"));
+        assert!(!host("node-[!0-9]*", "node-7"));
+    }
+
+    #[test]
+    fn test_path_globs() {
+        assert!(path("/api/**", "/api/v1/users"));
+        assert!(path("/api/**", "/api/"));
+        assert!(!path("/api/**", "/apis/v1"));
+        assert!(path("/api/*", "/api/users"));
+        assert!(!path("/api/*", "/api/v1/users"));
+        assert!(path("/api/**/users", "/api/users"));
+        assert!(path("/api/**/users", "/api/v1/admin/users"));
+        assert!(path("/static/*.{js,css}", "/static/app.css"));
+        assert!(!path("/static/*.{js,css}", "/static/app.json"));
+        assert!(path("/a\\*b", "/a*b"));
+        assert!(!path("/a\\*b", "/axb"));
+    }
+
+    #[test]
+    fn test_errors() {
+        assert!(Glob::new("/a/[bc", Some('/')).is_err());
+        assert!(Glob::new("{a,b", None).is_err());
+        assert!(Glob::new("a}", None).is_err());
+        assert!(Glob::new("[]]", None).unwrap().matches("]"));
+    }
+}
@@ -0,0 +1,111 @@
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+use url::Url;
+
+use crate::json::Value;
+use crate::psl;
+
+/// A field that `--group-by` can bucket URLs on.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GroupBy {
+    Site,
+}
+
+impl GroupBy {
+    pub fn parse(name: &str) -> Option<GroupBy> {
+        match name {
+            "site" => Some(GroupBy::Site),
+            _ => None,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            GroupBy::Site => "site",
+        }
+    }
+
+    /// Returns the group key for `url`; URLs with an opaque origin share "null".
+    fn key(self, url: &Url) -> String {
+        match self {
+            GroupBy::Site => psl::site(url).unwrap_or_else(|| "null".to_string()),
+        }
+    }
+}
+
+/// Writes one entry per group, sorted by key, listing its member URLs in input order.
+pub fn write_groups<W: Write>(
+    writer: &mut W,
+    urls: &[Url],
+    group_by: GroupBy,
+    json_output: bool,
+) -> io::Result<()> {
+    let mut groups: BTreeMap<String, Vec<&Url>> = BTreeMap::new();
+    for url in urls {
+        groups.entry(group_by.key(url)).or_default().push(url);
+    }
+
+    for (key, members) in &groups {
+        if json_output {
+            let urls = members.iter().map(|u| Value::from(u.as_str())).collect();
+            writer.write_all(b"{\"")?;
+            writer.write_all(group_by.name().as_bytes())?;
+            writer.write_all(b"\":")?;
+            Value::from(key.as_str()).write(writer)?;
+            write!(writer, ",\"count\":{},\"urls\":", members.len())?;
+            Value::Array(urls).write(writer)?;
+            writer.write_all(b"}\n")?;
+        } else {
+            writeln!(writer, "{} ({})", key, members.len())?;
+            for url in members {
+                writeln!(writer, "  {}", url)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn urls(inputs: &[&str]) -> Vec<Url> {
+        inputs.iter().map(|u| Url::parse(u).unwrap()).collect()
+    }
+
+    #[test]
+    fn test_write_groups_by_site_pretty() {
+        let urls = urls(&[
+            "https://www.example.co.uk/a",
+            "http://example.co.uk/b",
+            "https://cdn.example.co.uk/c",
+        ]);
+        let mut output = Vec::new();
+        write_groups(&mut output, &urls, GroupBy::Site, false).unwrap();
+
+        let output_str = String::from_utf8(output).unwrap();
+        assert_eq!(
+            output_str,
+            "http://example.co.uk (1)\n  http://example.co.uk/b\n\
+             https://example.co.uk (2)\n  https://www.example.co.uk/a\n  https://cdn.example.co.uk/c\n"
+        );
+    }
+
+    #[test]
+    fn test_write_groups_json() {
+        let urls = urls(&["https://a.example.com/", "data:text/plain,hi"]);
+        let mut output = Vec::new();
+        write_groups(&mut output, &urls, GroupBy::Site, true).unwrap();
+
+        let output_str = String::from_utf8(output).unwrap();
+        let lines: Vec<&str> = output_str.lines().collect();
+        assert_eq!(
+            lines[0],
+            r#"{"site":"https://example.com","count":1,"urls":["https://a.example.com/"]}"#
+        );
+        assert_eq!(
+            lines[1],
+            r#"{"site":"null","count":1,"urls":["data:text/plain,hi"]}"#
+        );
+    }
+}
@@ -0,0 +1,291 @@
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use url::Url;
+
+use crate::json::Value;
+use crate::{OutputOptions, ParseOptions, Records, time};
+
+/// Seconds between 1601-01-01, Chrome's timestamp epoch, and the Unix epoch.
+const WINDOWS_EPOCH_OFFSET: i64 = 11_644_473_600;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Browser {
+    Firefox,
+    Chrome,
+}
+
+impl Browser {
+    pub fn parse(name: &str) -> Option<Browser> {
+        match name {
+            "firefox" => Some(Browser::Firefox),
+            "chrome" => Some(Browser::Chrome),
+            _ => None,
+        }
+    }
+
+    fn query(self) -> &'static str {
+        match self {
+            Browser::Firefox => {
+                "SELECT url, IFNULL(title, ''), visit_count, IFNULL(last_visit_date, 0) \
+                 FROM moz_places WHERE visit_count > 0 ORDER BY last_visit_date"
+            }
+            Browser::Chrome => {
+                "SELECT url, IFNULL(title, ''), visit_count, last_visit_time \
+                 FROM urls ORDER BY last_visit_time"
+            }
+        }
+    }
+
+    /// Converts a stored visit time to Unix seconds; both browsers store
+    /// microseconds, from different epochs, with 0 meaning "never".
+    fn unix_seconds(self, raw: i64) -> Option<i64> {
+        if raw <= 0 {
+            return None;
+        }
+        match self {
+            Browser::Firefox => Some(raw / 1_000_000),
+            Browser::Chrome => Some(raw / 1_000_000 - WINDOWS_EPOCH_OFFSET),
+        }
+    }
+
+    /// Finds the history database of the default (or most recently used) profile.
+    fn locate(self) -> Option<PathBuf> {
+        let home = std::env::var_os("HOME").map(PathBuf::from);
+        let app_data = std::env::var_os("APPDATA").map(PathBuf::from);
+        let local_app_data = std::env::var_os("LOCALAPPDATA").map(PathBuf::from);
+
+        match self {
+            Browser::Firefox => {
+                let profile_dirs = [
+                    home.as_ref().map(|h| h.join(".mozilla/firefox")),
+                    home.as_ref()
+                        .map(|h| h.join("Library/Application Support/Firefox/Profiles")),
+                    app_data.map(|a| a.join("Mozilla/Firefox/Profiles")),
+                ];
+                profile_dirs
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|dir| std::fs::read_dir(dir).ok())
+                    .flatten()
+                    .flatten()
+                    .map(|entry| entry.path().join("places.sqlite"))
+                    .filter_map(|path| {
+                        let modified = path.metadata().ok()?.modified().ok()?;
+                        Some((modified, path))
+                    })
+                    .max()
+                    .map(|(_, path)| path)
+            }
+            Browser::Chrome => [
+                home.as_ref()
+                    .map(|h| h.join(".config/google-chrome/Default/History")),
+                home.as_ref()
+                    .map(|h| h.join("Library/Application Support/Google/Chrome/Default/History")),
+                local_app_data.map(|l| l.join("Google/Chrome/User Data/Default/History")),
+            ]
+            .into_iter()
+            .flatten()
+            .find(|path| path.is_file()),
+        }
+    }
+}
+
+/// One row of browser history.
+#[derive(Debug, PartialEq)]
+pub struct Visit {
+    pub url: String,
+    pub title: String,
+    pub visit_count: u64,
+    /// Unix seconds of the most recent visit, if recorded.
+    pub last_visit: Option<i64>,
+}
+
+/// Prints every URL in the browser's history, with visit counts and times,
+/// rewritten as `parsing` says. `database` overrides the automatically
+/// located history file.
+pub fn run(
+    browser: Browser,
+    database: Option<&str>,
+    parsing: &ParseOptions,
+    output: &OutputOptions,
+) {
+    let path = match database {
+        Some(path) => PathBuf::from(path),
+        None => browser.locate().unwrap_or_else(|| {
+            eprintln!(
+                "Could not find a {:?} history database; pass its path",
+                browser
+            );
+            std::process::exit(1);
+        }),
+    };
+
+    let visits = read_history(browser, &path).unwrap_or_else(|e| {
+        eprintln!("Failed to read history from {}: {}", path.display(), e);
+        std::process::exit(1);
+    });
+
+    let mut out = io::stdout().lock();
+    let _ = write_visits(&mut out, &visits, parsing, output);
+}
+
+/// Queries a copy of the database with the `sqlite3` command-line tool, since
+/// a running browser keeps the original locked.
+fn read_history(browser: Browser, path: &Path) -> Result<Vec<Visit>, String> {
+    let copy = std::env::temp_dir().join(format!("kurl-history-{}.sqlite", std::process::id()));
+    std::fs::copy(path, &copy).map_err(|e| e.to_string())?;
+
+    // Recent visits may only be in the write-ahead log, so bring it along.
+    let wal = PathBuf::from(format!("{}-wal", path.display()));
+    let copy_wal = PathBuf::from(format!("{}-wal", copy.display()));
+    if wal.is_file() {
+        let _ = std::fs::copy(&wal, &copy_wal);
+    }
+
+    let result = Command::new("sqlite3")
+        .arg("-ascii")
+        .arg(&copy)
+        .arg(browser.query())
+        .output();
+    let _ = std::fs::remove_file(&copy);
+    let _ = std::fs::remove_file(&copy_wal);
+
+    let output = result.map_err(|e| format!("could not run sqlite3: {}", e))?;
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+    Ok(parse_rows(
+        &String::from_utf8_lossy(&output.stdout),
+        browser,
+    ))
+}
+
+/// Parses `sqlite3 -ascii` output: fields separated by 0x1F, rows by 0x1E.
+fn parse_rows(rows: &str, browser: Browser) -> Vec<Visit> {
+    rows.split('\x1e')
+        .filter_map(|row| {
+            let mut fields = row.split('\x1f');
+            let url = fields.next().filter(|u| !u.is_empty())?;
+            let title = fields.next().unwrap_or("");
+            let visit_count = fields.next().and_then(|c| c.parse().ok()).unwrap_or(0);
+            let last_visit = fields
+                .next()
+                .and_then(|t| t.parse().ok())
+                .and_then(|t| browser.unix_seconds(t));
+            Some(Visit {
+                url: url.to_string(),
+                title: title.to_string(),
+                visit_count,
+                last_visit,
+            })
+        })
+        .collect()
+}
+
+fn write_visits<W: Write>(
+    writer: &mut W,
+    visits: &[Visit],
+    parsing: &ParseOptions,
+    output: &OutputOptions,
+) -> io::Result<()> {
+    let mut records = Records::new(writer, output);
+    for visit in visits {
+        let Ok(url) = Url::parse(&visit.url) else {
+            continue;
+        };
+
+        let mut extra = vec![
+            ("title", Value::from(visit.title.as_str())),
+            ("visit_count", Value::from(visit.visit_count)),
+        ];
+        if let Some(secs) = visit.last_visit {
+            extra.push(("last_visit", Value::from(time::format_utc(secs))));
+        }
+        let Some(url) = parsing.prepare(url, &mut extra) else {
+            continue;
+        };
+        records.write(&url, &extra)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_rows_firefox() {
+        let rows =
+            "https://a.com/x|y\x1fA\x1f3\x1f1700000000000000\x1ehttps://b.com\x1f\x1f1\x1f0\x1e";
+        let visits = parse_rows(rows, Browser::Firefox);
+        assert_eq!(
+            visits,
+            vec![
+                Visit {
+                    url: "https://a.com/x|y".into(),
+                    title: "A".into(),
+                    visit_count: 3,
+                    last_visit: Some(1_700_000_000),
+                },
+                Visit {
+                    url: "https://b.com".into(),
+                    title: "".into(),
+                    visit_count: 1,
+                    last_visit: None,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_chrome_timestamps() {
+        let visits = parse_rows(
+            "https://c.com\x1fC\x1f2\x1f13344473600000000\x1e",
+            Browser::Chrome,
+        );
+        assert_eq!(visits[0].last_visit, Some(1_700_000_000));
+    }
+
+    #[test]
+    fn test_write_visits_json() {
+        let visits = parse_rows(
+            "https://a.com/\x1fA\x1f3\x1f1700000000000000\x1e",
+            Browser::Firefox,
+        );
+        let options = OutputOptions {
+            json_output: true,
+            ..Default::default()
+        };
+        let mut output = Vec::new();
+        write_visits(&mut output, &visits, &ParseOptions::default(), &options).unwrap();
+
+        let output_str = String::from_utf8(output).unwrap();
+        assert!(output_str.ends_with(
+            r#","title":"A","visit_count":3,"last_visit":"2023-11-14T22:13:20Z"}
+"#
+        ));
+    }
+
+    #[test]
+    fn test_write_visits_redacted() {
+        let visits = parse_rows(
+            "https://a.com/cb?code=c&api_key=k\x1fA\x1f1\x1f1700000000000000\x1e",
+            Browser::Firefox,
+        );
+        let parsing = ParseOptions {
+            redact: true,
+            ..Default::default()
+        };
+        let output_options = OutputOptions {
+            get: vec!["url".to_string()],
+            ..Default::default()
+        };
+        let mut output = Vec::new();
+        write_visits(&mut output, &visits, &parsing, &output_options).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "https://a.com/cb?code=c&api_key=*****\n"
+        );
+    }
+}
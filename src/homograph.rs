@@ -0,0 +1,337 @@
+use std::fmt;
+
+/// Scripts that letters in hosts come from, as far as spotting homographs
+/// needs. Digits, hyphens, and combining marks belong to none.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Script {
+    Latin,
+    Greek,
+    Cyrillic,
+    Armenian,
+    Hebrew,
+    Arabic,
+    Devanagari,
+    Thai,
+    Georgian,
+    Cherokee,
+    Hangul,
+    Bopomofo,
+    Hiragana,
+    Katakana,
+    Han,
+    Other,
+}
+
+impl Script {
+    fn of(c: char) -> Option<Script> {
+        let script = match c {
+            'a'..='z' | 'A'..='Z' => Script::Latin,
+            '\u{00D7}' | '\u{00F7}' => return None,
+            '\u{00AA}' | '\u{00BA}' | '\u{00C0}'..='\u{02AF}' | '\u{1E00}'..='\u{1EFF}' => {
+                Script::Latin
+            }
+            '\u{2C60}'..='\u{2C7F}' | '\u{A720}'..='\u{A7FF}' | '\u{AB30}'..='\u{AB6F}' => {
+                Script::Latin
+            }
+            '\u{0370}'..='\u{03FF}' | '\u{1F00}'..='\u{1FFF}' => Script::Greek,
+            '\u{0400}'..='\u{052F}' | '\u{1C80}'..='\u{1C8F}' => Script::Cyrillic,
+            '\u{2DE0}'..='\u{2DFF}' | '\u{A640}'..='\u{A69F}' => Script::Cyrillic,
+            '\u{0530}'..='\u{058F}' => Script::Armenian,
+            '\u{0590}'..='\u{05FF}' => Script::Hebrew,
+            '\u{0600}'..='\u{06FF}' | '\u{0750}'..='\u{077F}' | '\u{08A0}'..='\u{08FF}' => {
+                Script::Arabic
+            }
+            '\u{0900}'..='\u{097F}' => Script::Devanagari,
+            '\u{0E00}'..='\u{0E7F}' => Script::Thai,
+            '\u{10A0}'..='\u{10FF}' => Script::Georgian,
+            '\u{13A0}'..='\u{13FF}' | '\u{AB70}'..='\u{ABBF}' => Script::Cherokee,
+            '\u{1100}'..='\u{11FF}' | '\u{3130}'..='\u{318F}' | '\u{AC00}'..='\u{D7AF}' => {
+                Script::Hangul
+            }
+            '\u{3100}'..='\u{312F}' => Script::Bopomofo,
+            // The prolonged sound mark is written with both kana.
+            '\u{30FC}' => return None,
+            '\u{3040}'..='\u{309F}' => Script::Hiragana,
+            '\u{30A0}'..='\u{30FF}' | '\u{31F0}'..='\u{31FF}' => Script::Katakana,
+            '\u{3400}'..='\u{4DBF}' | '\u{4E00}'..='\u{9FFF}' | '\u{20000}'..='\u{2FFFF}' => {
+                Script::Han
+            }
+            _ if c.is_ascii() || !c.is_alphabetic() => return None,
+            _ => Script::Other,
+        };
+        Some(script)
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Script::Latin => "Latin",
+            Script::Greek => "Greek",
+            Script::Cyrillic => "Cyrillic",
+            Script::Armenian => "Armenian",
+            Script::Hebrew => "Hebrew",
+            Script::Arabic => "Arabic",
+            Script::Devanagari => "Devanagari",
+            Script::Thai => "Thai",
+            Script::Georgian => "Georgian",
+            Script::Cherokee => "Cherokee",
+            Script::Hangul => "Hangul",
+            Script::Bopomofo => "Bopomofo",
+            Script::Hiragana => "Hiragana",
+            Script::Katakana => "Katakana",
+            Script::Han => "Han",
+            Script::Other => "another script",
+        }
+    }
+}
+
+/// Characters that look like ASCII letters, from the Unicode confusables
+/// list, with the letter each passes for. Only the lowercase forms matter,
+/// since hosts are lowercased before anyone sees them.
+const CONFUSABLES: &[(char, char)] = &[
+    // Cyrillic
+    ('а', 'a'),
+    ('с', 'c'),
+    ('ԁ', 'd'),
+    ('е', 'e'),
+    ('һ', 'h'),
+    ('і', 'i'),
+    ('ј', 'j'),
+    ('ӏ', 'l'),
+    ('о', 'o'),
+    ('р', 'p'),
+    ('ԛ', 'q'),
+    ('ѕ', 's'),
+    ('ԝ', 'w'),
+    ('х', 'x'),
+    ('у', 'y'),
+    ('ү', 'y'),
+    // Greek
+    ('α', 'a'),
+    ('ι', 'i'),
+    ('κ', 'k'),
+    ('ν', 'v'),
+    ('ο', 'o'),
+    ('ρ', 'p'),
+    ('υ', 'u'),
+    // Armenian
+    ('հ', 'h'),
+    ('ո', 'n'),
+    ('զ', 'q'),
+    ('ս', 'u'),
+    ('օ', 'o'),
+    // Latin letters that aren't ASCII but look it
+    ('ı', 'i'),
+    ('ɑ', 'a'),
+    ('ɡ', 'g'),
+];
+
+/// A host label that could pass for another: one that mixes scripts, or
+/// that reads as an all-ASCII label it isn't.
+#[derive(Debug, PartialEq)]
+pub struct Homograph {
+    /// The label in Unicode.
+    pub label: String,
+    /// The scripts its letters come from, in a fixed order.
+    scripts: Vec<Script>,
+    /// The ASCII label it looks like, if it looks like one.
+    lookalike: Option<String>,
+    /// Which characters are at fault, by index into the label's chars.
+    suspicious: Vec<usize>,
+}
+
+impl Homograph {
+    /// The label with each character at fault in brackets, as in `p[а]ypal`.
+    pub fn marked(&self) -> String {
+        self.label
+            .chars()
+            .enumerate()
+            .map(|(i, c)| {
+                if self.suspicious.contains(&i) {
+                    format!("[{}]", c)
+                } else {
+                    c.to_string()
+                }
+            })
+            .collect()
+    }
+
+    fn mixed(&self) -> bool {
+        !allowed_mix(&self.scripts)
+    }
+}
+
+impl fmt::Display for Homograph {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "label {}", self.marked())?;
+        if self.mixed() {
+            let names: Vec<&str> = self.scripts.iter().map(|script| script.name()).collect();
+            write!(f, " mixes {}", join(&names))?;
+            if let Some(lookalike) = &self.lookalike {
+                write!(f, " and looks like {}", lookalike)?;
+            }
+        } else if let Some(lookalike) = &self.lookalike {
+            match self.scripts[..] {
+                [Script::Latin] => write!(f, " looks like {}", lookalike)?,
+                [script, ..] => write!(f, " is {} that looks like {}", script.name(), lookalike)?,
+                [] => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Checks each label of `host`, which may be written with `xn--` labels.
+/// IP addresses have none to check.
+pub fn check(host: &str) -> Vec<Homograph> {
+    if host.starts_with('[') || host.parse::<std::net::Ipv4Addr>().is_ok() {
+        return Vec::new();
+    }
+    let (unicode, _) = idna::domain_to_unicode(host);
+    unicode.split('.').filter_map(label).collect()
+}
+
+/// A warning for each homograph label in `host`, naming the host as it is
+/// written and as it reads.
+pub fn warnings(host: &str) -> Vec<String> {
+    let homographs = check(host);
+    if homographs.is_empty() {
+        return Vec::new();
+    }
+    let (unicode, _) = idna::domain_to_unicode(host);
+    homographs
+        .iter()
+        .map(|homograph| format!("host {} reads as {}; its {}", host, unicode, homograph))
+        .collect()
+}
+
+fn label(label: &str) -> Option<Homograph> {
+    let chars: Vec<char> = label.chars().collect();
+    let mut scripts: Vec<Script> = chars.iter().filter_map(|&c| Script::of(c)).collect();
+    scripts.sort();
+    scripts.dedup();
+
+    let lookalike: String = chars.iter().map(|&c| skeleton(c)).collect();
+    let lookalike = (lookalike.is_ascii() && lookalike != label).then_some(lookalike);
+    let mixed = !allowed_mix(&scripts);
+    if !mixed && lookalike.is_none() {
+        return None;
+    }
+
+    // Confusable characters are at fault, and when scripts are mixed, so
+    // are the letters outside the label's main one.
+    let main = scripts
+        .iter()
+        .copied()
+        .max_by_key(|&script| {
+            chars
+                .iter()
+                .filter(|&&c| Script::of(c) == Some(script))
+                .count()
+        })
+        .unwrap_or(Script::Latin);
+    let suspicious = chars
+        .iter()
+        .enumerate()
+        .filter(|&(_, &c)| {
+            (lookalike.is_some() && skeleton(c) != c)
+                || (mixed && Script::of(c).is_some_and(|script| script != main))
+        })
+        .map(|(i, _)| i)
+        .collect();
+    Some(Homograph {
+        label: label.to_string(),
+        scripts,
+        lookalike,
+        suspicious,
+    })
+}
+
+/// The ASCII letter `c` passes for, or `c` itself.
+fn skeleton(c: char) -> char {
+    CONFUSABLES
+        .iter()
+        .find(|&&(confusable, _)| confusable == c)
+        .map_or(c, |&(_, ascii)| ascii)
+}
+
+/// Whether a label may use all of `scripts`, by the "highly restrictive"
+/// level of UTS #39: one script, or Latin with the scripts written together
+/// in Japanese, Chinese, or Korean.
+fn allowed_mix(scripts: &[Script]) -> bool {
+    let rest: Vec<Script> = scripts
+        .iter()
+        .copied()
+        .filter(|&script| script != Script::Latin && script != Script::Han)
+        .collect();
+    scripts.len() <= 1
+        || rest
+            .iter()
+            .all(|script| matches!(script, Script::Hiragana | Script::Katakana))
+        || rest == [Script::Bopomofo]
+        || rest == [Script::Hangul]
+}
+
+fn join(names: &[&str]) -> String {
+    match names {
+        [] => String::new(),
+        [one] => one.to_string(),
+        [rest @ .., last] => format!("{} and {}", rest.join(", "), last),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn messages(host: &str) -> Vec<String> {
+        check(host).iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn test_check() {
+        assert_eq!(
+            messages("pаypal.com"),
+            ["label p[а]ypal mixes Latin and Cyrillic and looks like paypal"]
+        );
+        // Hosts come out of the URL parser in punycode.
+        assert_eq!(messages("xn--pypal-4ve.com"), messages("pаypal.com"));
+        assert_eq!(
+            messages("www.аррӏе.com"),
+            ["label [а][р][р][ӏ][е] is Cyrillic that looks like apple"]
+        );
+        assert_eq!(messages("gooɡle.com"), ["label goo[ɡ]le looks like google"]);
+        assert_eq!(
+            messages("онлайнbank.ru"),
+            ["label онлайн[b][a][n][k] mixes Latin and Cyrillic"]
+        );
+    }
+
+    #[test]
+    fn test_warnings() {
+        assert_eq!(
+            warnings("xn--pypal-4ve.com"),
+            [
+                "host xn--pypal-4ve.com reads as pаypal.com; its label p[а]ypal mixes Latin and Cyrillic and looks like paypal"
+            ]
+        );
+        assert!(warnings("example.com").is_empty());
+    }
+
+    #[test]
+    fn test_check_allows() {
+        for host in [
+            "example.com",
+            "bücher.example",
+            "пример.рф",
+            "почта.рф",
+            "東京タワー.jp",
+            "ソニーabc.jp",
+            "한국abc.kr",
+            "xn--mnchen-3ya.de",
+            "192.168.0.1",
+            "[::1]",
+        ] {
+            assert_eq!(check(host), [], "{}", host);
+        }
+    }
+}
@@ -0,0 +1,260 @@
+use std::process::Command;
+
+use crate::config;
+use crate::extract;
+use crate::lint::{self, Diagnostic, LintOptions};
+
+const HOOK_HELP: &str = r#"kurl hook - check URLs from git hooks
+
+USAGE:
+    kurl hook pre-commit [OPTIONS]
+
+OPTIONS:
+        --allow-scheme <LIST>
+                        Report URLs whose scheme is not in the comma-separated list
+        --rule <NAME=SEVERITY>
+                        Set a lint rule to error, warning, or off (see `kurl
+                        lint --list-rules`)
+    -h, --help          Show this help message
+
+The pre-commit hook lints URLs on the lines added by the staged changes and
+exits 1, blocking the commit, if it finds anything. To install it:
+
+    printf '#!/bin/sh\nexec kurl hook pre-commit --allow-scheme https,mailto\n' > .git/hooks/pre-commit
+    chmod +x .git/hooks/pre-commit
+"#;
+
+pub fn run(args: &[String]) {
+    let mut hook = None;
+    let mut options = LintOptions::from_config(&config::load());
+
+    let mut rest = args.iter();
+    while let Some(arg) = rest.next() {
+        match arg.as_str() {
+            "-h" | "--help" => {
+                println!("{}", HOOK_HELP);
+                return;
+            }
+            other if options.accept(other, &mut rest) => {}
+            other if hook.is_none() && !other.starts_with('-') => hook = Some(other),
+            other => {
+                eprintln!("Unknown option for hook: {}", other);
+                std::process::exit(1);
+            }
+        }
+    }
+
+    match hook {
+        Some("pre-commit") => pre_commit(&options),
+        Some(other) => {
+            eprintln!("Unknown hook: {} (expected pre-commit)", other);
+            std::process::exit(1);
+        }
+        None => {
+            eprintln!("Usage: kurl hook pre-commit [--allow-scheme <LIST>]");
+            std::process::exit(1);
+        }
+    }
+}
+
+fn pre_commit(options: &LintOptions) {
+    let output = Command::new("git")
+        .args([
+            "diff",
+            "--cached",
+            "--unified=0",
+            "--no-color",
+            "--no-ext-diff",
+            "--diff-filter=ACMR",
+        ])
+        .output()
+        .unwrap_or_else(|e| {
+            eprintln!("Failed to run git: {}", e);
+            std::process::exit(1);
+        });
+    if !output.status.success() {
+        eprintln!(
+            "git diff failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+        std::process::exit(1);
+    }
+
+    let diff = String::from_utf8_lossy(&output.stdout);
+    let diagnostics = check_added_lines(&diff, options);
+    if diagnostics.is_empty() {
+        return;
+    }
+
+    for diagnostic in &diagnostics {
+        eprintln!("{}", diagnostic);
+    }
+    eprintln!(
+        "kurl: {} URL problem(s) in staged changes; commit blocked (git commit --no-verify skips this check)",
+        diagnostics.len()
+    );
+    std::process::exit(1);
+}
+
+/// A line added by a diff, with its number in the new version of the file.
+#[derive(Debug, PartialEq)]
+struct AddedLine<'a> {
+    file: &'a str,
+    line: usize,
+    text: &'a str,
+}
+
+/// Lists the added lines in a `git diff --unified=0` patch. Each hunk is
+/// read for as many lines as its header counts, so an added line that
+/// itself starts with `++ ` isn't taken for a file header.
+fn added_lines(diff: &str) -> Vec<AddedLine<'_>> {
+    let mut added = Vec::new();
+    let mut file = None;
+    let mut line = 0;
+    // Lines still to come in the current hunk, old and new.
+    let mut left: (usize, usize) = (0, 0);
+    let mut after_old_header = false;
+
+    for text in diff.lines() {
+        if left != (0, 0) {
+            match text.as_bytes().first() {
+                Some(b'+') => {
+                    if let Some(file) = file {
+                        added.push(AddedLine {
+                            file,
+                            line,
+                            text: &text[1..],
+                        });
+                    }
+                    line += 1;
+                    left.1 = left.1.saturating_sub(1);
+                }
+                Some(b'-') => left.0 = left.0.saturating_sub(1),
+                Some(b' ') => {
+                    line += 1;
+                    left = (left.0.saturating_sub(1), left.1.saturating_sub(1));
+                }
+                // `\ No newline at end of file`
+                _ => {}
+            }
+            continue;
+        }
+        if let Some(path) = text.strip_prefix("+++ ").filter(|_| after_old_header) {
+            let path = path.trim_matches('"');
+            file = path.strip_prefix("b/");
+        } else if let Some(hunk) = text.strip_prefix("@@ ") {
+            // @@ -old[,count] +new[,count] @@
+            let mut ranges = hunk.split_whitespace().take(2);
+            let old = ranges.next().and_then(|range| range.strip_prefix('-'));
+            let new = ranges.next().and_then(|range| range.strip_prefix('+'));
+            let (_, old_count) = old.map_or((0, 0), hunk_range);
+            let (start, new_count) = new.map_or((0, 0), hunk_range);
+            line = start;
+            left = (old_count, new_count);
+        }
+        after_old_header = text.starts_with("--- ");
+    }
+    added
+}
+
+/// Reads a hunk range, `start[,count]`, whose count defaults to 1.
+fn hunk_range(range: &str) -> (usize, usize) {
+    let (start, count) = range.split_once(',').unwrap_or((range, "1"));
+    (start.parse().unwrap_or(0), count.parse().unwrap_or(0))
+}
+
+fn check_added_lines<'a>(diff: &'a str, options: &LintOptions) -> Vec<Diagnostic<'a>> {
+    let mut diagnostics = Vec::new();
+    for added in added_lines(diff) {
+        for (offset, url) in extract::find_urls(added.text) {
+            for finding in lint::check(url, options) {
+                diagnostics.push(Diagnostic {
+                    file: added.file,
+                    line: added.line,
+                    column: added.text[..offset].chars().count() + 1,
+                    url,
+                    finding,
+                });
+            }
+        }
+    }
+    diagnostics
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DIFF: &str = "\
+diff --git a/README.md b/README.md
+index 1111111..2222222 100644
+--- a/README.md
++++ b/README.md
+@@ -3,0 +4,2 @@ Intro
++See http://example.com/docs for more.
++Clean: https://example.com/
+@@ -10 +12 @@
+-old https://example.com/
++new https://example.com/?token=abc
+diff --git a/new.txt b/new.txt
+new file mode 100644
+--- /dev/null
++++ b/new.txt
+@@ -0,0 +1 @@
++ftp://files.example.com/
+";
+
+    #[test]
+    fn test_added_lines() {
+        let added = added_lines(DIFF);
+        assert_eq!(added.len(), 4);
+        assert_eq!(
+            added[1],
+            AddedLine {
+                file: "README.md",
+                line: 5,
+                text: "Clean: https://example.com/",
+            }
+        );
+        assert_eq!((added[2].file, added[2].line), ("README.md", 12));
+        assert_eq!((added[3].file, added[3].line), ("new.txt", 1));
+    }
+
+    #[test]
+    fn test_added_lines_starting_with_plus() {
+        let diff = "\
+--- a/notes.txt
++++ b/notes.txt
+@@ -1 +1,3 @@
+-old
++++ counter
++-- dashes
++https://example.com/?token=abc
+";
+        let added = added_lines(diff);
+        assert_eq!(added.len(), 3);
+        assert_eq!(added[0].text, "++ counter");
+        assert_eq!((added[2].file, added[2].line), ("notes.txt", 3));
+    }
+
+    #[test]
+    fn test_check_added_lines() {
+        let options = LintOptions {
+            allowed_schemes: vec!["https".to_string(), "http".to_string()],
+            ..Default::default()
+        };
+        let diagnostics: Vec<String> = check_added_lines(DIFF, &options)
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(
+            diagnostics,
+            vec![
+                "README.md:4:5: warning: uses unencrypted http; prefer https [insecure-scheme]",
+                "README.md:12:5: error: query parameter token looks like a secret [secret-param]",
+                "new.txt:1:1: error: uses ftp, which is not among the allowed schemes (https, http) [scheme-not-allowed]",
+                "new.txt:1:1: warning: uses unencrypted ftp; prefer sftp or https [insecure-scheme]",
+            ]
+        );
+    }
+}
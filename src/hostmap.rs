@@ -0,0 +1,316 @@
+/// Host renames read from a `--host-map` file.
+///
+/// Each line is `OLD NEW`, separated by whitespace, with `#` comments. An OLD
+/// of `*.example.com` matches every subdomain of example.com (but not
+/// example.com itself), and a NEW like `*.example.net` keeps the matched
+/// subdomain labels. Exact entries win over wildcards, and among wildcards the
+/// longest suffix wins, so line order only matters for duplicates.
+#[derive(Debug, Clone, Default)]
+pub struct HostMap {
+    exact: Vec<(String, String)>,
+    /// Suffixes from `*.` entries, including their leading dot.
+    wildcards: Vec<(String, String)>,
+}
+
+impl HostMap {
+    pub fn parse(text: &str) -> Result<HostMap, String> {
+        let mut map = HostMap::default();
+        for (number, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            if line.is_empty() {
+                continue;
+            }
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let [old, new] = fields[..] else {
+                return Err(format!("expected OLD NEW on line {}", number + 1));
+            };
+            let (old, new) = (old.to_ascii_lowercase(), new.to_ascii_lowercase());
+            if new.starts_with("*.") && !old.starts_with("*.") {
+                return Err(format!(
+                    "{} has a wildcard but {} doesn't, on line {}",
+                    new,
+                    old,
+                    number + 1
+                ));
+            }
+            let misplaced = |host: &str| host.strip_prefix("*.").unwrap_or(host).contains('*');
+            if misplaced(&old) || misplaced(&new) {
+                return Err(format!(
+                    "wildcards must be a leading *. label, on line {}",
+                    number + 1
+                ));
+            }
+            match old.strip_prefix('*') {
+                Some(suffix) => map.wildcards.push((suffix.to_string(), new)),
+                None => map.exact.push((old, new)),
+            }
+        }
+        Ok(map)
+    }
+
+    /// Adds the entries of `other`, which rank as if they came after these.
+    pub fn extend(&mut self, other: HostMap) {
+        self.exact.extend(other.exact);
+        self.wildcards.extend(other.wildcards);
+    }
+
+    /// Returns the new name for `host`, if an entry matches it.
+    pub fn map(&self, host: &str) -> Option<String> {
+        let host = host.to_ascii_lowercase();
+        if let Some((_, new)) = self.exact.iter().find(|(old, _)| *old == host) {
+            return Some(new.clone());
+        }
+        let (suffix, new) = self
+            .wildcards
+            .iter()
+            .filter(|(suffix, _)| host.len() > suffix.len() && host.ends_with(suffix.as_str()))
+            .max_by_key(|(suffix, _)| suffix.len())?;
+        let labels = &host[..host.len() - suffix.len()];
+        Some(match new.strip_prefix('*') {
+            Some(new_suffix) => format!("{}{}", labels, new_suffix),
+            None => new.clone(),
+        })
+    }
+}
+
+/// Hosts read from an `--allow-file` or `--deny-file`.
+///
+/// Each line is a host, with `#` comments. An entry matches a host that is
+/// the entry or whose registrable domain is, so `example.com` matches
+/// `www.example.com` while `api.example.com` matches only itself, and
+/// `*.internal.corp` matches every subdomain of internal.corp (but not
+/// internal.corp itself). Unicode entries match their punycode hosts.
+#[derive(Debug, Clone, Default)]
+pub struct HostList {
+    /// Entries in ASCII, with wildcards keeping their `*.`.
+    entries: Vec<String>,
+}
+
+impl HostList {
+    pub fn parse(text: &str) -> Result<HostList, String> {
+        let mut list = HostList::default();
+        for (number, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            if line.is_empty() {
+                continue;
+            }
+            let (wildcard, host) = match line.strip_prefix("*.") {
+                Some(suffix) => ("*.", suffix),
+                None => ("", line),
+            };
+            if host.contains(['*', ' ', '\t']) {
+                return Err(format!(
+                    "expected one host, or a leading *. wildcard, on line {}",
+                    number + 1
+                ));
+            }
+            let host = host.trim_end_matches('.');
+            let ascii = match host.parse::<std::net::IpAddr>() {
+                Ok(ip) if wildcard.is_empty() => match ip {
+                    std::net::IpAddr::V6(ip) => format!("[{}]", ip),
+                    std::net::IpAddr::V4(ip) => ip.to_string(),
+                },
+                _ => idna::domain_to_ascii(host)
+                    .map_err(|_| format!("invalid host {} on line {}", line, number + 1))?,
+            };
+            list.entries.push(format!("{}{}", wildcard, ascii));
+        }
+        Ok(list)
+    }
+
+    /// Adds the entries of `other`.
+    pub fn extend(&mut self, other: HostList) {
+        self.entries.extend(other.entries);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns the first entry that matches the URL's host, if any does.
+    pub fn find(&self, url: &url::Url) -> Option<&str> {
+        let host = url.host_str()?.trim_end_matches('.').to_ascii_lowercase();
+        let registrable = match url.host()? {
+            url::Host::Domain(_) => crate::psl::list().registrable_domain(&host),
+            _ => None,
+        };
+        self.entries
+            .iter()
+            .find(|entry| match entry.strip_prefix('*') {
+                Some(suffix) => host.len() > suffix.len() && host.ends_with(suffix),
+                None => **entry == host || registrable == Some(entry.as_str()),
+            })
+            .map(String::as_str)
+    }
+}
+
+/// Parses a comma-separated list of `FROM=TO` pairs, as in `443=8443,80=8080`.
+pub fn pairs(spec: &str) -> Result<Vec<(&str, &str)>, String> {
+    spec.split(',')
+        .map(|pair| match pair.trim().split_once('=') {
+            Some((from, to)) if !from.is_empty() && !to.is_empty() => Ok((from, to)),
+            _ => Err(format!("expected FROM=TO, got {:?}", pair)),
+        })
+        .collect()
+}
+
+/// Replaces whole labels of `host` by `renames`, so `prod=staging` turns
+/// `api.prod.example.com` into `api.staging.example.com`. Returns `None` if no
+/// label changed.
+pub fn rename_labels(host: &str, renames: &[(String, String)]) -> Option<String> {
+    let mut changed = false;
+    let labels: Vec<&str> = host
+        .split('.')
+        .map(|label| {
+            match renames
+                .iter()
+                .find(|(from, _)| from.eq_ignore_ascii_case(label))
+            {
+                Some((_, to)) => {
+                    changed = true;
+                    to.as_str()
+                }
+                None => label,
+            }
+        })
+        .collect();
+    changed.then(|| labels.join("."))
+}
+
+/// Whether hosts should carry a `www.` label, from `--www`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Www {
+    Add,
+    Remove,
+}
+
+/// Adds or removes the `www.` label of `host`, but only where the host without
+/// it is a registrable domain, so `example.co.uk` gains one while `co.uk` and
+/// `api.example.com` don't. Returns `None` if nothing changes.
+pub fn apply_www(host: &str, policy: Www) -> Option<String> {
+    let psl = crate::psl::list();
+    let host = host.to_ascii_lowercase();
+    let is_registrable = |domain: &str| psl.registrable_domain(domain) == Some(domain);
+    match policy {
+        Www::Add if !host.starts_with("www.") && is_registrable(&host) => {
+            Some(format!("www.{}", host))
+        }
+        Www::Remove => host
+            .strip_prefix("www.")
+            .filter(|domain| is_registrable(domain))
+            .map(str::to_string),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_map() {
+        let map = HostMap::parse(
+            "# CDN move\n\
+             *.cdn-old.net      *.cdn.example.com\n\
+             *.img.cdn-old.net  images.example.com  # consolidated\n\
+             \n\
+             Old.Example.COM    new.example.com\n",
+        )
+        .unwrap();
+        assert_eq!(
+            map.map("old.example.com").as_deref(),
+            Some("new.example.com")
+        );
+        assert_eq!(
+            map.map("eu.static.cdn-old.net").as_deref(),
+            Some("eu.static.cdn.example.com")
+        );
+        assert_eq!(
+            map.map("a.img.cdn-old.net").as_deref(),
+            Some("images.example.com")
+        );
+        assert_eq!(map.map("cdn-old.net"), None);
+        assert_eq!(map.map("notcdn-old.net"), None);
+    }
+
+    #[test]
+    fn test_host_list() {
+        let list = HostList::parse(
+            "# vendors\n\
+             example.com\n\
+             API.Example.NET.   # one host only\n\
+             *.internal.corp\n\
+             bücher.example\n\
+             10.0.0.1\n",
+        )
+        .unwrap();
+        let find = |url: &str| list.find(&url::Url::parse(url).unwrap());
+        assert_eq!(find("https://example.com/"), Some("example.com"));
+        assert_eq!(find("https://www.Example.com./a"), Some("example.com"));
+        assert_eq!(find("https://api.example.net/"), Some("api.example.net"));
+        assert_eq!(find("https://www.api.example.net/"), None);
+        assert_eq!(find("https://example.net/"), None);
+        assert_eq!(
+            find("http://git.eu.internal.corp/"),
+            Some("*.internal.corp")
+        );
+        assert_eq!(find("http://internal.corp/"), None);
+        assert_eq!(
+            find("https://shop.bücher.example/"),
+            Some("xn--bcher-kva.example")
+        );
+        assert_eq!(find("http://10.0.0.1:8080/"), Some("10.0.0.1"));
+        assert_eq!(find("mailto:a@example.com"), None);
+
+        assert!(HostList::parse("a.*.example.com").is_err());
+        assert!(HostList::parse("example.com other.com").is_err());
+    }
+
+    #[test]
+    fn test_pairs_and_labels() {
+        assert_eq!(
+            pairs("443=8443, 80=8080"),
+            Ok(vec![("443", "8443"), ("80", "8080")])
+        );
+        assert!(pairs("443=").is_err());
+        assert!(pairs("443=8443,").is_err());
+
+        let renames = [("prod".to_string(), "staging".to_string())];
+        assert_eq!(
+            rename_labels("api.PROD.example.com", &renames).as_deref(),
+            Some("api.staging.example.com")
+        );
+        assert_eq!(rename_labels("production.example.com", &renames), None);
+    }
+
+    #[test]
+    fn test_www() {
+        assert_eq!(
+            apply_www("Example.co.uk", Www::Add).as_deref(),
+            Some("www.example.co.uk")
+        );
+        assert_eq!(apply_www("www.example.com", Www::Add), None);
+        assert_eq!(apply_www("api.example.com", Www::Add), None);
+        assert_eq!(apply_www("co.uk", Www::Add), None);
+        assert_eq!(
+            apply_www("www.example.com", Www::Remove).as_deref(),
+            Some("example.com")
+        );
+        assert_eq!(apply_www("www.co.uk", Www::Remove), None);
+        assert_eq!(apply_www("www.api.example.com", Www::Remove), None);
+        assert_eq!(apply_www("example.com", Www::Remove), None);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        for text in [
+            "a.com",
+            "a.com b.com c.com",
+            "a.com *.b.com",
+            "a*.com b.com",
+            "*.a.com b.*.com",
+        ] {
+            assert!(HostMap::parse(text).is_err(), "{}", text);
+        }
+    }
+}
@@ -0,0 +1,235 @@
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Write};
+use url::Url;
+
+use crate::json::Value;
+use crate::{ParseOptions, config};
+
+/// What `kurl hosts` and `kurl origins` list.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Field {
+    Host,
+    Origin,
+}
+
+impl Field {
+    fn name(self) -> &'static str {
+        match self {
+            Field::Host => "host",
+            Field::Origin => "origin",
+        }
+    }
+
+    /// The name with its indefinite article, as in "an origin".
+    fn with_article(self) -> &'static str {
+        match self {
+            Field::Host => "a host",
+            Field::Origin => "an origin",
+        }
+    }
+
+    /// The URL's host, or its origin; `None` for URLs without one, such as
+    /// `mailto:` links.
+    fn of(self, url: &Url) -> Option<String> {
+        match self {
+            Field::Host => url.host_str().map(str::to_string),
+            Field::Origin => {
+                let origin = url.origin();
+                origin.is_tuple().then(|| origin.ascii_serialization())
+            }
+        }
+    }
+}
+
+fn help(field: Field) -> String {
+    let name = field.name();
+    let a_name = field.with_article();
+    format!(
+        r#"kurl {name}s - list the unique {name}s in a list of URLs
+
+USAGE:
+    kurl {name}s [OPTIONS]
+
+OPTIONS:
+    -f, --file <FILE>   Read URLs from FILE, one per line, instead of stdin;
+                        repeat to read several files
+    -c, --count         Prefix each {name} with how many URLs have it
+    -j, --json          Output JSON lines: {{"{name}": ..., "count": N}}
+        --lenient       Assume https:// for input without a scheme
+    -h, --help          Show this help message
+
+Each {name} is printed once, sorted. Lines that aren't URLs, or URLs
+without {a_name}, are skipped.
+"#
+    )
+}
+
+pub fn run(args: &[String], field: Field) {
+    let mut files = Vec::new();
+    let mut count = false;
+    let mut json_output = false;
+    let mut parsing = ParseOptions::from_config(&config::load());
+
+    let mut rest = args.iter();
+    while let Some(arg) = rest.next() {
+        match arg.as_str() {
+            "-h" | "--help" => {
+                println!("{}", help(field));
+                return;
+            }
+            "-f" | "--file" => files.push(crate::option_value(&mut rest, arg)),
+            "-c" | "--count" => count = true,
+            "-j" | "--json" => json_output = true,
+            other if parsing.accept(other, &mut rest) => {}
+            other => {
+                eprintln!("Unknown option for {}s: {}", field.name(), other);
+                std::process::exit(1);
+            }
+        }
+    }
+
+    let mut tally = Tally::default();
+    if files.is_empty() {
+        tally_lines(&mut tally, io::stdin().lock(), field, &parsing, "stdin");
+    }
+    for path in files {
+        let file = File::open(path).unwrap_or_else(|e| {
+            eprintln!("Failed to open {}: {}", path, e);
+            std::process::exit(1);
+        });
+        tally_lines(&mut tally, BufReader::new(file), field, &parsing, path);
+    }
+
+    let mut out = io::stdout().lock();
+    let _ = tally.write(&mut out, field, count, json_output);
+    if tally.skipped > 0 {
+        eprintln!(
+            "Skipped {} line(s) without {}",
+            tally.skipped,
+            field.with_article()
+        );
+    }
+}
+
+fn tally_lines<R: BufRead>(
+    tally: &mut Tally,
+    reader: R,
+    field: Field,
+    parsing: &ParseOptions,
+    name: &str,
+) {
+    for line in reader.lines() {
+        let line = line.unwrap_or_else(|e| {
+            eprintln!("Failed to read {}: {}", name, e);
+            std::process::exit(1);
+        });
+        tally.add(line.trim(), field, parsing);
+    }
+}
+
+/// How often each value occurs, kept sorted.
+#[derive(Debug, Default)]
+struct Tally {
+    counts: BTreeMap<String, u64>,
+    skipped: u64,
+}
+
+impl Tally {
+    fn add(&mut self, line: &str, field: Field, parsing: &ParseOptions) {
+        if line.is_empty() {
+            return;
+        }
+        let Ok((url, mut extra)) = parsing.parse(line) else {
+            self.skipped += 1;
+            return;
+        };
+        if parsing.filter(&url, &mut extra) {
+            return;
+        }
+        match field.of(&url) {
+            Some(value) => *self.counts.entry(value).or_default() += 1,
+            None => self.skipped += 1,
+        }
+    }
+
+    fn write<W: Write>(
+        &self,
+        writer: &mut W,
+        field: Field,
+        count: bool,
+        json_output: bool,
+    ) -> io::Result<()> {
+        for (value, n) in &self.counts {
+            if json_output {
+                let mut fields = vec![(field.name().to_string(), Value::from(value.as_str()))];
+                if count {
+                    fields.push(("count".to_string(), Value::from(*n)));
+                }
+                Value::Object(fields).write(writer)?;
+                writeln!(writer)?;
+            } else if count {
+                writeln!(writer, "{:>7} {}", n, value)?;
+            } else {
+                writeln!(writer, "{}", value)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const URLS: &str = "https://b.example.com/x
+http://a.example.com:8080/
+https://b.example.com/y?q=1
+
+mailto:someone@example.com
+not a url
+https://B.EXAMPLE.COM/z
+";
+
+    fn tally(field: Field) -> Tally {
+        let mut tally = Tally::default();
+        tally_lines(
+            &mut tally,
+            URLS.as_bytes(),
+            field,
+            &ParseOptions::default(),
+            "test",
+        );
+        tally
+    }
+
+    fn output(tally: &Tally, field: Field, count: bool, json_output: bool) -> String {
+        let mut buffer = Vec::new();
+        tally.write(&mut buffer, field, count, json_output).unwrap();
+        String::from_utf8(buffer).unwrap()
+    }
+
+    #[test]
+    fn test_unique_hosts() {
+        let hosts = tally(Field::Host);
+        assert_eq!(hosts.skipped, 2);
+        assert_eq!(
+            output(&hosts, Field::Host, false, false),
+            "a.example.com\nb.example.com\n"
+        );
+        assert_eq!(
+            output(&hosts, Field::Host, true, false),
+            "      1 a.example.com\n      3 b.example.com\n"
+        );
+    }
+
+    #[test]
+    fn test_unique_origins() {
+        let origins = tally(Field::Origin);
+        assert_eq!(
+            output(&origins, Field::Origin, true, true),
+            "{\"origin\":\"http://a.example.com:8080\",\"count\":1}\n\
+             {\"origin\":\"https://b.example.com\",\"count\":3}\n"
+        );
+    }
+}
@@ -0,0 +1,592 @@
+use std::borrow::Cow;
+use std::io::{self, Write};
+use url::Url;
+
+use crate::json::Value;
+use crate::{OutputOptions, ParseOptions};
+
+/// Attributes whose values are URLs; `srcset` holds a list of them.
+const URL_ATTRIBUTES: &[&str] = &["href", "src", "srcset", "action"];
+
+/// Prints every link in `html`, resolved against the document's `<base href>`
+/// (itself resolved against `base`, if given), and rewritten as `parsing`
+/// says. Without a base, protocol-relative links get `parsing`'s default
+/// scheme, if it has one.
+pub fn run(html: &str, base: Option<&Url>, parsing: &ParseOptions, output: &OutputOptions) {
+    let mut out = io::stdout().lock();
+    match write_links(&mut out, html, base, parsing, output) {
+        Ok(0) => {}
+        Ok(skipped) => eprintln!(
+            "Skipped {} relative URL(s) with no base; pass --base to resolve them",
+            skipped
+        ),
+        Err(_) => {}
+    }
+}
+
+/// Writes each resolvable link as a record, returning how many were skipped
+/// because they were relative and no base was known.
+fn write_links<W: Write>(
+    writer: &mut W,
+    html: &str,
+    base: Option<&Url>,
+    parsing: &ParseOptions,
+    output: &OutputOptions,
+) -> io::Result<usize> {
+    let tags = tags(html);
+    let document_base = document_base(&tags, base);
+
+    let mut records = crate::Records::new(writer, output);
+    let mut skipped = 0;
+    for link in links(&tags) {
+        let mut extra = vec![
+            ("element", Value::from(link.element.to_ascii_lowercase())),
+            ("attribute", Value::from(link.attribute)),
+        ];
+        let resolved = match (&document_base, parsing.default_scheme.as_deref()) {
+            (Some(base), _) => base.join(&link.value),
+            (None, Some(scheme)) if link.value.starts_with("//") => {
+                extra.push(("scheme_inferred", Value::from(true)));
+                Url::parse(&format!("{}:{}", scheme, link.value))
+            }
+            (None, _) => Url::parse(&link.value),
+        };
+        let Ok(url) = resolved else {
+            if document_base.is_none() {
+                skipped += 1;
+            }
+            continue;
+        };
+        let Some(url) = parsing.prepare(url, &mut extra) else {
+            continue;
+        };
+        records.write(&url, &extra)?;
+    }
+    Ok(skipped)
+}
+
+/// Picks the base URL for a document: the first `<base href>`, resolved
+/// against `fallback`, or `fallback` itself when the page declares none.
+fn document_base(tags: &[Tag<'_>], fallback: Option<&Url>) -> Option<Url> {
+    let href = tags
+        .iter()
+        .filter(|tag| tag.is("base"))
+        .find_map(|tag| tag.attr("href"))
+        .map(|attr| decode_entities(attr.value));
+
+    match (href, fallback) {
+        (Some(href), Some(fallback)) => fallback.join(href.trim()).ok(),
+        (Some(href), None) => Url::parse(href.trim()).ok(),
+        (None, fallback) => fallback.cloned(),
+    }
+}
+
+/// A URL-valued attribute found in a document.
+#[derive(Debug, PartialEq)]
+pub struct Link<'a> {
+    pub element: &'a str,
+    pub attribute: &'static str,
+    pub value: String,
+}
+
+/// Lists every URL in `href`, `src`, `srcset`, and `action` attributes, in
+/// document order. `<base href>` is not itself a link and is left out.
+pub fn links<'a>(tags: &[Tag<'a>]) -> Vec<Link<'a>> {
+    let mut found = Vec::new();
+    for tag in tags.iter().filter(|tag| !tag.is("base")) {
+        for &attribute in URL_ATTRIBUTES {
+            let Some(attr) = tag.attr(attribute) else {
+                continue;
+            };
+            let value = decode_entities(attr.value);
+            let values = if attribute == "srcset" {
+                srcset_urls(&value)
+            } else {
+                vec![value.trim()]
+            };
+            for value in values.into_iter().filter(|v| !v.is_empty()) {
+                found.push(Link {
+                    element: tag.name,
+                    attribute,
+                    value: value.to_string(),
+                });
+            }
+        }
+    }
+    found
+}
+
+/// Splits a `srcset` value into its candidate URLs, dropping the width and
+/// density descriptors.
+pub fn srcset_urls(srcset: &str) -> Vec<&str> {
+    let mut urls = Vec::new();
+    let mut rest = srcset;
+    loop {
+        rest = rest.trim_start_matches(|c: char| c.is_ascii_whitespace() || c == ',');
+        if rest.is_empty() {
+            break;
+        }
+
+        let end = rest
+            .find(|c: char| c.is_ascii_whitespace())
+            .unwrap_or(rest.len());
+        let url = &rest[..end];
+        rest = &rest[end..];
+
+        if let Some(url) = url.strip_suffix(',') {
+            urls.push(url.trim_end_matches(','));
+            continue;
+        }
+        urls.push(url);
+        rest = rest.find(',').map_or("", |i| &rest[i + 1..]);
+    }
+    urls
+}
+
+/// A tag and its attributes, as found by [`tags`].
+#[derive(Debug)]
+pub struct Tag<'a> {
+    pub name: &'a str,
+    pub attrs: Vec<Attr<'a>>,
+    /// Whether this is an end tag such as `</a>`; end tags have no attributes.
+    pub closing: bool,
+    /// Byte offset just past the tag's closing `>`.
+    pub end: usize,
+}
+
+/// A raw attribute; `offset` is the byte offset of `value` in the source text.
+#[derive(Debug)]
+pub struct Attr<'a> {
+    pub name: &'a str,
+    pub value: &'a str,
+    pub offset: usize,
+}
+
+impl<'a> Tag<'a> {
+    /// Whether this is a start tag named `name`.
+    pub fn is(&self, name: &str) -> bool {
+        !self.closing && self.name.eq_ignore_ascii_case(name)
+    }
+
+    /// Whether this is an end tag named `name`.
+    pub fn closes(&self, name: &str) -> bool {
+        self.closing && self.name.eq_ignore_ascii_case(name)
+    }
+
+    /// Returns the text between this tag and the next one, entity-decoded and trimmed.
+    pub fn text_after<'h>(&self, html: &'h str) -> Cow<'h, str> {
+        let rest = &html[self.end..];
+        let text = &rest[..rest.find('<').unwrap_or(rest.len())];
+        decode_entities(text.trim())
+    }
+
+    pub fn attr(&self, name: &str) -> Option<&Attr<'a>> {
+        self.attrs
+            .iter()
+            .find(|a| a.name.eq_ignore_ascii_case(name))
+    }
+}
+
+/// Scans `html` for start and end tags, skipping comments and doctypes.
+///
+/// This is a forgiving tokenizer, not a parser: it never fails, and text that
+/// merely looks like markup is reported as well.
+pub fn tags(html: &str) -> Vec<Tag<'_>> {
+    let bytes = html.as_bytes();
+    let mut found = Vec::new();
+    let mut pos = 0;
+
+    while let Some(offset) = html[pos..].find('<') {
+        let open = pos + offset;
+        pos = open + 1;
+
+        if html[pos..].starts_with("!--") {
+            pos = html[pos..].find("-->").map_or(html.len(), |i| pos + i + 3);
+            continue;
+        }
+        let closing = bytes.get(pos) == Some(&b'/');
+        if closing {
+            pos += 1;
+        }
+        if !bytes.get(pos).is_some_and(u8::is_ascii_alphabetic) {
+            continue;
+        }
+
+        let name_end = scan(bytes, pos, |b| b.is_ascii_alphanumeric() || b == b'-');
+        let name = &html[pos..name_end];
+        pos = name_end;
+
+        let mut attrs = Vec::new();
+        loop {
+            pos = scan(bytes, pos, |b| b.is_ascii_whitespace() || b == b'/');
+            match bytes.get(pos) {
+                None => break,
+                Some(b'>') => {
+                    pos += 1;
+                    break;
+                }
+                _ => {}
+            }
+
+            let attr_start = pos;
+            pos = scan(bytes, pos, |b| {
+                !b.is_ascii_whitespace() && !matches!(b, b'=' | b'>' | b'/')
+            });
+            let attr_name = &html[attr_start..pos];
+            if attr_name.is_empty() {
+                pos += 1;
+                continue;
+            }
+
+            let after_name = scan(bytes, pos, |b| b.is_ascii_whitespace());
+            if bytes.get(after_name) != Some(&b'=') {
+                attrs.push(Attr {
+                    name: attr_name,
+                    value: "",
+                    offset: pos,
+                });
+                continue;
+            }
+            pos = scan(bytes, after_name + 1, |b| b.is_ascii_whitespace());
+
+            let (value_start, value_end) = match bytes.get(pos) {
+                Some(&quote @ (b'"' | b'\'')) => {
+                    let start = pos + 1;
+                    let end = scan(bytes, start, |b| b != quote);
+                    pos = (end + 1).min(html.len());
+                    (start, end)
+                }
+                _ => {
+                    let start = pos;
+                    pos = scan(bytes, pos, |b| !b.is_ascii_whitespace() && b != b'>');
+                    (start, pos)
+                }
+            };
+            attrs.push(Attr {
+                name: attr_name,
+                value: &html[value_start..value_end],
+                offset: value_start,
+            });
+        }
+
+        found.push(Tag {
+            name,
+            attrs,
+            closing,
+            end: pos,
+        });
+    }
+
+    found
+}
+
+fn scan(bytes: &[u8], mut pos: usize, keep: impl Fn(u8) -> bool) -> usize {
+    while pos < bytes.len() && keep(bytes[pos]) {
+        pos += 1;
+    }
+    pos
+}
+
+/// Decodes the character references that commonly appear in attribute values.
+pub fn decode_entities(value: &str) -> Cow<'_, str> {
+    if !value.contains('&') {
+        return Cow::Borrowed(value);
+    }
+
+    let mut out = String::with_capacity(value.len());
+    let mut rest = value;
+    while let Some(amp) = rest.find('&') {
+        out.push_str(&rest[..amp]);
+        rest = &rest[amp..];
+
+        let decoded = rest.find(';').filter(|&semi| semi <= 10).and_then(|semi| {
+            let entity = &rest[1..semi];
+            let c = match entity {
+                "amp" => Some('&'),
+                "lt" => Some('<'),
+                "gt" => Some('>'),
+                "quot" => Some('"'),
+                "apos" => Some('\''),
+                _ => entity
+                    .strip_prefix("#x")
+                    .or_else(|| entity.strip_prefix("#X"))
+                    .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                    .or_else(|| entity.strip_prefix('#').and_then(|d| d.parse().ok()))
+                    .and_then(char::from_u32),
+            };
+            c.map(|c| (c, semi + 1))
+        });
+
+        match decoded {
+            Some((c, len)) => {
+                out.push(c);
+                rest = &rest[len..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    Cow::Owned(out)
+}
+
+/// A parsed `<meta http-equiv="refresh">` tag.
+#[derive(Debug, PartialEq)]
+pub struct MetaRefresh {
+    pub delay: u32,
+    /// The raw target, if the content names one; relative targets are kept as-is.
+    pub url: Option<String>,
+    /// Byte range of the tag's `content` value in the source text.
+    pub span: (usize, usize),
+}
+
+/// Finds every meta refresh tag in `html`.
+pub fn meta_refreshes(html: &str) -> Vec<MetaRefresh> {
+    tags(html)
+        .iter()
+        .filter(|tag| tag.is("meta"))
+        .filter(|tag| {
+            tag.attr("http-equiv")
+                .is_some_and(|a| a.value.trim().eq_ignore_ascii_case("refresh"))
+        })
+        .filter_map(|tag| {
+            let content = tag.attr("content")?;
+            let decoded = decode_entities(content.value);
+            let (delay, url) = parse_refresh_content(&decoded)?;
+            Some(MetaRefresh {
+                delay,
+                url: url.filter(|u| !u.is_empty()).map(str::to_string),
+                span: (content.offset, content.offset + content.value.len()),
+            })
+        })
+        .collect()
+}
+
+/// Parses a refresh `content` value such as `5; url=https://example.com/`,
+/// following the HTML "shared declarative refresh steps".
+pub fn parse_refresh_content(content: &str) -> Option<(u32, Option<&str>)> {
+    let content = content.trim_start();
+    let digits = content
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(content.len());
+    if digits == 0 && !content.starts_with('.') {
+        return None;
+    }
+    let delay = content[..digits].parse().unwrap_or(0);
+
+    let rest = content[digits..].trim_start_matches(|c: char| c.is_ascii_digit() || c == '.');
+    let rest = rest.trim_start();
+    let rest = match rest.strip_prefix([';', ',']) {
+        Some(r) => r.trim_start(),
+        None if rest.is_empty() => return Some((delay, None)),
+        None => rest,
+    };
+
+    let rest = match rest.get(..3) {
+        Some(prefix) if prefix.eq_ignore_ascii_case("url") => {
+            match rest[3..].trim_start().strip_prefix('=') {
+                Some(r) => r.trim_start(),
+                None => rest,
+            }
+        }
+        _ => rest,
+    };
+
+    let target = match rest.chars().next() {
+        Some(quote @ ('"' | '\'')) => {
+            let inner = &rest[1..];
+            &inner[..inner.find(quote).unwrap_or(inner.len())]
+        }
+        _ => rest.trim_end(),
+    };
+
+    Some((delay, Some(target)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tags_attributes() {
+        let html = r#"<!-- <a href="no"> --><A HREF='/x' data-y=z disabled><img src="i.png"/>"#;
+        let found = tags(html);
+        assert_eq!(found.len(), 2);
+        assert!(found[0].is("a"));
+        assert!(!found[0].closes("a"));
+        assert_eq!(found[0].attr("href").unwrap().value, "/x");
+        assert_eq!(found[0].attr("data-y").unwrap().value, "z");
+        assert_eq!(found[0].attr("disabled").unwrap().value, "");
+        assert_eq!(found[1].attr("src").unwrap().value, "i.png");
+
+        let offset = found[1].attr("src").unwrap().offset;
+        assert_eq!(&html[offset..offset + 5], "i.png");
+    }
+
+    fn json_options() -> OutputOptions {
+        OutputOptions {
+            json_output: true,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_srcset_urls() {
+        assert_eq!(
+            srcset_urls("a.png 1x, /b.png 2x,c.png,  d.png 640w"),
+            vec!["a.png", "/b.png", "c.png", "d.png"]
+        );
+        assert_eq!(srcset_urls(" "), Vec::<&str>::new());
+    }
+
+    #[test]
+    fn test_links_with_base_tag() {
+        let html = r#"<base href="https://cdn.example.com/assets/">
+<a href="../about?x=1&amp;y=2">About</a>
+<img src="logo.png" srcset="logo-2x.png 2x, https://img.example.org/l.png 3x">
+<form action="/search"></form>"#;
+        let mut output = Vec::new();
+        let skipped = write_links(
+            &mut output,
+            html,
+            None,
+            &ParseOptions::default(),
+            &json_options(),
+        )
+        .unwrap();
+        assert_eq!(skipped, 0);
+
+        let output_str = String::from_utf8(output).unwrap();
+        let lines: Vec<&str> = output_str.lines().collect();
+        assert_eq!(lines.len(), 5);
+        assert!(lines[0].contains("\"path\":\"/about\""));
+        assert!(lines[0].contains("\"x\":\"1\",\"y\":\"2\""));
+        assert!(lines[0].contains("\"element\":\"a\",\"attribute\":\"href\""));
+        assert!(lines[1].contains("\"path\":\"/assets/logo.png\""));
+        assert!(lines[2].contains("\"path\":\"/assets/logo-2x.png\""));
+        assert!(lines[3].contains("\"host\":\"img.example.org\""));
+        assert!(lines[4].contains("\"host\":\"cdn.example.com\",\"path\":\"/search\""));
+    }
+
+    #[test]
+    fn test_links_base_flag_and_skipped_relative() {
+        let html = r#"<a href="/x">x</a><a href="https://a.com/">a</a>"#;
+
+        let mut output = Vec::new();
+        assert_eq!(
+            write_links(
+                &mut output,
+                html,
+                None,
+                &ParseOptions::default(),
+                &json_options()
+            )
+            .unwrap(),
+            1
+        );
+        assert_eq!(String::from_utf8(output).unwrap().lines().count(), 1);
+
+        let base = Url::parse("https://site.example/page").unwrap();
+        let mut output = Vec::new();
+        assert_eq!(
+            write_links(
+                &mut output,
+                html,
+                Some(&base),
+                &ParseOptions::default(),
+                &json_options()
+            )
+            .unwrap(),
+            0
+        );
+        let output_str = String::from_utf8(output).unwrap();
+        assert!(
+            output_str
+                .starts_with("{\"scheme\":\"https\",\"host\":\"site.example\",\"path\":\"/x\"")
+        );
+        let html = r#"<script src="//cdn.example.com/lib.js"></script>"#;
+        let parsing = ParseOptions {
+            default_scheme: Some("https".to_string()),
+            ..Default::default()
+        };
+        let mut output = Vec::new();
+        assert_eq!(
+            write_links(&mut output, html, None, &parsing, &json_options()).unwrap(),
+            0
+        );
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "{\"scheme\":\"https\",\"host\":\"cdn.example.com\",\"path\":\"/lib.js\",\"site\":\"https://example.com\",\"registrable_domain\":\"example.com\",\"public_suffix\":\"com\",\"subdomain\":\"cdn\",\"domain\":\"example\",\"suffix\":\"com\",\"safe_url\":\"https://cdn.example.com/lib.js\",\"extension\":\"js\",\"element\":\"script\",\"attribute\":\"src\",\"scheme_inferred\":true}\n"
+        );
+    }
+
+    #[test]
+    fn test_links_rewritten() {
+        let html = r#"<a href="https://a.com/x?utm_source=mail#top">x</a>"#;
+        let parsing = ParseOptions {
+            strip: vec!["query", "fragment"],
+            ..Default::default()
+        };
+        let output_options = OutputOptions {
+            get: vec!["url".to_string()],
+            ..Default::default()
+        };
+        let mut output = Vec::new();
+        write_links(&mut output, html, None, &parsing, &output_options).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), "https://a.com/x\n");
+    }
+
+    #[test]
+    fn test_tags_end_tags_and_text() {
+        let html = "<DT><A HREF=\"https://a.com\">Fish &amp; Chips </a></DL>";
+        let found = tags(html);
+        assert_eq!(found.len(), 4);
+        assert_eq!(found[1].text_after(html), "Fish & Chips");
+        assert!(found[2].closes("a"));
+        assert!(found[3].closes("dl"));
+        assert!(!found[3].is("dl"));
+        assert_eq!(found[3].end, html.len());
+    }
+
+    #[test]
+    fn test_decode_entities() {
+        assert_eq!(decode_entities("/a?x=1&amp;y=2"), "/a?x=1&y=2");
+        assert_eq!(
+            decode_entities("&#47;&#x41;&bogus; & more"),
+            "/A&bogus; & more"
+        );
+    }
+
+    #[test]
+    fn test_parse_refresh_content() {
+        assert_eq!(parse_refresh_content("5"), Some((5, None)));
+        assert_eq!(
+            parse_refresh_content("0; URL='https://a.com/'"),
+            Some((0, Some("https://a.com/")))
+        );
+        assert_eq!(
+            parse_refresh_content("3,url=/next"),
+            Some((3, Some("/next")))
+        );
+        assert_eq!(
+            parse_refresh_content("1.5; https://b.com"),
+            Some((1, Some("https://b.com")))
+        );
+        assert_eq!(parse_refresh_content("soon"), None);
+    }
+
+    #[test]
+    fn test_meta_refreshes() {
+        let html = r#"<meta http-equiv="Refresh" content="5;url=https://a.com/?x=1&amp;y=2">"#;
+        let found = meta_refreshes(html);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].delay, 5);
+        assert_eq!(found[0].url.as_deref(), Some("https://a.com/?x=1&y=2"));
+        assert_eq!(
+            &html[found[0].span.0..found[0].span.1],
+            "5;url=https://a.com/?x=1&amp;y=2"
+        );
+    }
+}
@@ -0,0 +1,123 @@
+use std::collections::hash_map::RandomState;
+use std::hash::BuildHasher;
+use url::Url;
+
+/// How `--id-field` identifies each output record.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum IdField {
+    /// A random version 4 UUID.
+    Uuid,
+    /// A hash of the serialized URL, so identical URLs share an id.
+    Hash,
+    /// The record's 1-based position in the output.
+    Seq,
+}
+
+impl IdField {
+    pub fn parse(name: &str) -> Option<IdField> {
+        match name {
+            "uuid" => Some(IdField::Uuid),
+            "hash" => Some(IdField::Hash),
+            "seq" => Some(IdField::Seq),
+            _ => None,
+        }
+    }
+
+    /// Generates the id for the `seq`th record (counting from 1).
+    pub fn generate(self, seq: u64, url: &Url) -> String {
+        match self {
+            IdField::Uuid => uuid_v4(seq),
+            IdField::Hash => format!("{:016x}", fnv1a(url.as_str().as_bytes())),
+            IdField::Seq => seq.to_string(),
+        }
+    }
+
+    /// Like `generate`, but derives uuids from the URL and position instead
+    /// of randomness, so that every run gives the same ids.
+    pub fn generate_stable(self, seq: u64, url: &Url) -> String {
+        match self {
+            IdField::Uuid => {
+                let mut bytes = seq.to_le_bytes().to_vec();
+                bytes.extend_from_slice(url.as_str().as_bytes());
+                let hi = fnv1a(&bytes);
+                uuid_format(hi, fnv1a(&hi.to_le_bytes()))
+            }
+            other => other.generate(seq, url),
+        }
+    }
+}
+
+fn uuid_v4(seq: u64) -> String {
+    // Each RandomState is freshly keyed, which is random enough for correlation ids.
+    let hi = RandomState::new().hash_one(seq);
+    let lo = RandomState::new().hash_one(hi);
+    uuid_format(hi, lo)
+}
+
+/// Formats 128 bits as a version 4, RFC 4122 variant UUID.
+fn uuid_format(hi: u64, lo: u64) -> String {
+    let hi = (hi & 0xffff_ffff_ffff_0fff) | 0x4000;
+    let lo = (lo & 0x3fff_ffff_ffff_ffff) | 0x8000_0000_0000_0000;
+    format!(
+        "{:08x}-{:04x}-{:04x}-{:04x}-{:012x}",
+        hi >> 32,
+        (hi >> 16) & 0xffff,
+        hi & 0xffff,
+        lo >> 48,
+        lo & 0xffff_ffff_ffff
+    )
+}
+
+/// 64-bit FNV-1a, chosen for being stable across platforms and releases.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &b| {
+        (hash ^ b as u64).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_seq_and_hash_ids() {
+        let url = Url::parse("https://example.com/a").unwrap();
+        assert_eq!(IdField::Seq.generate(3, &url), "3");
+
+        let hash = IdField::Hash.generate(1, &url);
+        assert_eq!(hash.len(), 16);
+        assert_eq!(hash, IdField::Hash.generate(2, &url));
+        assert_ne!(
+            hash,
+            IdField::Hash.generate(1, &Url::parse("https://example.com/b").unwrap())
+        );
+        assert_eq!(fnv1a(b""), 0xcbf2_9ce4_8422_2325);
+    }
+
+    #[test]
+    fn test_uuid_format() {
+        let url = Url::parse("https://example.com").unwrap();
+        let id = IdField::Uuid.generate(1, &url);
+        let groups: Vec<&str> = id.split('-').collect();
+        assert_eq!(
+            groups.iter().map(|g| g.len()).collect::<Vec<_>>(),
+            vec![8, 4, 4, 4, 12]
+        );
+        assert!(groups[2].starts_with('4'));
+        assert!(matches!(
+            groups[3].chars().next(),
+            Some('8' | '9' | 'a' | 'b')
+        ));
+        assert_ne!(id, IdField::Uuid.generate(1, &url));
+    }
+
+    #[test]
+    fn test_stable_uuid() {
+        let url = Url::parse("https://example.com").unwrap();
+        let id = IdField::Uuid.generate_stable(1, &url);
+        assert_eq!(id, IdField::Uuid.generate_stable(1, &url));
+        assert_ne!(id, IdField::Uuid.generate_stable(2, &url));
+        assert_eq!(&id[14..15], "4");
+        assert_eq!(IdField::Seq.generate_stable(5, &url), "5");
+    }
+}
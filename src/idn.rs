@@ -0,0 +1,266 @@
+use std::io::{self, IsTerminal, Write};
+
+use idna::uts46::{AsciiDenyList, DnsLength, Hyphens, Uts46};
+
+use crate::homograph;
+
+const IDN_HELP: &str = r#"kurl idn - convert domains between Unicode and punycode
+
+USAGE:
+    kurl idn encode <DOMAIN>...
+    kurl idn decode <DOMAIN>...
+    <domains> | kurl idn encode|decode
+
+OPTIONS:
+    -h, --help          Show this help message
+
+`encode` prints the ASCII form of each domain, with `xn--` labels for the
+Unicode ones, and `decode` prints the Unicode form. Both map the domain the
+way UTS #46 does (lowercasing, normalizing, and treating 。 as a dot) and
+check it the way registries do: only letters, digits, and hyphens, no hyphen
+at either end of a label or in its third and fourth places unless it starts
+with `xn--`, labels of at most 63 bytes, and the IDNA2008 rules for joiners
+and right-to-left text. That is stricter than URL parsing, which lets through
+names like `_dmarc.example.com`.
+
+Each label that fails is reported on stderr, and the exit status is 1 if any
+domain failed.
+
+Labels that mix scripts, or that only look like ASCII, as Cyrillic а makes
+pаypal.com look like paypal.com, get a warning on stderr with the characters
+at fault in brackets. They don't change the exit status.
+
+EXAMPLES:
+    kurl idn encode bücher.example
+    kurl idn decode xn--mnchen-3ya.de
+    cut -f1 domains.tsv | kurl idn encode
+"#;
+
+/// Label separators in UTS #46: the full stop and its ideographic, fullwidth,
+/// and halfwidth ideographic variants.
+const SEPARATORS: [char; 4] = ['.', '\u{3002}', '\u{FF0E}', '\u{FF61}'];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Direction {
+    Encode,
+    Decode,
+}
+
+pub fn run(args: &[String]) {
+    let mut direction = None;
+    let mut domains = Vec::new();
+    for arg in args {
+        match arg.as_str() {
+            "-h" | "--help" => {
+                println!("{}", IDN_HELP);
+                return;
+            }
+            "encode" if direction.is_none() => direction = Some(Direction::Encode),
+            "decode" if direction.is_none() => direction = Some(Direction::Decode),
+            other if other.starts_with('-') => {
+                eprintln!("Unknown option for idn: {}", other);
+                std::process::exit(1);
+            }
+            other if direction.is_none() => {
+                eprintln!("Unknown idn command: {} (expected encode or decode)", other);
+                std::process::exit(1);
+            }
+            other => domains.push(other),
+        }
+    }
+    let Some(direction) = direction else {
+        eprintln!("Usage: kurl idn encode|decode <DOMAIN>...");
+        std::process::exit(1);
+    };
+
+    let buffer;
+    if domains.is_empty() {
+        if io::stdin().is_terminal() {
+            eprintln!("Usage: kurl idn encode|decode <DOMAIN>...");
+            std::process::exit(1);
+        }
+        buffer = crate::read_stdin();
+        domains = buffer
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .collect();
+    }
+
+    let mut out = io::stdout().lock();
+    let mut failed = false;
+    for domain in domains {
+        match convert(domain, direction) {
+            Ok(converted) => {
+                if writeln!(out, "{}", converted).is_err() {
+                    return;
+                }
+                for warning in homograph::warnings(&converted) {
+                    eprintln!("Warning: {}", warning);
+                }
+            }
+            Err(problems) => {
+                for problem in problems {
+                    eprintln!("Invalid domain {}: {}", domain, problem);
+                }
+                failed = true;
+            }
+        }
+    }
+    if failed {
+        std::process::exit(1);
+    }
+}
+
+/// Converts `domain` or lists what is wrong with it, label by label.
+fn convert(domain: &str, direction: Direction) -> Result<String, Vec<String>> {
+    if let Some(converted) = process(domain, direction, DnsLength::VerifyAllowRootDot) {
+        return Ok(converted);
+    }
+
+    let labels: Vec<&str> = domain.split(SEPARATORS).collect();
+    // A trailing dot is the root label, not an empty one.
+    let labels = match labels.split_last() {
+        Some((&"", rest)) if !rest.is_empty() => rest,
+        _ => &labels[..],
+    };
+    let problems: Vec<String> = labels
+        .iter()
+        .enumerate()
+        .filter_map(|(i, label)| {
+            let problem = label_problem(label, direction)?;
+            Some(format!("label {} `{}` {}", i + 1, label, problem))
+        })
+        .collect();
+    if !problems.is_empty() {
+        return Err(problems);
+    }
+
+    // Every label is fine alone, so it's a rule about the whole domain.
+    let length: usize = labels
+        .iter()
+        .filter_map(|label| process(label, Direction::Encode, DnsLength::Ignore))
+        .map(|label| label.len() + 1)
+        .sum();
+    if direction == Direction::Encode && length > 254 {
+        Err(vec!["is longer than 253 bytes in ASCII".to_string()])
+    } else {
+        Err(vec![
+            "mixes right-to-left and other labels in a way the bidi rule forbids".to_string(),
+        ])
+    }
+}
+
+/// Runs UTS #46 ToASCII or ToUnicode with the registry checks, returning
+/// `None` for an invalid domain. Lengths are only checked when encoding with
+/// `dns_length`.
+fn process(domain: &str, direction: Direction, dns_length: DnsLength) -> Option<String> {
+    let uts46 = Uts46::new();
+    match direction {
+        Direction::Encode => uts46
+            .to_ascii(
+                domain.as_bytes(),
+                AsciiDenyList::STD3,
+                Hyphens::Check,
+                dns_length,
+            )
+            .ok()
+            .map(|ascii| ascii.into_owned()),
+        Direction::Decode => {
+            let (unicode, result) =
+                uts46.to_unicode(domain.as_bytes(), AsciiDenyList::STD3, Hyphens::Check);
+            result.ok().map(|_| unicode.into_owned())
+        }
+    }
+}
+
+/// Says why `label` is invalid on its own, or returns `None` if it isn't.
+fn label_problem(label: &str, direction: Direction) -> Option<&'static str> {
+    if label.is_empty() {
+        return Some("is empty");
+    }
+    if let Some(ascii) = process(label, direction, DnsLength::Ignore) {
+        if direction == Direction::Encode && ascii.len() > 63 {
+            return Some("is longer than 63 bytes in ASCII");
+        }
+        return None;
+    }
+
+    let lowercase = label.to_lowercase();
+    Some(if lowercase.starts_with("xn--") {
+        "is not valid punycode, or decodes to a label that isn't allowed"
+    } else if label.starts_with('-') || label.ends_with('-') {
+        "starts or ends with a hyphen"
+    } else if lowercase.get(2..4) == Some("--") {
+        "has hyphens in the third and fourth places, which are kept for xn--"
+    } else if label
+        .chars()
+        .any(|c| c.is_ascii() && !c.is_ascii_alphanumeric() && c != '-')
+    {
+        "contains ASCII other than letters, digits, and hyphens"
+    } else {
+        "contains characters that IDNA2008 disallows, or joins them in a way it forbids"
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_convert() {
+        assert_eq!(
+            convert("Bücher.example", Direction::Encode),
+            Ok("xn--bcher-kva.example".to_string())
+        );
+        assert_eq!(
+            convert("münchen。de.", Direction::Encode),
+            Ok("xn--mnchen-3ya.de.".to_string())
+        );
+        assert_eq!(
+            convert("xn--BCHER-kva.example", Direction::Decode),
+            Ok("bücher.example".to_string())
+        );
+        assert_eq!(
+            convert("example.com", Direction::Decode),
+            Ok("example.com".to_string())
+        );
+    }
+
+    #[test]
+    fn test_convert_errors() {
+        assert_eq!(
+            convert("a_b.bücher.-x.example", Direction::Encode),
+            Err(vec![
+                "label 1 `a_b` contains ASCII other than letters, digits, and hyphens".to_string(),
+                "label 3 `-x` starts or ends with a hyphen".to_string(),
+            ])
+        );
+        assert_eq!(
+            convert("ab--c..com", Direction::Encode),
+            Err(vec![
+                "label 1 `ab--c` has hyphens in the third and fourth places, which are kept for xn--"
+                    .to_string(),
+                "label 2 `` is empty".to_string(),
+            ])
+        );
+        assert_eq!(
+            convert("xn--zz-.com", Direction::Decode),
+            Err(vec![
+                "label 1 `xn--zz-` is not valid punycode, or decodes to a label that isn't allowed"
+                    .to_string()
+            ])
+        );
+        assert_eq!(
+            convert(&format!("{}.com", "a".repeat(64)), Direction::Encode),
+            Err(vec![format!(
+                "label 1 `{}` is longer than 63 bytes in ASCII",
+                "a".repeat(64)
+            )])
+        );
+        assert_eq!(
+            convert(&vec!["a".repeat(60); 5].join("."), Direction::Encode),
+            Err(vec!["is longer than 253 bytes in ASCII".to_string()])
+        );
+    }
+}
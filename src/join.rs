@@ -0,0 +1,145 @@
+use std::io::{self, IsTerminal, Write};
+use url::Url;
+
+use crate::{OutputOptions, Records};
+
+const JOIN_HELP: &str = r#"kurl join - resolve relative references against a base URL
+
+USAGE:
+    kurl join [OPTIONS] <BASE> <REF>...
+    <REFs> | kurl join [OPTIONS] --base <BASE>
+
+OPTIONS:
+        --base <URL>    Base URL, so that every argument (or, with none, every
+                        line of stdin) is a reference
+    -j, --json          Print each result's components as JSON
+        --get <FIELD>   Print only FIELD of each result, as with `kurl --get`
+    -h, --help          Show this help message
+
+References are resolved as browsers resolve links, following the WHATWG URL
+Standard: `../img/a.png`, `?page=2`, `#top`, `//cdn.example.com/x`, and
+absolute URLs all work.
+
+EXAMPLES:
+    kurl join https://example.com/docs/guide/ ../api/ index.html?v=2
+    cat links.txt | kurl join --base https://example.com/blog/post
+"#;
+
+pub fn run(args: &[String]) {
+    let mut base = None;
+    let mut references = Vec::new();
+    let mut output = OutputOptions::default();
+
+    let mut rest = args.iter();
+    while let Some(arg) = rest.next() {
+        match arg.as_str() {
+            "-h" | "--help" => {
+                println!("{}", JOIN_HELP);
+                return;
+            }
+            "--base" => base = Some(crate::option_value(&mut rest, arg)),
+            other if output.accept(other, &mut rest) => {}
+            // References may legitimately start with `-`, but never with `--`.
+            other if other.starts_with("--") => {
+                eprintln!("Unknown option for join: {}", other);
+                std::process::exit(1);
+            }
+            other => references.push(other),
+        }
+    }
+
+    if base.is_none() && !references.is_empty() {
+        base = Some(references.remove(0));
+    }
+    let Some(base) = base else {
+        eprintln!("Usage: kurl join <BASE> <REF>...");
+        std::process::exit(1);
+    };
+    let base = crate::parse_url_or_exit(base);
+
+    let buffer;
+    if references.is_empty() {
+        if io::stdin().is_terminal() {
+            eprintln!("Usage: kurl join <BASE> <REF>...");
+            eprintln!("   or: <REFs> | kurl join --base <BASE>");
+            std::process::exit(1);
+        }
+        buffer = crate::read_stdin();
+        references = buffer
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .collect();
+    }
+
+    let mut out = io::stdout().lock();
+    if let Ok(false) = write_joined(&mut out, &base, &references, &output) {
+        std::process::exit(1);
+    }
+}
+
+/// Writes each reference resolved against `base`, returning whether all of
+/// them resolved.
+fn write_joined<W: Write>(
+    writer: &mut W,
+    base: &Url,
+    references: &[&str],
+    output: &OutputOptions,
+) -> io::Result<bool> {
+    let mut records = Records::new(writer, output);
+    let mut resolved_all = true;
+    for (i, reference) in references.iter().enumerate() {
+        match base.join(reference) {
+            Ok(url) if output.json_output || !output.get.is_empty() => records.write(&url, &[])?,
+            Ok(url) => writeln!(records.writer, "{}", url)?,
+            Err(e) => {
+                eprintln!(
+                    "Failed to resolve {} (reference {}): {}",
+                    reference,
+                    i + 1,
+                    e
+                );
+                resolved_all = false;
+            }
+        }
+    }
+    Ok(resolved_all)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_join() {
+        let base = Url::parse("https://example.com/docs/guide/intro?lang=en#top").unwrap();
+        let mut output = Vec::new();
+        let resolved = write_joined(
+            &mut output,
+            &base,
+            &[
+                "../api/",
+                "setup.html",
+                "?lang=de",
+                "#next",
+                "//cdn.example.com/app.js",
+                "/",
+                "mailto:docs@example.com",
+                "http://[::1",
+            ],
+            &OutputOptions::default(),
+        )
+        .unwrap();
+        assert!(!resolved);
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "https://example.com/docs/api/\n\
+             https://example.com/docs/guide/setup.html\n\
+             https://example.com/docs/guide/intro?lang=de\n\
+             https://example.com/docs/guide/intro?lang=en#next\n\
+             https://cdn.example.com/app.js\n\
+             https://example.com/\n\
+             mailto:docs@example.com\n"
+        );
+    }
+}
@@ -0,0 +1,400 @@
+use std::io::{self, Write};
+
+/// A JSON value, used for output fields that don't map onto a URL component
+/// and for reading JSON input.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Null,
+    Bool(bool),
+    /// A number, kept as its literal text so integers never lose precision.
+    Number(String),
+    String(String),
+    Array(Vec<Value>),
+    Object(Vec<(String, Value)>),
+}
+
+impl Value {
+    pub fn write<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        match self {
+            Value::Null => writer.write_all(b"null"),
+            Value::Bool(b) => write!(writer, "{}", b),
+            Value::Number(n) => writer.write_all(n.as_bytes()),
+            Value::String(s) => {
+                writer.write_all(b"\"")?;
+                crate::write_json_escaped(writer, s)?;
+                writer.write_all(b"\"")
+            }
+            Value::Array(items) => {
+                writer.write_all(b"[")?;
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        writer.write_all(b",")?;
+                    }
+                    item.write(writer)?;
+                }
+                writer.write_all(b"]")
+            }
+            Value::Object(fields) => {
+                writer.write_all(b"{")?;
+                for (i, (key, value)) in fields.iter().enumerate() {
+                    if i > 0 {
+                        writer.write_all(b",")?;
+                    }
+                    writer.write_all(b"\"")?;
+                    crate::write_json_escaped(writer, key)?;
+                    writer.write_all(b"\":")?;
+                    value.write(writer)?;
+                }
+                writer.write_all(b"}")
+            }
+        }
+    }
+
+    /// Serializes the value as compact JSON.
+    pub fn to_json(&self) -> String {
+        let mut buffer = Vec::new();
+        let _ = self.write(&mut buffer);
+        String::from_utf8(buffer).unwrap_or_default()
+    }
+
+    /// Looks up `key` if this is an object.
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        match self {
+            Value::Object(fields) => fields.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for Value {
+    /// Formats scalars as plain text, for pretty output, arrays as
+    /// comma-separated lists of their items, and objects as compact JSON.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Value::Bool(b) => write!(f, "{}", b),
+            Value::String(s) => f.write_str(s),
+            Value::Number(n) => f.write_str(n),
+            Value::Array(items) => {
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    write!(f, "{}", item)?;
+                }
+                Ok(())
+            }
+            other => f.write_str(&other.to_json()),
+        }
+    }
+}
+
+impl From<&str> for Value {
+    fn from(s: &str) -> Self {
+        Value::String(s.to_string())
+    }
+}
+
+impl From<String> for Value {
+    fn from(s: String) -> Self {
+        Value::String(s)
+    }
+}
+
+impl From<bool> for Value {
+    fn from(b: bool) -> Self {
+        Value::Bool(b)
+    }
+}
+
+macro_rules! number_from {
+    ($($t:ty),*) => {
+        $(impl From<$t> for Value {
+            fn from(n: $t) -> Self {
+                Value::Number(n.to_string())
+            }
+        })*
+    };
+}
+
+number_from!(u16, u32, u64, usize, i64);
+
+/// Parses a complete JSON document.
+pub fn parse(text: &str) -> Result<Value, String> {
+    let mut parser = Parser {
+        bytes: text.as_bytes(),
+        text,
+        pos: 0,
+    };
+    let value = parser.value()?;
+    parser.skip_whitespace();
+    if parser.pos < parser.bytes.len() {
+        return Err(parser.error("trailing characters"));
+    }
+    Ok(value)
+}
+
+/// Parses a stream of JSON documents, one after another with any whitespace
+/// between, as `jq` prints them and as in newline-delimited JSON.
+pub fn parse_stream(text: &str) -> Result<Vec<Value>, String> {
+    let mut parser = Parser {
+        bytes: text.as_bytes(),
+        text,
+        pos: 0,
+    };
+    let mut values = Vec::new();
+    parser.skip_whitespace();
+    while parser.pos < parser.bytes.len() {
+        values.push(parser.value()?);
+        parser.skip_whitespace();
+    }
+    Ok(values)
+}
+
+struct Parser<'a> {
+    bytes: &'a [u8],
+    text: &'a str,
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn error(&self, message: &str) -> String {
+        format!("{} at byte {}", message, self.pos)
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self
+            .bytes
+            .get(self.pos)
+            .is_some_and(u8::is_ascii_whitespace)
+        {
+            self.pos += 1;
+        }
+    }
+
+    fn eat(&mut self, b: u8) -> bool {
+        self.skip_whitespace();
+        if self.bytes.get(self.pos) == Some(&b) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, b: u8) -> Result<(), String> {
+        if self.eat(b) {
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected '{}'", b as char)))
+        }
+    }
+
+    fn value(&mut self) -> Result<Value, String> {
+        self.skip_whitespace();
+        match self.bytes.get(self.pos) {
+            Some(b'{') => {
+                self.pos += 1;
+                let mut fields = Vec::new();
+                if self.eat(b'}') {
+                    return Ok(Value::Object(fields));
+                }
+                loop {
+                    self.skip_whitespace();
+                    let key = self.string()?;
+                    self.expect(b':')?;
+                    fields.push((key, self.value()?));
+                    if !self.eat(b',') {
+                        self.expect(b'}')?;
+                        return Ok(Value::Object(fields));
+                    }
+                }
+            }
+            Some(b'[') => {
+                self.pos += 1;
+                let mut items = Vec::new();
+                if self.eat(b']') {
+                    return Ok(Value::Array(items));
+                }
+                loop {
+                    items.push(self.value()?);
+                    if !self.eat(b',') {
+                        self.expect(b']')?;
+                        return Ok(Value::Array(items));
+                    }
+                }
+            }
+            Some(b'"') => self.string().map(Value::String),
+            Some(b't') => self.literal("true", Value::Bool(true)),
+            Some(b'f') => self.literal("false", Value::Bool(false)),
+            Some(b'n') => self.literal("null", Value::Null),
+            Some(b'-' | b'0'..=b'9') => self.number(),
+            Some(_) => Err(self.error("unexpected character")),
+            None => Err(self.error("unexpected end of input")),
+        }
+    }
+
+    fn literal(&mut self, word: &str, value: Value) -> Result<Value, String> {
+        if self.text[self.pos..].starts_with(word) {
+            self.pos += word.len();
+            Ok(value)
+        } else {
+            Err(self.error("unexpected character"))
+        }
+    }
+
+    fn number(&mut self) -> Result<Value, String> {
+        let start = self.pos;
+        while self
+            .bytes
+            .get(self.pos)
+            .is_some_and(|b| matches!(b, b'-' | b'+' | b'.' | b'e' | b'E' | b'0'..=b'9'))
+        {
+            self.pos += 1;
+        }
+        let literal = &self.text[start..self.pos];
+        if literal.parse::<f64>().is_err() {
+            return Err(self.error("invalid number"));
+        }
+        Ok(Value::Number(literal.to_string()))
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        if self.bytes.get(self.pos) != Some(&b'"') {
+            return Err(self.error("expected string"));
+        }
+        self.pos += 1;
+
+        let mut out = String::new();
+        loop {
+            let rest = &self.text[self.pos..];
+            let Some(special) = rest.find(['"', '\\']) else {
+                return Err(self.error("unterminated string"));
+            };
+            out.push_str(&rest[..special]);
+            self.pos += special;
+
+            if self.bytes[self.pos] == b'"' {
+                self.pos += 1;
+                return Ok(out);
+            }
+
+            self.pos += 1;
+            let escaped = match self.bytes.get(self.pos) {
+                Some(b'"') => '"',
+                Some(b'\\') => '\\',
+                Some(b'/') => '/',
+                Some(b'b') => '\u{8}',
+                Some(b'f') => '\u{c}',
+                Some(b'n') => '\n',
+                Some(b'r') => '\r',
+                Some(b't') => '\t',
+                Some(b'u') => {
+                    let high = self.hex4()?;
+                    let code = if (0xd800..0xdc00).contains(&high)
+                        && self.text[self.pos + 1..].starts_with("\\u")
+                    {
+                        self.pos += 2;
+                        let low = self.hex4()?;
+                        0x10000 + ((high - 0xd800) << 10) + (low.wrapping_sub(0xdc00) & 0x3ff)
+                    } else {
+                        high
+                    };
+                    char::from_u32(code).unwrap_or('\u{fffd}')
+                }
+                _ => return Err(self.error("invalid escape")),
+            };
+            out.push(escaped);
+            self.pos += 1;
+        }
+    }
+
+    /// Reads the four hex digits after a `\u`, leaving `pos` on the last one.
+    fn hex4(&mut self) -> Result<u32, String> {
+        let digits = self
+            .text
+            .get(self.pos + 1..self.pos + 5)
+            .ok_or_else(|| self.error("truncated \\u escape"))?;
+        let code = u32::from_str_radix(digits, 16).map_err(|_| self.error("invalid \\u escape"))?;
+        self.pos += 4;
+        Ok(code)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn to_json(value: &Value) -> String {
+        value.to_json()
+    }
+
+    #[test]
+    fn test_write_scalars() {
+        assert_eq!(to_json(&Value::from("a\"b")), r#""a\"b""#);
+        assert_eq!(to_json(&Value::from(5u32)), "5");
+        assert_eq!(to_json(&Value::from(true)), "true");
+    }
+
+    #[test]
+    fn test_write_array() {
+        let value = Value::Array(vec![Value::from("a"), Value::from(1u16)]);
+        assert_eq!(to_json(&value), r#"["a",1]"#);
+        assert_eq!(value.to_string(), "a, 1");
+    }
+
+    #[test]
+    fn test_display_scalars_as_text() {
+        assert_eq!(Value::from("plain").to_string(), "plain");
+        assert_eq!(Value::from(42u64).to_string(), "42");
+        assert_eq!(Value::from(false).to_string(), "false");
+    }
+
+    #[test]
+    fn test_parse_round_trip() {
+        let text = r#"{"scheme":"https","port":8080,"query":{"k":"v"},"tags":[true,null,-1.5e3]}"#;
+        let value = parse(text).unwrap();
+        assert_eq!(value.get("port"), Some(&Value::from(8080u16)));
+        assert_eq!(
+            value.get("query").and_then(|q| q.get("k")),
+            Some(&Value::from("v"))
+        );
+        assert_eq!(to_json(&value), text);
+    }
+
+    #[test]
+    fn test_parse_strings_and_whitespace() {
+        let value = parse(" [ \"a\\n\\u00e9\\ud83d\\ude00\\\"\" , {} ] ").unwrap();
+        assert_eq!(
+            value,
+            Value::Array(vec![Value::from("a\né😀\""), Value::Object(vec![])])
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(parse("").is_err());
+        assert!(parse("{\"a\" 1}").is_err());
+        assert!(parse("[1,]").is_err());
+        assert!(parse("\"open").is_err());
+        assert!(parse("{} x").is_err());
+        assert!(parse("-").is_err());
+    }
+
+    #[test]
+    fn test_parse_stream() {
+        let values = parse_stream("{\"a\":1}\n{\n  \"b\": [\n    2\n  ]\n} 3\n").unwrap();
+        assert_eq!(
+            values,
+            vec![
+                Value::Object(vec![("a".to_string(), Value::from(1u16))]),
+                Value::Object(vec![(
+                    "b".to_string(),
+                    Value::Array(vec![Value::from(2u16)])
+                )]),
+                Value::from(3u16),
+            ]
+        );
+        assert_eq!(parse_stream(" \n"), Ok(Vec::new()));
+        assert!(parse_stream("{} {").is_err());
+    }
+}
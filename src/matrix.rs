@@ -0,0 +1,120 @@
+use url::Url;
+
+use crate::edit::percent_decode;
+use crate::json::Value;
+
+/// A path segment with matrix parameters, as in `/users;id=42`.
+#[derive(Debug, PartialEq)]
+pub struct Segment {
+    /// Position of the segment in the path, from 0.
+    pub index: usize,
+    /// The decoded segment, without its parameters.
+    pub name: String,
+    /// Decoded parameters in order; ones without `=` have empty values.
+    pub params: Vec<(String, String)>,
+}
+
+impl Segment {
+    pub fn to_value(&self) -> Value {
+        Value::Object(vec![
+            ("index".to_string(), Value::from(self.index)),
+            ("segment".to_string(), Value::from(self.name.as_str())),
+            (
+                "params".to_string(),
+                Value::Object(
+                    self.params
+                        .iter()
+                        .map(|(key, value)| (key.clone(), Value::from(value.as_str())))
+                        .collect(),
+                ),
+            ),
+        ])
+    }
+}
+
+/// Lists the path segments of `url` that carry matrix parameters, which
+/// follow the segment after `;` and are separated by `;`. URLs without a
+/// hierarchical path have none.
+pub fn segments(url: &Url) -> Vec<Segment> {
+    let Some(path) = url.path_segments() else {
+        return Vec::new();
+    };
+    path.enumerate()
+        .filter_map(|(index, segment)| {
+            let (name, params) = segment.split_once(';')?;
+            let params = params
+                .split(';')
+                .filter(|param| !param.is_empty())
+                .map(|param| {
+                    let (key, value) = param.split_once('=').unwrap_or((param, ""));
+                    (percent_decode(key), percent_decode(value))
+                })
+                .collect();
+            Some(Segment {
+                index,
+                name: percent_decode(name),
+                params,
+            })
+        })
+        .collect()
+}
+
+/// The decoded value of the first matrix parameter named `name`, in path
+/// order.
+pub fn value(url: &Url, name: &str) -> Option<String> {
+    segments(url)
+        .into_iter()
+        .flat_map(|segment| segment.params)
+        .find(|(key, _)| key == name)
+        .map(|(_, value)| value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_segments() {
+        let url = Url::parse("https://example.com/users;id=42/books;sort=asc;lang=en%20GB/x;flag")
+            .unwrap();
+        let found = segments(&url);
+        assert_eq!(
+            found,
+            vec![
+                Segment {
+                    index: 0,
+                    name: "users".to_string(),
+                    params: vec![("id".to_string(), "42".to_string())],
+                },
+                Segment {
+                    index: 1,
+                    name: "books".to_string(),
+                    params: vec![
+                        ("sort".to_string(), "asc".to_string()),
+                        ("lang".to_string(), "en GB".to_string()),
+                    ],
+                },
+                Segment {
+                    index: 2,
+                    name: "x".to_string(),
+                    params: vec![("flag".to_string(), String::new())],
+                },
+            ]
+        );
+        assert_eq!(
+            found[0].to_value().to_json(),
+            r#"{"index":0,"segment":"users","params":{"id":"42"}}"#
+        );
+        assert!(segments(&Url::parse("https://example.com/a/b?x=1;y=2").unwrap()).is_empty());
+        assert!(segments(&Url::parse("mailto:a;b@example.com").unwrap()).is_empty());
+    }
+
+    #[test]
+    fn test_value() {
+        let url = Url::parse("https://example.com/a;v=1/b;lang=en;v=2/c;flag").unwrap();
+        assert_eq!(value(&url, "v").as_deref(), Some("1"));
+        assert_eq!(value(&url, "lang").as_deref(), Some("en"));
+        assert_eq!(value(&url, "flag").as_deref(), Some(""));
+        assert_eq!(value(&url, "a"), None);
+    }
+}
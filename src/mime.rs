@@ -0,0 +1,123 @@
+use url::Url;
+
+use crate::edit::percent_decode;
+
+/// Media types for common file extensions, as servers usually send them.
+const TYPES: &[(&str, &str)] = &[
+    ("7z", "application/x-7z-compressed"),
+    ("avif", "image/avif"),
+    ("bmp", "image/bmp"),
+    ("css", "text/css"),
+    ("csv", "text/csv"),
+    ("doc", "application/msword"),
+    (
+        "docx",
+        "application/vnd.openxmlformats-officedocument.wordprocessingml.document",
+    ),
+    ("epub", "application/epub+zip"),
+    ("flac", "audio/flac"),
+    ("gif", "image/gif"),
+    ("gz", "application/gzip"),
+    ("htm", "text/html"),
+    ("html", "text/html"),
+    ("ico", "image/vnd.microsoft.icon"),
+    ("ics", "text/calendar"),
+    ("jpeg", "image/jpeg"),
+    ("jpg", "image/jpeg"),
+    ("js", "text/javascript"),
+    ("json", "application/json"),
+    ("m3u8", "application/vnd.apple.mpegurl"),
+    ("md", "text/markdown"),
+    ("mjs", "text/javascript"),
+    ("mov", "video/quicktime"),
+    ("mp3", "audio/mpeg"),
+    ("mp4", "video/mp4"),
+    ("oga", "audio/ogg"),
+    ("ogg", "audio/ogg"),
+    ("ogv", "video/ogg"),
+    ("otf", "font/otf"),
+    ("pdf", "application/pdf"),
+    ("png", "image/png"),
+    ("ppt", "application/vnd.ms-powerpoint"),
+    (
+        "pptx",
+        "application/vnd.openxmlformats-officedocument.presentationml.presentation",
+    ),
+    ("rar", "application/vnd.rar"),
+    ("rss", "application/rss+xml"),
+    ("svg", "image/svg+xml"),
+    ("tar", "application/x-tar"),
+    ("tif", "image/tiff"),
+    ("tiff", "image/tiff"),
+    ("ttf", "font/ttf"),
+    ("txt", "text/plain"),
+    ("wasm", "application/wasm"),
+    ("wav", "audio/wav"),
+    ("webm", "video/webm"),
+    ("webmanifest", "application/manifest+json"),
+    ("webp", "image/webp"),
+    ("woff", "font/woff"),
+    ("woff2", "font/woff2"),
+    ("xhtml", "application/xhtml+xml"),
+    ("xls", "application/vnd.ms-excel"),
+    (
+        "xlsx",
+        "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+    ),
+    ("xml", "application/xml"),
+    ("yaml", "application/yaml"),
+    ("yml", "application/yaml"),
+    ("zip", "application/zip"),
+];
+
+/// The extension of the last path segment, lowercased: `pdf` for
+/// `/docs/Guide.PDF` and `gz` for `/a.tar.gz`. Directories, dotfiles like
+/// `/.env`, and segments ending in a dot have none.
+pub fn extension(url: &Url) -> Option<String> {
+    let last = percent_decode(url.path_segments()?.next_back()?);
+    let (stem, extension) = last.rsplit_once('.')?;
+    if stem.is_empty() || extension.is_empty() {
+        return None;
+    }
+    Some(extension.to_lowercase())
+}
+
+/// Guesses the media type of what the URL points at from its extension.
+pub fn guess(url: &Url) -> Option<&'static str> {
+    let extension = extension(url)?;
+    TYPES
+        .iter()
+        .find(|(known, _)| *known == extension)
+        .map(|&(_, media_type)| media_type)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parts(url: &str) -> (Option<String>, Option<&'static str>) {
+        let url = Url::parse(url).unwrap();
+        (extension(&url), guess(&url))
+    }
+
+    #[test]
+    fn test_extension_and_guess() {
+        assert_eq!(
+            parts("https://example.com/docs/Guide.PDF?dl=1#p2"),
+            (Some("pdf".to_string()), Some("application/pdf"))
+        );
+        assert_eq!(
+            parts("https://example.com/a.tar.gz"),
+            (Some("gz".to_string()), Some("application/gzip"))
+        );
+        assert_eq!(
+            parts("https://example.com/build.v2.xyz"),
+            (Some("xyz".to_string()), None)
+        );
+        assert_eq!(parts("https://example.com/images/"), (None, None));
+        assert_eq!(parts("https://example.com/.env"), (None, None));
+        assert_eq!(parts("https://example.com/file."), (None, None));
+        assert_eq!(parts("mailto:a@example.com"), (None, None));
+        assert!(TYPES.windows(2).all(|pair| pair[0].0 < pair[1].0));
+    }
+}
@@ -0,0 +1,393 @@
+use std::io::{self, IsTerminal, Write};
+use url::{Origin, Url};
+
+use crate::json::Value;
+use crate::psl;
+
+const ORIGIN_HELP: &str = r#"kurl origin - print the origin of URLs
+
+USAGE:
+    kurl origin [OPTIONS] <URL>...
+    <URLs> | kurl origin [OPTIONS]
+
+OPTIONS:
+        --tuple         Print each origin as a JSON tuple: {"scheme", "host",
+                        "port"}, with a null port for the scheme's default,
+                        or null for an opaque origin
+    -h, --help          Show this help message
+
+Origins follow the WHATWG URL Standard, as browsers compare them for CORS and
+the same-origin policy: scheme://host, with the port only when it isn't the
+scheme's default, and the host in punycode. URLs with schemes other than
+http, https, ws, wss, and ftp, such as file: or data:, have an opaque origin,
+printed as `null`. A blob: URL has the origin of the URL inside it.
+
+EXAMPLES:
+    kurl origin https://Example.COM:443/path
+    kurl origin --tuple http://localhost:8080/api
+"#;
+
+const SAME_ORIGIN_HELP: &str = r#"kurl same-origin - test whether two URLs have the same origin
+
+USAGE:
+    kurl same-origin [OPTIONS] <URL1> <URL2>
+
+OPTIONS:
+    -q, --quiet         Print nothing; only set the exit status
+    -h, --help          Show this help message
+
+Exits 0 if the URLs have the same origin, as CORS and the same-origin policy
+decide it, and 1 if not, after saying why: which of the scheme, host, and port
+differ, or that a URL has an opaque origin, which matches no other.
+
+EXAMPLES:
+    kurl same-origin https://app.example.com/ https://app.example.com:443/api
+    kurl same-origin -q "$page" "$api" || echo "needs CORS"
+"#;
+
+const SAME_SITE_HELP: &str = r#"kurl same-site - test whether two URLs are on the same site
+
+USAGE:
+    kurl same-site [OPTIONS] <URL1> <URL2>
+
+OPTIONS:
+        --schemeless    Ignore schemes, as the older same-site check did
+    -q, --quiet         Print nothing; only set the exit status
+    -h, --help          Show this help message
+
+Exits 0 if the URLs are on the same site and 1 if not, after saying why. A
+site is the scheme plus the registrable domain from the Public Suffix List, as
+browsers use it for SameSite cookies, so api.example.com and www.example.com
+are the same site, while alice.github.io and bob.github.io are not, since
+github.io is a public suffix. IP addresses are sites of their own.
+
+EXAMPLES:
+    kurl same-site https://www.example.com/ https://api.example.com/
+    kurl same-site --schemeless http://example.com/ https://example.com/
+"#;
+
+/// What `kurl same-origin` and `kurl same-site` compare.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Relation {
+    Origin,
+    Site,
+}
+
+pub fn compare(args: &[String], relation: Relation) {
+    let (help, name) = match relation {
+        Relation::Origin => (SAME_ORIGIN_HELP, "same-origin"),
+        Relation::Site => (SAME_SITE_HELP, "same-site"),
+    };
+    let mut quiet = false;
+    let mut schemeless = false;
+    let mut urls = Vec::new();
+    for arg in args {
+        match arg.as_str() {
+            "-h" | "--help" => {
+                println!("{}", help);
+                return;
+            }
+            "-q" | "--quiet" => quiet = true,
+            "--schemeless" if relation == Relation::Site => schemeless = true,
+            other if other.starts_with('-') => {
+                eprintln!("Unknown option for {}: {}", name, other);
+                std::process::exit(1);
+            }
+            other => urls.push(other),
+        }
+    }
+
+    let [left, right] = urls[..] else {
+        eprintln!("Usage: kurl {} [OPTIONS] <URL1> <URL2>", name);
+        std::process::exit(1);
+    };
+    let (left, right) = (
+        crate::parse_url_or_exit(left),
+        crate::parse_url_or_exit(right),
+    );
+    let (same, reason) = match relation {
+        Relation::Origin => same_origin(&left, &right),
+        Relation::Site => same_site(&left, &right, schemeless),
+    };
+    if !quiet {
+        println!("{}", reason);
+    }
+    if !same {
+        std::process::exit(1);
+    }
+}
+
+/// Whether the URLs have the same origin, and why.
+fn same_origin(left: &Url, right: &Url) -> (bool, String) {
+    let (Origin::Tuple(left_scheme, left_host, left_port), Origin::Tuple(scheme, host, port)) =
+        (left.origin(), right.origin())
+    else {
+        let opaque = if left.origin().is_tuple() {
+            right
+        } else {
+            left
+        };
+        return (
+            false,
+            format!(
+                "different origins: {} has an opaque origin, which matches no other",
+                opaque
+            ),
+        );
+    };
+    let mut differ = Vec::new();
+    if left_scheme != scheme {
+        differ.push("schemes");
+    }
+    if left_host != host {
+        differ.push("hosts");
+    }
+    if left_port != port {
+        differ.push("ports");
+    }
+    if differ.is_empty() {
+        return (
+            true,
+            format!("same origin: {}", left.origin().ascii_serialization()),
+        );
+    }
+    (
+        false,
+        format!(
+            "different origins: {} vs {} (the {} differ)",
+            left.origin().ascii_serialization(),
+            right.origin().ascii_serialization(),
+            differ.join(" and ")
+        ),
+    )
+}
+
+/// Whether the URLs are on the same site, with or without comparing schemes,
+/// and why.
+fn same_site(left: &Url, right: &Url, schemeless: bool) -> (bool, String) {
+    let site = |url: &Url| url.origin().is_tuple().then(|| psl::site(url)).flatten();
+    let (Some(left_site), Some(right_site)) = (site(left), site(right)) else {
+        let opaque = if site(left).is_some() { right } else { left };
+        return (
+            false,
+            format!(
+                "different sites: {} has an opaque origin, and so no site",
+                opaque
+            ),
+        );
+    };
+    let (left_scheme, left_host) = left_site.split_once("://").unwrap_or(("", &left_site));
+    let (right_scheme, right_host) = right_site.split_once("://").unwrap_or(("", &right_site));
+
+    if left_host != right_host {
+        let mut reason = format!("different sites: {} vs {}", left_site, right_site);
+        // Hosts under a multi-label public suffix look related but aren't.
+        if let (Some(url::Host::Domain(left)), Some(url::Host::Domain(right))) =
+            (left.host(), right.host())
+        {
+            let suffix = psl::list().public_suffix(left);
+            if suffix == psl::list().public_suffix(right) && suffix.contains('.') {
+                reason.push_str(&format!(", since {} is a public suffix", suffix));
+            }
+        }
+        return (false, reason);
+    }
+    if left_scheme != right_scheme && !schemeless {
+        return (
+            false,
+            format!(
+                "different sites: {} vs {} (the hosts share {}, but sites include the scheme; pass --schemeless to ignore it)",
+                left_site, right_site, left_host
+            ),
+        );
+    }
+    let site = if left_scheme == right_scheme {
+        left_site.as_str()
+    } else {
+        left_host
+    };
+    (true, format!("same site: {}", site))
+}
+
+pub fn run(args: &[String]) {
+    let mut tuple = false;
+    let mut urls = Vec::new();
+    for arg in args {
+        match arg.as_str() {
+            "-h" | "--help" => {
+                println!("{}", ORIGIN_HELP);
+                return;
+            }
+            "--tuple" => tuple = true,
+            other if other.starts_with('-') => {
+                eprintln!("Unknown option for origin: {}", other);
+                std::process::exit(1);
+            }
+            other => urls.push(other),
+        }
+    }
+
+    let buffer;
+    if urls.is_empty() {
+        if io::stdin().is_terminal() {
+            eprintln!("Usage: kurl origin [--tuple] <URL>...");
+            std::process::exit(1);
+        }
+        buffer = crate::read_stdin();
+        urls = buffer
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .collect();
+    }
+
+    let mut out = io::stdout().lock();
+    let mut failed = false;
+    for input in urls {
+        let url = match Url::parse(input) {
+            Ok(url) => url,
+            Err(e) => {
+                eprintln!("Failed to parse URL {}: {}", input, e);
+                failed = true;
+                continue;
+            }
+        };
+        let origin = url.origin();
+        let result = if tuple {
+            to_tuple(&origin)
+                .write(&mut out)
+                .and_then(|_| writeln!(out))
+        } else {
+            writeln!(out, "{}", origin.ascii_serialization())
+        };
+        if result.is_err() {
+            return;
+        }
+    }
+    if failed {
+        std::process::exit(1);
+    }
+}
+
+/// The origin as JSON: an object for a tuple origin, or null for an opaque
+/// one, which equals no other origin.
+fn to_tuple(origin: &Origin) -> Value {
+    let Origin::Tuple(scheme, host, port) = origin else {
+        return Value::Null;
+    };
+    // Only these special schemes have tuple origins.
+    let default = match scheme.as_str() {
+        "http" | "ws" => 80,
+        "https" | "wss" => 443,
+        "ftp" => 21,
+        _ => 0,
+    };
+    let port = if *port == default {
+        Value::Null
+    } else {
+        Value::from(*port)
+    };
+    Value::Object(vec![
+        ("scheme".to_string(), Value::from(scheme.as_str())),
+        ("host".to_string(), Value::from(host.to_string())),
+        ("port".to_string(), port),
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn origin(url: &str) -> (String, String) {
+        let origin = Url::parse(url).unwrap().origin();
+        (origin.ascii_serialization(), to_tuple(&origin).to_json())
+    }
+
+    #[test]
+    fn test_origin() {
+        assert_eq!(
+            origin("https://Example.COM:443/path?q#f"),
+            (
+                "https://example.com".to_string(),
+                r#"{"scheme":"https","host":"example.com","port":null}"#.to_string()
+            )
+        );
+        assert_eq!(
+            origin("http://[::1]:8080/api"),
+            (
+                "http://[::1]:8080".to_string(),
+                r#"{"scheme":"http","host":"[::1]","port":8080}"#.to_string()
+            )
+        );
+        assert_eq!(
+            origin("blob:https://bücher.example/uuid"),
+            (
+                "https://xn--bcher-kva.example".to_string(),
+                r#"{"scheme":"https","host":"xn--bcher-kva.example","port":null}"#.to_string()
+            )
+        );
+        assert_eq!(
+            origin("file:///etc/hosts"),
+            ("null".to_string(), "null".to_string())
+        );
+        assert_eq!(
+            origin("data:text/plain,hi"),
+            ("null".to_string(), "null".to_string())
+        );
+    }
+
+    #[test]
+    fn test_same_origin() {
+        let same = |a: &str, b: &str| same_origin(&Url::parse(a).unwrap(), &Url::parse(b).unwrap());
+        assert_eq!(
+            same("https://Example.com/a", "https://example.com:443/b?q"),
+            (true, "same origin: https://example.com".to_string())
+        );
+        assert_eq!(
+            same("http://example.com/", "https://example.com:8443/"),
+            (
+                false,
+                "different origins: http://example.com vs https://example.com:8443 (the schemes and ports differ)"
+                    .to_string()
+            )
+        );
+        assert_eq!(
+            same("https://example.com/", "data:text/plain,hi"),
+            (
+                false,
+                "different origins: data:text/plain,hi has an opaque origin, which matches no other"
+                    .to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn test_same_site() {
+        let same = |a: &str, b: &str, schemeless| {
+            same_site(&Url::parse(a).unwrap(), &Url::parse(b).unwrap(), schemeless)
+        };
+        assert_eq!(
+            same(
+                "https://www.example.com/",
+                "https://api.example.com:8443/",
+                false
+            ),
+            (true, "same site: https://example.com".to_string())
+        );
+        assert_eq!(
+            same("https://alice.github.io/", "https://bob.github.io/", false),
+            (
+                false,
+                "different sites: https://alice.github.io vs https://bob.github.io, since github.io is a public suffix"
+                    .to_string()
+            )
+        );
+        assert!(!same("http://example.com/", "https://example.com/", false).0);
+        assert_eq!(
+            same("http://example.com/", "https://www.example.com/", true),
+            (true, "same site: example.com".to_string())
+        );
+        assert!(!same("https://127.0.0.1/", "https://127.0.0.2/", false).0);
+        assert!(!same("file:///a", "file:///a", false).0);
+    }
+}
@@ -0,0 +1,100 @@
+use std::env;
+use std::io::{self, IsTerminal, Write};
+use std::process::{Command, Stdio};
+
+use crate::term;
+
+/// Where main prints records: straight to stdout, or into a buffer that is
+/// shown through a pager once it's complete, if it's taller than the terminal.
+pub enum Output {
+    Direct(io::StdoutLock<'static>),
+    Paged(Vec<u8>),
+}
+
+impl Output {
+    /// Pages only when asked to and stdout is a terminal, as git does.
+    pub fn new(page: bool) -> Output {
+        if page && io::stdout().is_terminal() {
+            Output::Paged(Vec::new())
+        } else {
+            Output::Direct(io::stdout().lock())
+        }
+    }
+
+    /// Shows buffered output, through the pager if it doesn't fit.
+    pub fn finish(self) {
+        let Output::Paged(text) = self else {
+            return;
+        };
+        let lines = text.iter().filter(|&&b| b == b'\n').count();
+        let pager = pager_command(env::var("KURL_PAGER").ok(), env::var("PAGER").ok());
+        if let Some(pager) = pager.filter(|_| term::rows().is_some_and(|h| lines >= h))
+            && spawn(&pager, &text)
+        {
+            return;
+        }
+        let _ = io::stdout().write_all(&text);
+    }
+}
+
+impl Write for Output {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Output::Direct(out) => out.write(buf),
+            Output::Paged(text) => text.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Output::Direct(out) => out.flush(),
+            Output::Paged(_) => Ok(()),
+        }
+    }
+}
+
+/// The pager to run: `KURL_PAGER`, then `PAGER`, then less. An empty value
+/// or `cat` turns paging off.
+fn pager_command(kurl_pager: Option<String>, pager: Option<String>) -> Option<String> {
+    let pager = kurl_pager.or(pager).unwrap_or_else(|| "less".to_string());
+    let pager = pager.trim();
+    (!pager.is_empty() && pager != "cat").then(|| pager.to_string())
+}
+
+/// Runs `pager` through the shell, so it can have arguments, and feeds it
+/// `text`; returns false if it couldn't be started. Like git, less gets
+/// `LESS=FRX` unless `LESS` is set, so it keeps colors and the screen.
+fn spawn(pager: &str, text: &[u8]) -> bool {
+    let mut command = Command::new("sh");
+    command.arg("-c").arg(pager).stdin(Stdio::piped());
+    if env::var_os("LESS").is_none() {
+        command.env("LESS", "FRX");
+    }
+    let Ok(mut child) = command.spawn() else {
+        return false;
+    };
+    if let Some(mut stdin) = child.stdin.take() {
+        // Quitting the pager early closes the pipe, which isn't an error.
+        let _ = stdin.write_all(text);
+    }
+    let _ = child.wait();
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pager_command() {
+        let some = |pager: &str| Some(pager.to_string());
+        assert_eq!(pager_command(None, None), some("less"));
+        assert_eq!(pager_command(None, some("more")), some("more"));
+        assert_eq!(
+            pager_command(some("less -S "), some("more")),
+            some("less -S")
+        );
+        assert_eq!(pager_command(some(""), some("more")), None);
+        assert_eq!(pager_command(some("cat"), None), None);
+    }
+}
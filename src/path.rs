@@ -0,0 +1,243 @@
+use std::io::{self, IsTerminal};
+use url::Url;
+
+use crate::OutputOptions;
+use crate::transform::{self, TransformOptions};
+
+const PATH_HELP: &str = r#"kurl path - edit individual path segments
+
+USAGE:
+    kurl path [OPTIONS] [<URL>...]
+    <URLs> | kurl path [OPTIONS]
+
+OPTIONS:
+        --append <SEGMENT>  Add a segment at the end
+        --prepend <SEGMENT> Add a segment at the start
+        --pop               Remove the last segment
+        --replace <N=SEGMENT>
+                            Replace segment N, counting from 0, or from -1 for
+                            the last one
+        --remove <N>        Remove segment N
+    -j, --json              Print each result's components as JSON
+        --dry-run           Report how many URLs would change without printing them
+        --show-diff         Print a diff of the URLs that would change
+        --verify-idempotent Fail, printing nothing, unless a second pass leaves
+                            every result unchanged
+    -h, --help              Show this help message
+
+Edits apply in order, to each URL argument or, with none, to each line of
+stdin. A new segment is one segment, percent-encoded as needed, so a `/` or
+`%` in it is encoded rather than starting another segment or an escape.
+Untouched segments keep their encoding, and a trailing slash stays, so
+appending to /api/ gives /api/v2/ rather than /api//v2. Editing a segment
+that isn't there fails for that URL.
+
+EXAMPLES:
+    kurl path https://example.com/users --append 42 --append 'Q&A notes'
+    kurl path https://example.com/v1/users/ --replace 0=v2 --pop
+    cat urls.txt | kurl path --prepend api
+"#;
+
+/// One change to a URL's path segments.
+#[derive(Debug, PartialEq)]
+pub enum Edit<'a> {
+    Append(&'a str),
+    Prepend(&'a str),
+    Pop,
+    Replace(isize, &'a str),
+    Remove(isize),
+}
+
+pub fn run(args: &[String]) {
+    let mut edits = Vec::new();
+    let mut urls = Vec::new();
+    let mut options = TransformOptions::default();
+    let mut output = OutputOptions::default();
+
+    let mut rest = args.iter();
+    while let Some(arg) = rest.next() {
+        match arg.as_str() {
+            "-h" | "--help" => {
+                println!("{}", PATH_HELP);
+                return;
+            }
+            "--pop" => edits.push(Edit::Pop),
+            flag @ ("--append" | "--prepend" | "--replace" | "--remove") => {
+                let value = crate::option_value(&mut rest, flag);
+                let edit = match flag {
+                    "--append" => Edit::Append(value),
+                    "--prepend" => Edit::Prepend(value),
+                    "--replace" => {
+                        let Some((index, segment)) = value
+                            .split_once('=')
+                            .and_then(|(index, segment)| Some((index.parse().ok()?, segment)))
+                        else {
+                            eprintln!("Expected N=SEGMENT for --replace, got {}", value);
+                            std::process::exit(1);
+                        };
+                        Edit::Replace(index, segment)
+                    }
+                    _ => Edit::Remove(value.parse().unwrap_or_else(|_| {
+                        eprintln!("Expected a segment number for --remove, got {}", value);
+                        std::process::exit(1);
+                    })),
+                };
+                edits.push(edit);
+            }
+            other if options.accept(other) => {}
+            other if output.accept(other, &mut rest) => {}
+            other if other.starts_with('-') => {
+                eprintln!("Unknown option for path: {}", other);
+                std::process::exit(1);
+            }
+            other => urls.push(other),
+        }
+    }
+
+    let usage = "Usage: kurl path [--append SEGMENT] [--prepend SEGMENT] [--pop] [--replace N=SEGMENT] [--remove N] [<URL>...]";
+    if edits.is_empty() {
+        eprintln!("{}", usage);
+        std::process::exit(1);
+    }
+
+    let mut buffer = String::new();
+    if urls.is_empty() {
+        if io::stdin().is_terminal() {
+            eprintln!("{}", usage);
+            std::process::exit(1);
+        }
+        buffer = crate::read_stdin();
+        urls.push("-");
+    }
+    let inputs = crate::batch_inputs(&urls, &buffer);
+
+    transform::run(&inputs, &options, &output, |url| apply(url, &edits));
+}
+
+/// Applies `edits` to the raw path segments, so that only the segments an
+/// edit adds are encoded. The trailing slash, if there is one, isn't a
+/// segment, and stays.
+pub fn apply(url: &Url, edits: &[Edit]) -> Result<Url, String> {
+    let Some(raw) = url.path_segments() else {
+        return Err(format!("{} has no path segments", url));
+    };
+    let mut segments: Vec<String> = raw.map(str::to_string).collect();
+    let trailing_slash = segments.len() > 1 && segments.last().is_some_and(String::is_empty);
+    if segments.last().is_some_and(String::is_empty) {
+        segments.pop();
+    }
+
+    for edit in edits {
+        match *edit {
+            Edit::Append(segment) => segments.push(encode(url, segment)),
+            Edit::Prepend(segment) => segments.insert(0, encode(url, segment)),
+            Edit::Pop => {
+                if segments.pop().is_none() {
+                    return Err(format!("{} has no segment to pop", url));
+                }
+            }
+            Edit::Replace(index, segment) => {
+                let index = position(&segments, index, url)?;
+                segments[index] = encode(url, segment);
+            }
+            Edit::Remove(index) => {
+                let index = position(&segments, index, url)?;
+                segments.remove(index);
+            }
+        }
+    }
+
+    let mut path = format!("/{}", segments.join("/"));
+    if trailing_slash && !segments.is_empty() {
+        path.push('/');
+    }
+    let mut url = url.clone();
+    url.set_path(&path);
+    Ok(url)
+}
+
+/// The position of segment `index`, which counts back from the end when
+/// negative.
+fn position(segments: &[String], index: isize, url: &Url) -> Result<usize, String> {
+    let position = if index < 0 {
+        segments.len().checked_sub(index.unsigned_abs())
+    } else {
+        Some(index as usize).filter(|&i| i < segments.len())
+    };
+    position.ok_or_else(|| {
+        format!(
+            "{} has no segment {} ({} segments)",
+            url,
+            index,
+            segments.len()
+        )
+    })
+}
+
+/// Encodes `text` as one path segment of `url`, the way the URL itself
+/// encodes segments.
+fn encode(url: &Url, text: &str) -> String {
+    let mut scratch = url.clone();
+    if let Ok(mut segments) = scratch.path_segments_mut() {
+        segments.clear().push(text);
+    }
+    scratch.path()[1..].to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn edited(url: &str, edits: &[Edit]) -> Result<String, String> {
+        apply(&Url::parse(url).unwrap(), edits).map(|url| url.to_string())
+    }
+
+    #[test]
+    fn test_apply() {
+        assert_eq!(
+            edited(
+                "https://example.com/v1/users/?page=2",
+                &[Edit::Replace(0, "v2"), Edit::Append("a/b 100%")]
+            ),
+            Ok("https://example.com/v2/users/a%2Fb%20100%25/?page=2".to_string())
+        );
+        assert_eq!(
+            edited(
+                "https://example.com/caf%C3%A9/menu",
+                &[Edit::Pop, Edit::Prepend("api")]
+            ),
+            Ok("https://example.com/api/caf%C3%A9".to_string())
+        );
+        assert_eq!(
+            edited("https://example.com/", &[Edit::Append("users")]),
+            Ok("https://example.com/users".to_string())
+        );
+        assert_eq!(
+            edited(
+                "https://example.com/a/b/c",
+                &[Edit::Remove(-1), Edit::Remove(0)]
+            ),
+            Ok("https://example.com/b".to_string())
+        );
+        assert_eq!(
+            edited("https://example.com/a/", &[Edit::Pop]),
+            Ok("https://example.com/".to_string())
+        );
+    }
+
+    #[test]
+    fn test_apply_errors() {
+        assert!(
+            edited("https://example.com/", &[Edit::Pop])
+                .unwrap_err()
+                .contains("no segment to pop")
+        );
+        assert!(
+            edited("https://example.com/a", &[Edit::Replace(1, "b")])
+                .unwrap_err()
+                .contains("no segment 1 (1 segments)")
+        );
+        assert!(edited("https://example.com/a", &[Edit::Remove(-2)]).is_err());
+        assert!(edited("mailto:a@example.com", &[Edit::Append("x")]).is_err());
+    }
+}
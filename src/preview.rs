@@ -0,0 +1,106 @@
+use std::io::{self, Write};
+
+use crate::{OutputOptions, ParseOptions, Records, config};
+
+const PREVIEW_HELP: &str = r#"kurl preview - summarize a picker line for fzf's preview window
+
+USAGE:
+    kurl preview [OPTIONS] <LINE>
+
+OPTIONS:
+    -j, --json          Output as JSON instead of formatted text
+        --get <FIELD>   Print only FIELD, as with `kurl --get`
+        --lenient       Assume https:// for input without a scheme
+        --default-scheme <NAME>
+                        Scheme for protocol-relative input like //host/path
+    -h, --help          Show this help message
+
+LINE is the URL at the start of the line, up to the first tab, so annotated
+lists work too. Problems are printed in place of the summary and never make
+the command fail, since fzf shows the output either way:
+
+    cat urls.txt | kurl --fzf | fzf --preview 'kurl preview {}'
+"#;
+
+pub fn run(args: &[String]) {
+    let mut output = OutputOptions::default();
+    let mut parsing = ParseOptions::from_config(&config::load());
+    let mut line = None;
+
+    let mut rest = args.iter();
+    while let Some(arg) = rest.next() {
+        match arg.as_str() {
+            "-h" | "--help" => {
+                println!("{}", PREVIEW_HELP);
+                return;
+            }
+            other if output.accept(other, &mut rest) => {}
+            other if parsing.accept(other, &mut rest) => {}
+            other if line.is_none() => line = Some(other),
+            other => {
+                eprintln!("Unknown option for preview: {}", other);
+                std::process::exit(1);
+            }
+        }
+    }
+
+    let Some(line) = line else {
+        eprintln!("Usage: kurl preview [--json] <LINE>");
+        std::process::exit(1);
+    };
+    let mut out = io::stdout().lock();
+    let _ = preview(&mut out, line, &parsing, &output);
+}
+
+fn preview<W: Write>(
+    writer: &mut W,
+    line: &str,
+    parsing: &ParseOptions,
+    output: &OutputOptions,
+) -> io::Result<()> {
+    let input = line.split('\t').next().unwrap_or_default().trim();
+    if input.is_empty() {
+        return Ok(());
+    }
+    match parsing.parse(input) {
+        Ok((url, mut extra)) => {
+            if parsing.filter(&url, &mut extra) {
+                if let Some(mismatch) = parsing.mismatch(&url) {
+                    return writeln!(writer, "Left out: {}", mismatch);
+                }
+                let reason = parsing.denial(&url).unwrap_or_default();
+                return writeln!(writer, "Denied: {}", reason);
+            }
+            Records::new(writer, output).write(&url, &extra)
+        }
+        Err(e) => writeln!(writer, "Not a URL: {}", e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn preview_text(line: &str, output: &OutputOptions) -> String {
+        let mut buffer = Vec::new();
+        preview(&mut buffer, line, &ParseOptions::default(), output).unwrap();
+        String::from_utf8(buffer).unwrap()
+    }
+
+    #[test]
+    fn test_preview_uses_the_first_column() {
+        let output = OutputOptions {
+            get: vec!["host".to_string(), "query.q".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(
+            preview_text("https://example.com/?q=1\tExample page", &output),
+            "example.com\t1\n"
+        );
+        assert_eq!(preview_text("  \t", &output), "");
+        assert_eq!(
+            preview_text("example.com", &output),
+            "Not a URL: relative URL without a base (pass --lenient to assume https://)\n"
+        );
+    }
+}
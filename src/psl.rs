@@ -0,0 +1,451 @@
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::process::Command;
+use std::sync::OnceLock;
+
+use crate::config;
+
+const BUNDLED_LIST: &str = include_str!("../data/public_suffix_list.dat");
+
+/// Where `kurl psl update` downloads the list from by default.
+const LIST_URL: &str = "https://publicsuffix.org/list/public_suffix_list.dat";
+
+const PSL_HELP: &str = r#"kurl psl - manage the Public Suffix List
+
+USAGE:
+    kurl psl update [--url <URL>]
+    kurl psl status
+
+COMMANDS:
+    update              Download the current list with curl and cache it,
+                        so every command uses it instead of the bundled one
+    status              Show which list is in use and how many rules it has
+
+OPTIONS:
+        --url <URL>     Where to download the list from (default:
+                        https://publicsuffix.org/list/public_suffix_list.dat)
+    -h, --help          Show this help message
+
+The list decides registrable domains, public suffixes, sites, and wildcards.
+kurl bundles a copy, which ages as registries add suffixes; the cached list
+lives in kurl/public_suffix_list.dat under $XDG_CACHE_HOME, %LOCALAPPDATA%,
+or ~/.cache, and deleting it goes back to the bundled one.
+
+EXAMPLES:
+    kurl psl update
+    kurl psl status
+"#;
+
+/// A parsed Public Suffix List, with rules stored in their ASCII (punycode) form
+/// so they can be compared against `Url::host_str` directly.
+pub struct List {
+    rules: HashSet<String>,
+    /// `*.example` rules, stored without the leading `*.`.
+    wildcards: HashSet<String>,
+    /// `!www.example` rules, stored without the leading `!`.
+    exceptions: HashSet<String>,
+}
+
+impl List {
+    pub fn parse(text: &str) -> List {
+        let mut list = List {
+            rules: HashSet::new(),
+            wildcards: HashSet::new(),
+            exceptions: HashSet::new(),
+        };
+
+        for line in text.lines() {
+            let Some(rule) = line.split_whitespace().next() else {
+                continue;
+            };
+            if rule.starts_with("//") {
+                continue;
+            }
+
+            let rule = if rule.is_ascii() {
+                rule.to_ascii_lowercase()
+            } else {
+                url::quirks::domain_to_ascii(rule)
+            };
+            if let Some(base) = rule.strip_prefix("*.") {
+                list.wildcards.insert(base.to_string());
+            } else if let Some(base) = rule.strip_prefix('!') {
+                list.exceptions.insert(base.to_string());
+            } else if !rule.is_empty() {
+                list.rules.insert(rule);
+            }
+        }
+
+        list
+    }
+
+    /// Returns the public suffix of `domain`. Domains that match no rule fall
+    /// back to the implicit `*` rule, so their last label is the suffix.
+    pub fn public_suffix<'a>(&self, domain: &'a str) -> &'a str {
+        let domain = domain.strip_suffix('.').unwrap_or(domain);
+
+        let mut start = 0;
+        loop {
+            let candidate = &domain[start..];
+            let parent = candidate.find('.').map(|i| &candidate[i + 1..]);
+
+            if self.exceptions.contains(candidate) {
+                return parent.unwrap_or(candidate);
+            }
+            if self.rules.contains(candidate) || parent.is_some_and(|p| self.wildcards.contains(p))
+            {
+                return candidate;
+            }
+
+            match candidate.find('.') {
+                Some(dot) => start += dot + 1,
+                None => return candidate,
+            }
+        }
+    }
+
+    /// Lists the rules at or under `tld` in the list's own notation (`*.` for
+    /// wildcards, `!` for exceptions), sorted, in ASCII form.
+    pub fn rules_under(&self, tld: &str) -> Vec<String> {
+        let under = |rule: &&String| {
+            rule.as_str() == tld || rule.strip_suffix(tld).is_some_and(|r| r.ends_with('.'))
+        };
+        let mut rules: Vec<String> = self.rules.iter().filter(under).cloned().collect();
+        rules.extend(
+            self.wildcards
+                .iter()
+                .filter(under)
+                .map(|r| format!("*.{}", r)),
+        );
+        rules.extend(
+            self.exceptions
+                .iter()
+                .filter(under)
+                .map(|r| format!("!{}", r)),
+        );
+        rules.sort();
+        rules
+    }
+
+    /// Whether this looks like a whole Public Suffix List rather than some
+    /// other download, such as an error page.
+    fn is_complete(&self) -> bool {
+        self.rules.contains("com") && self.rules.len() > 1000
+    }
+
+    /// Counts the rules of every kind.
+    fn rule_count(&self) -> usize {
+        self.rules.len() + self.wildcards.len() + self.exceptions.len()
+    }
+
+    /// Returns the registrable domain (eTLD+1) of `domain`, or `None` when the
+    /// domain is itself a public suffix.
+    pub fn registrable_domain<'a>(&self, domain: &'a str) -> Option<&'a str> {
+        let domain = domain.strip_suffix('.').unwrap_or(domain);
+        let suffix = self.public_suffix(domain);
+        let prefix = domain.strip_suffix(suffix)?.strip_suffix('.')?;
+        let start = prefix.rfind('.').map_or(0, |i| i + 1);
+        Some(&domain[start..])
+    }
+}
+
+/// The list in use: the one `kurl psl update` cached, if there is a usable
+/// one, or else the one bundled into the binary.
+pub fn list() -> &'static List {
+    static LIST: OnceLock<List> = OnceLock::new();
+    LIST.get_or_init(|| cached().unwrap_or_else(|| List::parse(BUNDLED_LIST)))
+}
+
+fn cache_path() -> Option<PathBuf> {
+    Some(config::cache_dir()?.join("public_suffix_list.dat"))
+}
+
+fn cached() -> Option<List> {
+    let text = std::fs::read_to_string(cache_path()?).ok()?;
+    Some(List::parse(&text)).filter(List::is_complete)
+}
+
+/// Returns the public suffix of the URL's host, for domain hosts.
+pub fn public_suffix(url: &url::Url) -> Option<&str> {
+    match url.host()? {
+        url::Host::Domain(domain) => Some(list().public_suffix(domain)),
+        _ => None,
+    }
+}
+
+/// Returns the registrable domain of the URL's host, for domain hosts that
+/// aren't public suffixes themselves.
+pub fn registrable_domain(url: &url::Url) -> Option<&str> {
+    match url.host()? {
+        url::Host::Domain(domain) => list().registrable_domain(domain),
+        _ => None,
+    }
+}
+
+/// A domain host taken apart at its public suffix, as
+/// `a.b.example.co.uk` is `a.b`, `example`, and `co.uk`.
+#[derive(Debug, PartialEq)]
+pub struct Parts<'a> {
+    /// The labels before the registrable domain, if there are any.
+    pub subdomain: Option<&'a str>,
+    /// The label before the suffix, unless the host is a public suffix.
+    pub domain: Option<&'a str>,
+    pub suffix: &'a str,
+}
+
+/// Splits the URL's host into subdomain, domain, and suffix, for domain hosts.
+pub fn parts(url: &url::Url) -> Option<Parts<'_>> {
+    let url::Host::Domain(host) = url.host()? else {
+        return None;
+    };
+    let host = host.strip_suffix('.').unwrap_or(host);
+    let suffix = list().public_suffix(host);
+    let Some(registrable) = list().registrable_domain(host) else {
+        return Some(Parts {
+            subdomain: None,
+            domain: None,
+            suffix,
+        });
+    };
+    let domain = registrable.strip_suffix(suffix)?.strip_suffix('.')?;
+    let subdomain = host.strip_suffix(registrable)?.strip_suffix('.');
+    Some(Parts {
+        subdomain,
+        domain: Some(domain),
+        suffix,
+    })
+}
+
+/// Computes the URL's "schemeful site": its scheme plus registrable domain,
+/// or plus the host itself for IP addresses and hosts that are public suffixes.
+/// URLs without a host have an opaque origin, and so no site.
+pub fn site(url: &url::Url) -> Option<String> {
+    let host = url.host()?;
+    let site_host = match host {
+        url::Host::Domain(domain) => list().registrable_domain(domain).unwrap_or(domain),
+        _ => url.host_str()?,
+    };
+    Some(format!("{}://{}", url.scheme(), site_host))
+}
+
+/// Returns the `*.` wildcard for the registrable domain of the URL's host, in
+/// its ASCII (punycode) form, as certificate SANs and firewall rules need it.
+/// IP addresses and hosts that are public suffixes have none.
+pub fn wildcard(url: &url::Url) -> Option<String> {
+    let url::Host::Domain(domain) = url.host()? else {
+        return None;
+    };
+    // Hosts of non-special URLs keep their case and percent-encoding.
+    let domain = url::quirks::domain_to_ascii(&crate::edit::percent_decode(domain));
+    let registrable = list().registrable_domain(&domain)?;
+    Some(format!("*.{}", registrable))
+}
+
+pub fn run(args: &[String]) {
+    let mut command = None;
+    let mut source = LIST_URL;
+    let mut rest = args.iter();
+    while let Some(arg) = rest.next() {
+        match arg.as_str() {
+            "-h" | "--help" => {
+                println!("{}", PSL_HELP);
+                return;
+            }
+            "--url" => source = crate::option_value(&mut rest, arg),
+            other if command.is_none() && !other.starts_with('-') => command = Some(other),
+            other => {
+                eprintln!("Unknown option for psl: {}", other);
+                std::process::exit(1);
+            }
+        }
+    }
+
+    let Some(path) = cache_path() else {
+        eprintln!("No cache directory: set XDG_CACHE_HOME or HOME");
+        std::process::exit(1);
+    };
+    match command {
+        Some("update") => {
+            let count = update(source, &path).unwrap_or_else(|e| {
+                eprintln!("Failed to update the Public Suffix List: {}", e);
+                std::process::exit(1);
+            });
+            println!("Saved {} rules to {}", count, path.display());
+        }
+        Some("status") => match cached() {
+            Some(list) => println!("Using {} ({} rules)", path.display(), list.rule_count()),
+            None => println!(
+                "Using the bundled list ({} rules); run `kurl psl update` for the current one",
+                List::parse(BUNDLED_LIST).rule_count()
+            ),
+        },
+        Some(other) => {
+            eprintln!("Unknown psl command: {} (expected update or status)", other);
+            std::process::exit(1);
+        }
+        None => {
+            eprintln!("Usage: kurl psl update|status");
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Downloads the list from `source` into `path`, checking that it is one
+/// before replacing the cached copy, and returns its number of rules.
+fn update(source: &str, path: &std::path::Path) -> Result<usize, String> {
+    let output = Command::new("curl")
+        .args(["-fsSL", "--", source])
+        .output()
+        .map_err(|e| format!("could not run curl: {}", e))?;
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+    let text = String::from_utf8(output.stdout).map_err(|_| "the list is not UTF-8")?;
+    let list = List::parse(&text);
+    if !list.is_complete() {
+        return Err(format!(
+            "{} does not look like the Public Suffix List",
+            source
+        ));
+    }
+
+    let dir = path.parent().ok_or("no cache directory")?;
+    std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    // Written aside and renamed, so a failed write never leaves half a list.
+    let partial = path.with_extension("dat.partial");
+    std::fs::write(&partial, &text)
+        .and_then(|_| std::fs::rename(&partial, path))
+        .map_err(|e| e.to_string())?;
+    Ok(list.rule_count())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use url::Url;
+
+    const RULES: &str = "// comment\ncom\nco.uk\nuk\n*.ck\n!www.ck\ngithub.io\n公司.cn\ncn\n";
+
+    #[test]
+    fn test_public_suffix_rules() {
+        let list = List::parse(RULES);
+        assert_eq!(list.public_suffix("www.example.com"), "com");
+        assert_eq!(list.public_suffix("a.b.example.co.uk"), "co.uk");
+        assert_eq!(list.public_suffix("foo.bar.ck"), "bar.ck");
+        assert_eq!(list.public_suffix("www.ck"), "ck");
+        assert_eq!(list.public_suffix("example.unknown"), "unknown");
+        assert_eq!(list.public_suffix("x.xn--55qx5d.cn"), "xn--55qx5d.cn");
+    }
+
+    #[test]
+    fn test_rules_under() {
+        let list = List::parse(RULES);
+        assert_eq!(list.rules_under("ck"), vec!["!www.ck", "*.ck"]);
+        assert_eq!(list.rules_under("cn"), vec!["cn", "xn--55qx5d.cn"]);
+        assert_eq!(list.rules_under("k"), Vec::<String>::new());
+    }
+
+    #[test]
+    fn test_registrable_domain() {
+        let list = List::parse(RULES);
+        assert_eq!(
+            list.registrable_domain("a.b.example.co.uk"),
+            Some("example.co.uk")
+        );
+        assert_eq!(list.registrable_domain("example.com."), Some("example.com"));
+        assert_eq!(
+            list.registrable_domain("user.github.io"),
+            Some("user.github.io")
+        );
+        assert_eq!(list.registrable_domain("a.www.ck"), Some("www.ck"));
+        assert_eq!(list.registrable_domain("co.uk"), None);
+        assert_eq!(list.registrable_domain("localhost"), None);
+    }
+
+    #[test]
+    fn test_url_suffixes() {
+        let parts = |s: &str| {
+            let url = Url::parse(s).unwrap();
+            (
+                public_suffix(&url).map(str::to_string),
+                registrable_domain(&url).map(str::to_string),
+            )
+        };
+        assert_eq!(
+            parts("https://a.b.example.co.uk/"),
+            (Some("co.uk".into()), Some("example.co.uk".into()))
+        );
+        assert_eq!(parts("https://co.uk/"), (Some("co.uk".into()), None));
+        assert_eq!(parts("https://192.0.2.1/"), (None, None));
+        assert_eq!(parts("mailto:a@example.com"), (None, None));
+        assert!(List::parse(BUNDLED_LIST).is_complete());
+        assert!(!List::parse(RULES).is_complete());
+    }
+
+    #[test]
+    fn test_parts() {
+        let parts = |s: &str| {
+            let url = Url::parse(s).unwrap();
+            parts(&url).map(|p| {
+                (
+                    p.subdomain.map(str::to_string),
+                    p.domain.map(str::to_string),
+                    p.suffix.to_string(),
+                )
+            })
+        };
+        let some = |s: &str| Some(s.to_string());
+        assert_eq!(
+            parts("https://a.b.example.co.uk/"),
+            Some((some("a.b"), some("example"), "co.uk".to_string()))
+        );
+        assert_eq!(
+            parts("https://example.com./"),
+            Some((None, some("example"), "com".to_string()))
+        );
+        assert_eq!(
+            parts("https://co.uk/"),
+            Some((None, None, "co.uk".to_string()))
+        );
+        assert_eq!(parts("https://[::1]/"), None);
+    }
+
+    #[test]
+    fn test_site() {
+        let site_of = |s: &str| site(&Url::parse(s).unwrap());
+        assert_eq!(
+            site_of("https://a.b.example.co.uk:8443/x"),
+            Some("https://example.co.uk".into())
+        );
+        assert_eq!(
+            site_of("http://127.0.0.1:8080/"),
+            Some("http://127.0.0.1".into())
+        );
+        assert_eq!(site_of("https://[::1]/"), Some("https://[::1]".into()));
+        assert_eq!(
+            site_of("https://localhost/"),
+            Some("https://localhost".into())
+        );
+        assert_eq!(site_of("mailto:a@example.com"), None);
+    }
+
+    #[test]
+    fn test_wildcard() {
+        let wildcard_of = |s: &str| wildcard(&Url::parse(s).unwrap());
+        assert_eq!(
+            wildcard_of("https://a.b.example.co.uk/"),
+            Some("*.example.co.uk".into())
+        );
+        assert_eq!(
+            wildcard_of("https://www.Bücher.com/"),
+            Some("*.xn--bcher-kva.com".into())
+        );
+        assert_eq!(
+            wildcard_of("ssh://git.Bücher.de/repo"),
+            Some("*.xn--bcher-kva.de".into())
+        );
+        assert_eq!(wildcard_of("https://co.uk/"), None);
+        assert_eq!(wildcard_of("https://127.0.0.1/"), None);
+        assert_eq!(wildcard_of("mailto:a@example.com"), None);
+    }
+}
@@ -0,0 +1,928 @@
+use std::io::{self, IsTerminal};
+use url::Url;
+use url::form_urlencoded;
+
+use crate::OutputOptions;
+use crate::json::Value;
+use crate::regex::Regex;
+use crate::transform::{self, TransformOptions};
+
+const QUERY_HELP: &str = r#"kurl query - edit individual query parameters
+
+USAGE:
+    kurl query [OPTIONS] [<URL>...]
+    <URLs> | kurl query [OPTIONS]
+
+OPTIONS:
+        --add <KEY=VALUE>   Append a parameter, even if KEY is already present
+        --set <KEY=VALUE>   Replace the first KEY parameter, dropping any others,
+                            or append one if there is none
+        --remove <KEY>      Remove every KEY parameter
+    -j, --json              Print each result's components as JSON
+        --dry-run           Report how many URLs would change without printing them
+        --show-diff         Print a diff of the URLs that would change
+        --verify-idempotent Fail, printing nothing, unless a second pass leaves
+                            every result unchanged
+    -h, --help              Show this help message
+
+Edits apply in order, to each URL argument or, with none, to each line of
+stdin. Keys match after decoding, so `--remove q` also removes `%71=x`. New
+parameters are form-encoded; untouched ones keep their position and their
+original encoding.
+
+EXAMPLES:
+    kurl query 'https://example.com/search?q=rust&page=1' --set page=2 --add lang=en
+    cat urls.txt | kurl query --remove utm_source --remove utm_medium
+"#;
+
+/// One change to a URL's query parameters.
+#[derive(Debug, PartialEq)]
+pub enum Edit<'a> {
+    Add(&'a str, &'a str),
+    Set(&'a str, &'a str),
+    Remove(&'a str),
+}
+
+pub fn run(args: &[String]) {
+    let mut edits = Vec::new();
+    let mut urls = Vec::new();
+    let mut options = TransformOptions::default();
+    let mut output = OutputOptions::default();
+
+    let mut rest = args.iter();
+    while let Some(arg) = rest.next() {
+        match arg.as_str() {
+            "-h" | "--help" => {
+                println!("{}", QUERY_HELP);
+                return;
+            }
+            flag @ ("--add" | "--set" | "--remove") => {
+                let Some(value) = rest.next() else {
+                    eprintln!("Missing value for {}", flag);
+                    std::process::exit(1);
+                };
+                let edit = match (flag, value.split_once('=')) {
+                    ("--remove", _) => Edit::Remove(value),
+                    ("--add", Some((key, value))) => Edit::Add(key, value),
+                    ("--set", Some((key, value))) => Edit::Set(key, value),
+                    _ => {
+                        eprintln!("Expected KEY=VALUE for {}, got {}", flag, value);
+                        std::process::exit(1);
+                    }
+                };
+                edits.push(edit);
+            }
+            other if options.accept(other) => {}
+            other if output.accept(other, &mut rest) => {}
+            other if other.starts_with('-') => {
+                eprintln!("Unknown option for query: {}", other);
+                std::process::exit(1);
+            }
+            other => urls.push(other),
+        }
+    }
+
+    if edits.is_empty() {
+        eprintln!(
+            "Usage: kurl query [--add KEY=VALUE] [--set KEY=VALUE] [--remove KEY] [<URL>...]"
+        );
+        std::process::exit(1);
+    }
+
+    let mut buffer = String::new();
+    if urls.is_empty() {
+        if io::stdin().is_terminal() {
+            eprintln!(
+                "Usage: kurl query [--add KEY=VALUE] [--set KEY=VALUE] [--remove KEY] [<URL>...]"
+            );
+            std::process::exit(1);
+        }
+        buffer = crate::read_stdin();
+        urls.push("-");
+    }
+    let inputs = crate::batch_inputs(&urls, &buffer);
+
+    transform::run(&inputs, &options, &output, |url| Ok(apply(url, &edits)));
+}
+
+/// Applies `edits` to the raw `&`-separated parameters, so that only the
+/// parameters an edit touches are re-encoded.
+pub fn apply(url: &Url, edits: &[Edit]) -> Url {
+    let mut params = raw_params(url);
+
+    for edit in edits {
+        match *edit {
+            Edit::Add(key, value) => params.push(encode(key, value)),
+            Edit::Set(key, value) => {
+                let pair = encode(key, value);
+                match params.iter().position(|param| has_key(param, key)) {
+                    Some(first) => {
+                        params[first] = pair;
+                        params = params
+                            .into_iter()
+                            .enumerate()
+                            .filter(|(index, param)| *index == first || !has_key(param, key))
+                            .map(|(_, param)| param)
+                            .collect();
+                    }
+                    None => params.push(pair),
+                }
+            }
+            Edit::Remove(key) => params.retain(|param| !has_key(param, key)),
+        }
+    }
+
+    let mut url = url.clone();
+    set_raw_params(&mut url, &params);
+    url
+}
+
+/// A change to the query parameters named by key, from `--rename-query` or
+/// `--map-query`.
+#[derive(Debug, Clone)]
+pub enum Rewrite {
+    /// Gives every `from` parameter the key `to`, keeping its value.
+    Rename { from: String, to: String },
+    /// Replaces the matches of `pattern` in every `key` parameter's value.
+    Map {
+        key: String,
+        pattern: Regex,
+        replacement: String,
+    },
+}
+
+impl Rewrite {
+    /// Parses a `--rename-query OLD=NEW` value.
+    pub fn rename(spec: &str) -> Result<Rewrite, String> {
+        match spec.split_once('=') {
+            Some((from, to)) if !from.is_empty() && !to.is_empty() => Ok(Rewrite::Rename {
+                from: from.to_string(),
+                to: to.to_string(),
+            }),
+            _ => Err(format!("expected OLD=NEW, got {}", spec)),
+        }
+    }
+
+    /// Parses a `--map-query KEY:REGEX:REPLACEMENT` value. The regex ends at
+    /// the first `:` not escaped as `\:`, so the replacement may have more.
+    pub fn map(spec: &str) -> Result<Rewrite, String> {
+        let parts = spec.split_once(':').and_then(|(key, rest)| {
+            let mut escaped = false;
+            let end = rest.char_indices().find_map(|(i, c)| match c {
+                ':' if !escaped => Some(i),
+                _ => {
+                    escaped = c == '\\' && !escaped;
+                    None
+                }
+            })?;
+            Some((key, &rest[..end], &rest[end + 1..]))
+        });
+        let Some((key, pattern, replacement)) = parts.filter(|(key, _, _)| !key.is_empty()) else {
+            return Err(format!("expected KEY:REGEX:REPLACEMENT, got {}", spec));
+        };
+        Ok(Rewrite::Map {
+            key: key.to_string(),
+            pattern: Regex::new(pattern)?,
+            replacement: replacement.to_string(),
+        })
+    }
+}
+
+/// Applies `rewrites` in order. Parameters they don't change keep their
+/// original encoding, and changed keys and values are form-encoded.
+pub fn rewrite(url: &mut Url, rewrites: &[Rewrite]) {
+    let mut params = raw_params(url);
+    for rewrite in rewrites {
+        for param in params.iter_mut() {
+            let (raw_key, raw_value) = match param.split_once('=') {
+                Some((key, value)) => (key, Some(value)),
+                None => (param.as_str(), None),
+            };
+            let new = match rewrite {
+                Rewrite::Rename { from, to } if has_key(param, from) => {
+                    let key: String = form_urlencoded::byte_serialize(to.as_bytes()).collect();
+                    match raw_value {
+                        Some(value) => format!("{}={}", key, value),
+                        None => key,
+                    }
+                }
+                Rewrite::Map {
+                    key,
+                    pattern,
+                    replacement,
+                } if has_key(param, key) => {
+                    let value = decoded(raw_value.unwrap_or(""));
+                    let mapped = pattern.replace_all(&value, replacement);
+                    if mapped == value {
+                        continue;
+                    }
+                    let mapped: String =
+                        form_urlencoded::byte_serialize(mapped.as_bytes()).collect();
+                    format!("{}={}", raw_key, mapped)
+                }
+                _ => continue,
+            };
+            *param = new;
+        }
+    }
+    set_raw_params(url, &params);
+}
+
+/// Removes the parameters whose decoded key `unwanted` returns true for,
+/// leaving the rest as they were.
+pub fn remove(url: &mut Url, unwanted: impl Fn(&str) -> bool) {
+    let mut params = raw_params(url);
+    params.retain(|param| !unwanted(&decoded_key(param)));
+    set_raw_params(url, &params);
+}
+
+/// A query key that appears more than once, with its decoded values in order.
+#[derive(Debug, PartialEq)]
+pub struct Duplicate {
+    pub key: String,
+    pub values: Vec<String>,
+}
+
+impl Duplicate {
+    /// Whether the values differ, so servers that keep the first, keep the
+    /// last, or join them all disagree about what the parameter says.
+    pub fn conflict(&self) -> bool {
+        self.values.iter().any(|value| *value != self.values[0])
+    }
+
+    pub fn to_value(&self) -> Value {
+        Value::Object(vec![
+            ("key".to_string(), Value::from(self.key.as_str())),
+            ("count".to_string(), Value::from(self.values.len())),
+            (
+                "values".to_string(),
+                Value::Array(
+                    self.values
+                        .iter()
+                        .map(|v| Value::from(v.as_str()))
+                        .collect(),
+                ),
+            ),
+            ("conflict".to_string(), Value::from(self.conflict())),
+        ])
+    }
+}
+
+/// What separates query parameters, from `--query-separator`.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum Separator {
+    #[default]
+    Ampersand,
+    Semicolon,
+    /// `;` for queries that have one and no `&`, else `&`.
+    Auto,
+    /// Both `&` and `;`.
+    Both,
+}
+
+impl Separator {
+    pub fn parse(spec: &str) -> Option<Separator> {
+        match spec {
+            "&" => Some(Separator::Ampersand),
+            ";" => Some(Separator::Semicolon),
+            "auto" => Some(Separator::Auto),
+            "both" => Some(Separator::Both),
+            _ => None,
+        }
+    }
+
+    /// The characters that separate `url`'s query parameters.
+    pub fn chars(self, url: &Url) -> &'static [char] {
+        match self {
+            Separator::Ampersand => &['&'],
+            Separator::Semicolon => &[';'],
+            Separator::Both => &['&', ';'],
+            Separator::Auto => match url.query() {
+                Some(query) if query.contains(';') && !query.contains('&') => &[';'],
+                _ => &['&'],
+            },
+        }
+    }
+}
+
+/// How queries are read into parameters.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Syntax {
+    /// What separates parameters, from `--query-separator`.
+    pub separator: Separator,
+    /// Whether `+` decodes to a space, as in HTML forms, or stays a `+`, as
+    /// many APIs mean it, from `--no-plus-as-space`.
+    pub plus_as_space: bool,
+    /// Whether `a[b][]=1` style keys nest, from `--nested-query`.
+    pub nested: bool,
+    /// Whether values like `3` and `true` print as JSON numbers and
+    /// booleans, from `--typed`.
+    pub typed: bool,
+}
+
+impl Default for Syntax {
+    fn default() -> Syntax {
+        Syntax {
+            separator: Separator::Ampersand,
+            plus_as_space: true,
+            nested: false,
+            typed: false,
+        }
+    }
+}
+
+impl From<Separator> for Syntax {
+    fn from(separator: Separator) -> Syntax {
+        Syntax {
+            separator,
+            ..Syntax::default()
+        }
+    }
+}
+
+/// The decoded query parameters of `url`, read with `syntax`.
+pub fn pairs(url: &Url, syntax: Syntax) -> Vec<(String, String)> {
+    let separators = syntax.separator.chars(url);
+    if separators == ['&'] && syntax.plus_as_space {
+        return url.query_pairs().into_owned().collect();
+    }
+    parse(
+        url.query().unwrap_or_default(),
+        separators,
+        syntax.plus_as_space,
+    )
+}
+
+/// A fragment that holds parameters, as single-page apps keep their state
+/// (`#/settings?tab=keys`) and OAuth's implicit flow returns tokens
+/// (`#access_token=...&state=...`).
+#[derive(Debug, PartialEq)]
+pub struct FragmentQuery {
+    /// The route before the `?`, if the fragment has one.
+    pub route: Option<String>,
+    pub params: Vec<(String, String)>,
+}
+
+/// Reads `url`'s fragment as a query string, split at `&`, or `None` if it
+/// is a plain anchor with no `?` or `=`.
+pub fn fragment(url: &Url, syntax: Syntax) -> Option<FragmentQuery> {
+    let fragment = url.fragment()?;
+    let (route, params) = match fragment.split_once('?') {
+        Some((route, params)) => (Some(route.to_string()), params),
+        None if fragment.contains('=') => (None, fragment),
+        None => return None,
+    };
+    Some(FragmentQuery {
+        route,
+        params: parse(params, &['&'], syntax.plus_as_space),
+    })
+}
+
+/// Decodes the parameters of `text`, split at `separators`.
+fn parse(text: &str, separators: &[char], plus_as_space: bool) -> Vec<(String, String)> {
+    text.split(separators)
+        .filter(|param| !param.is_empty())
+        .flat_map(|param| {
+            // A `&` left in a parameter is part of it, not a separator, and a
+            // `+` that isn't a space is a literal one.
+            let mut param = param.replace('&', "%26");
+            if !plus_as_space {
+                param = param.replace('+', "%2B");
+            }
+            form_urlencoded::parse(param.as_bytes())
+                .into_owned()
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+/// The query parameters of `url` as JSON values, nested and typed as
+/// `syntax` says.
+pub fn values(url: &Url, syntax: Syntax) -> Vec<(String, Value)> {
+    let pairs = pairs(url, syntax);
+    let params = if syntax.nested {
+        nest(&pairs)
+    } else {
+        pairs
+            .into_iter()
+            .map(|(key, value)| (key, Value::from(value)))
+            .collect()
+    };
+    if !syntax.typed {
+        return params;
+    }
+    params
+        .into_iter()
+        .map(|(key, value)| (key, typed(value)))
+        .collect()
+}
+
+/// Turns strings in `value` that are exactly an integer, a decimal, `true`,
+/// `false`, or `null` into those JSON values. Only text a JSON reader gives
+/// back unchanged converts, so `007`, `1.50`, `1e3`, `+1`, and numbers with
+/// more than 15 digits, which doubles can't all hold, stay strings.
+pub fn typed(value: Value) -> Value {
+    match value {
+        Value::String(text) => match text.as_str() {
+            "true" => Value::Bool(true),
+            "false" => Value::Bool(false),
+            "null" => Value::Null,
+            number if is_exact_number(number) => Value::Number(text),
+            _ => Value::String(text),
+        },
+        Value::Array(items) => Value::Array(items.into_iter().map(typed).collect()),
+        Value::Object(fields) => Value::Object(
+            fields
+                .into_iter()
+                .map(|(key, value)| (key, typed(value)))
+                .collect(),
+        ),
+        other => other,
+    }
+}
+
+fn is_exact_number(text: &str) -> bool {
+    let unsigned = text.strip_prefix('-').unwrap_or(text);
+    let (whole, fraction) = match unsigned.split_once('.') {
+        Some((whole, fraction)) => (whole, Some(fraction)),
+        None => (unsigned, None),
+    };
+    let digits = |part: &str| !part.is_empty() && part.bytes().all(|b| b.is_ascii_digit());
+    let canonical = match fraction {
+        Some(fraction) => digits(fraction) && !fraction.ends_with('0'),
+        // `-0` would read back as 0.
+        None => unsigned != "0" || text == "0",
+    };
+    digits(whole)
+        && (whole == "0" || !whole.starts_with('0'))
+        && canonical
+        && whole.len() + fraction.map_or(0, str::len) <= 15
+}
+
+/// A step into a nested parameter: `[name]` or `[]`.
+#[derive(Clone, Copy)]
+enum Segment<'a> {
+    Key(&'a str),
+    Push,
+}
+
+/// Nests PHP and Rails style parameters, so `a[b][]=1&a[b][]=2&a[c]=x`
+/// becomes `{"a":{"b":["1","2"],"c":"x"}}`. As in Rack, `a[][x]=1&a[][y]=2`
+/// builds one object in the array and a repeated `x` starts the next. Keys
+/// that aren't well-formed, and parameters whose shape conflicts with an
+/// earlier one, as `a[b]=2` does with `a=1`, are kept flat under their
+/// literal keys; repeated plain keys are kept, as in the flat query.
+pub fn nest(pairs: &[(String, String)]) -> Vec<(String, Value)> {
+    let mut root = Vec::new();
+    for (key, value) in pairs {
+        let nested = match key_path(key) {
+            Some((name, path)) => insert(&mut root, name, &path, value),
+            None => false,
+        };
+        if !nested {
+            root.push((key.clone(), Value::from(value.as_str())));
+        }
+    }
+    root
+}
+
+/// Splits `a[b][]` into `a` and its segments, or `None` if it isn't a
+/// name followed by only bracketed segments.
+fn key_path(key: &str) -> Option<(&str, Vec<Segment<'_>>)> {
+    let (name, mut rest) = key.split_at(key.find('[').unwrap_or(key.len()));
+    if name.is_empty() {
+        return None;
+    }
+    let mut path = Vec::new();
+    while !rest.is_empty() {
+        let (inside, after) = rest.strip_prefix('[')?.split_once(']')?;
+        if inside.contains('[') {
+            return None;
+        }
+        path.push(match inside {
+            "" => Segment::Push,
+            key => Segment::Key(key),
+        });
+        rest = after;
+    }
+    Some((name, path))
+}
+
+/// Sets `name`, then `path` under it, to `value` in an object's `fields`.
+fn insert(fields: &mut Vec<(String, Value)>, name: &str, path: &[Segment], value: &str) -> bool {
+    let Some((&first, rest)) = path.split_first() else {
+        fields.push((name.to_string(), Value::from(value)));
+        return true;
+    };
+    let index = match fields.iter().rposition(|(key, _)| key == name) {
+        Some(index) => index,
+        None => {
+            let empty = match first {
+                Segment::Key(_) => Value::Object(Vec::new()),
+                Segment::Push => Value::Array(Vec::new()),
+            };
+            fields.push((name.to_string(), empty));
+            fields.len() - 1
+        }
+    };
+    step(&mut fields[index].1, first, rest, value)
+}
+
+/// Sets `segment`, then `rest` under it, to `value` in `container`, or
+/// returns false if `container` has the wrong shape.
+fn step(container: &mut Value, segment: Segment, rest: &[Segment], value: &str) -> bool {
+    match (segment, container) {
+        (Segment::Key(key), Value::Object(fields)) => insert(fields, key, rest, value),
+        (Segment::Push, Value::Array(items)) => {
+            let Some((&next, after)) = rest.split_first() else {
+                items.push(Value::from(value));
+                return true;
+            };
+            // An object takes the value unless it already has one there.
+            let fresh = match items.last() {
+                Some(Value::Object(fields)) => has_path(fields, rest),
+                _ => true,
+            };
+            if fresh {
+                items.push(match next {
+                    Segment::Key(_) => Value::Object(Vec::new()),
+                    Segment::Push => Value::Array(Vec::new()),
+                });
+            }
+            let last = items.len() - 1;
+            step(&mut items[last], next, after, value)
+        }
+        _ => false,
+    }
+}
+
+/// Whether an object's `fields` already have a value at `path`, which never
+/// holds for paths with `[]`, since those can grow.
+fn has_path(fields: &[(String, Value)], path: &[Segment]) -> bool {
+    let Some((Segment::Key(key), rest)) = path.split_first() else {
+        return false;
+    };
+    match (fields.iter().rfind(|(k, _)| k == key), rest.is_empty()) {
+        (Some(_), true) => true,
+        (Some((_, Value::Object(inner))), false) => has_path(inner, rest),
+        _ => false,
+    }
+}
+
+/// Rewrites a `;`-separated query with `&` between its parameters, so the
+/// other edits can work on it, encoding any `&` inside them as `%26` so it
+/// stays where it is.
+pub fn to_ampersands(url: &mut Url) {
+    if let Some(query) = url.query() {
+        let query = query.replace('&', "%26").replace(';', "&");
+        url.set_query(Some(&query));
+    }
+}
+
+/// Rewrites a query separated by both `;` and `&` with only `&`. Unlike
+/// [`to_ampersands`], it can't be undone, since which separator stood where
+/// is lost.
+pub fn merge_separators(url: &mut Url) {
+    if let Some(query) = url.query().filter(|query| query.contains(';')) {
+        let query = query.replace(';', "&");
+        url.set_query(Some(&query));
+    }
+}
+
+/// Undoes [`to_ampersands`], putting `;` back between the parameters.
+pub fn to_semicolons(url: &mut Url) {
+    if let Some(query) = url.query() {
+        let query = query.replace('&', ";");
+        url.set_query(Some(&query));
+    }
+}
+
+/// Lists the decoded keys that appear more than once in the query, split at
+/// `separator`, in order of first appearance. `%61=1&a=2` repeats `a`, as
+/// servers decode it.
+pub fn duplicates(url: &Url, syntax: Syntax) -> Vec<Duplicate> {
+    let mut keys: Vec<Duplicate> = Vec::new();
+    for (key, value) in pairs(url, syntax) {
+        match keys.iter_mut().find(|seen| seen.key == key) {
+            Some(seen) => seen.values.push(value),
+            None => keys.push(Duplicate {
+                key,
+                values: vec![value],
+            }),
+        }
+    }
+    keys.retain(|key| key.values.len() > 1);
+    keys
+}
+
+/// Orders the query parameters by decoded key, keeping repeated keys in
+/// their original order and every parameter in its original encoding.
+pub fn sort(url: &mut Url) {
+    let mut params = raw_params(url);
+    params.sort_by_cached_key(|param| decoded_key(param));
+    set_raw_params(url, &params);
+}
+
+fn raw_params(url: &Url) -> Vec<String> {
+    url.query()
+        .map(|query| {
+            query
+                .split('&')
+                .filter(|param| !param.is_empty())
+                .map(str::to_string)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn set_raw_params(url: &mut Url, params: &[String]) {
+    if params.is_empty() {
+        url.set_query(None);
+    } else {
+        url.set_query(Some(&params.join("&")));
+    }
+}
+
+fn encode(key: &str, value: &str) -> String {
+    form_urlencoded::Serializer::new(String::new())
+        .append_pair(key, value)
+        .finish()
+}
+
+/// Whether the raw parameter `param` decodes to the key `key`.
+fn has_key(param: &str, key: &str) -> bool {
+    decoded_key(param) == key
+}
+
+fn decoded_key(param: &str) -> String {
+    decoded(param.split('=').next().unwrap_or(param))
+}
+
+/// Decodes one form-encoded key or value.
+fn decoded(raw: &str) -> String {
+    form_urlencoded::parse(raw.as_bytes())
+        .next()
+        .map(|(decoded, _)| decoded.into_owned())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn edited(url: &str, edits: &[Edit]) -> String {
+        apply(&Url::parse(url).unwrap(), edits).to_string()
+    }
+
+    #[test]
+    fn test_edits_preserve_untouched_params() {
+        assert_eq!(
+            edited(
+                "https://example.com/search?q=a%20b&page=1&sort=new#top",
+                &[
+                    Edit::Set("page", "2"),
+                    Edit::Add("tag", "c&d é"),
+                    Edit::Remove("sort"),
+                ]
+            ),
+            "https://example.com/search?q=a%20b&page=2&tag=c%26d+%C3%A9#top"
+        );
+        assert_eq!(
+            edited("https://example.com/?a=1&b=2&a=3", &[Edit::Set("a", "x")]),
+            "https://example.com/?a=x&b=2"
+        );
+        assert_eq!(
+            edited("https://example.com/?a=1", &[Edit::Set("b", "2")]),
+            "https://example.com/?a=1&b=2"
+        );
+    }
+
+    #[test]
+    fn test_remove_matches_decoded_keys() {
+        assert_eq!(
+            edited(
+                "https://example.com/?utm%5Fsource=x&my+key=y&keep",
+                &[Edit::Remove("utm_source"), Edit::Remove("my key")]
+            ),
+            "https://example.com/?keep"
+        );
+        assert_eq!(
+            edited("https://example.com/?a=1", &[Edit::Remove("a")]),
+            "https://example.com/"
+        );
+        assert_eq!(
+            edited("https://example.com/", &[Edit::Add("a", "")]),
+            "https://example.com/?a="
+        );
+    }
+
+    #[test]
+    fn test_rewrites() {
+        let rewrites = [
+            Rewrite::rename("pg=page").unwrap(),
+            Rewrite::map(r"ref:^legacy-(\d+)$:new-$1").unwrap(),
+            Rewrite::map(r"next:^http\://:https://").unwrap(),
+        ];
+        let mut url = Url::parse(
+            "https://example.com/?pg=2&ref=legacy-7&q=a%20b&ref=other&next=http://x/&pg",
+        )
+        .unwrap();
+        rewrite(&mut url, &rewrites);
+        assert_eq!(
+            url.as_str(),
+            "https://example.com/?page=2&ref=new-7&q=a%20b&ref=other&next=https%3A%2F%2Fx%2F&page"
+        );
+
+        assert!(Rewrite::rename("pg").is_err());
+        assert!(Rewrite::map("ref:no-replacement").is_err());
+        assert!(Rewrite::map(":a:b").is_err());
+        assert!(Rewrite::map("ref:(:b").is_err());
+    }
+
+    #[test]
+    fn test_sort_is_stable() {
+        let mut url = Url::parse("https://example.com/?b=2&a=z&%61=y&c&a=x#top").unwrap();
+        sort(&mut url);
+        assert_eq!(url.as_str(), "https://example.com/?a=z&%61=y&a=x&b=2&c#top");
+
+        let mut url = Url::parse("https://example.com/?").unwrap();
+        sort(&mut url);
+        assert_eq!(url.as_str(), "https://example.com/");
+    }
+
+    #[test]
+    fn test_duplicates() {
+        let url = Url::parse("https://example.com/?page=1&q=a&%70age=2&tag=x&tag=x&one=1").unwrap();
+        let found = duplicates(&url, Separator::Ampersand.into());
+        assert_eq!(
+            found,
+            vec![
+                Duplicate {
+                    key: "page".to_string(),
+                    values: vec!["1".to_string(), "2".to_string()]
+                },
+                Duplicate {
+                    key: "tag".to_string(),
+                    values: vec!["x".to_string(), "x".to_string()]
+                },
+            ]
+        );
+        assert_eq!(
+            found[0].to_value().to_json(),
+            r#"{"key":"page","count":2,"values":["1","2"],"conflict":true}"#
+        );
+        assert!(!found[1].conflict());
+        let url = Url::parse("https://example.com/?a=1&b=1").unwrap();
+        assert!(duplicates(&url, Separator::Ampersand.into()).is_empty());
+        let legacy = Url::parse("https://example.com/?a=1;a=2").unwrap();
+        assert_eq!(duplicates(&legacy, Separator::Semicolon.into()).len(), 1);
+        assert!(duplicates(&legacy, Separator::Ampersand.into()).is_empty());
+    }
+
+    #[test]
+    fn test_separator() {
+        let url = Url::parse("https://example.com/p?a=1;b=x%3By;c").unwrap();
+        assert_eq!(Separator::Auto.chars(&url), [';']);
+        assert_eq!(Separator::Ampersand.chars(&url), ['&']);
+        assert_eq!(
+            pairs(&url, Separator::Semicolon.into()),
+            vec![
+                ("a".to_string(), "1".to_string()),
+                ("b".to_string(), "x;y".to_string()),
+                ("c".to_string(), String::new()),
+            ]
+        );
+        assert_eq!(pairs(&url, Separator::Ampersand.into()).len(), 1);
+        let mixed = Url::parse("https://example.com/?a=1;b=2&c=R%26D").unwrap();
+        assert_eq!(Separator::Auto.chars(&mixed), ['&']);
+        assert_eq!(pairs(&mixed, Separator::Semicolon.into())[1].1, "2&c=R&D");
+        assert_eq!(
+            pairs(&mixed, Separator::Both.into()),
+            vec![
+                ("a".to_string(), "1".to_string()),
+                ("b".to_string(), "2".to_string()),
+                ("c".to_string(), "R&D".to_string()),
+            ]
+        );
+        let mut merged = mixed.clone();
+        merge_separators(&mut merged);
+        assert_eq!(merged.query(), Some("a=1&b=2&c=R%26D"));
+
+        let mut url = Url::parse("https://example.com/?q=R%26D;b&w=2;z=1").unwrap();
+        to_ampersands(&mut url);
+        assert_eq!(url.query(), Some("q=R%26D&b%26w=2&z=1"));
+        sort(&mut url);
+        to_semicolons(&mut url);
+        assert_eq!(url.query(), Some("b%26w=2;q=R%26D;z=1"));
+    }
+
+    #[test]
+    fn test_fragment() {
+        let read = |url: &str| fragment(&Url::parse(url).unwrap(), Syntax::default());
+        assert_eq!(
+            read("https://app.example.com/#/settings?tab=keys&id=3"),
+            Some(FragmentQuery {
+                route: Some("/settings".to_string()),
+                params: vec![
+                    ("tab".to_string(), "keys".to_string()),
+                    ("id".to_string(), "3".to_string()),
+                ],
+            })
+        );
+        let implicit =
+            read("https://app.example.com/cb#access_token=abc%2F1&token_type=Bearer").unwrap();
+        assert_eq!(implicit.route, None);
+        assert_eq!(
+            implicit.params[0],
+            ("access_token".to_string(), "abc/1".to_string())
+        );
+        assert_eq!(read("https://example.com/docs#install"), None);
+        assert_eq!(read("https://example.com/docs"), None);
+        assert_eq!(
+            read("https://example.com/#!/inbox?").unwrap().params,
+            Vec::new()
+        );
+    }
+
+    #[test]
+    fn test_nest() {
+        let nested = |query: &str| {
+            let url = Url::parse(&format!("https://example.com/?{}", query)).unwrap();
+            Value::Object(nest(&pairs(&url, Syntax::default()))).to_json()
+        };
+        assert_eq!(
+            nested("a[b][]=1&a[b][]=2&a[c]=x&q=rust"),
+            r#"{"a":{"b":["1","2"],"c":"x"},"q":"rust"}"#
+        );
+        assert_eq!(
+            nested("user[][name]=jo&user[][role]=admin&user[][name]=sam"),
+            r#"{"user":[{"name":"jo","role":"admin"},{"name":"sam"}]}"#
+        );
+        assert_eq!(
+            nested("f%5Ba%5D%5Bb%5D=1&f[a][c][]=2"),
+            r#"{"f":{"a":{"b":"1","c":["2"]}}}"#
+        );
+        assert_eq!(nested("m[][]=1&m[][]=2"), r#"{"m":[["1"],["2"]]}"#);
+        // Conflicting shapes and malformed keys stay flat.
+        assert_eq!(
+            nested("a=1&a[b]=2&c[]=3&c[d]=4&x[=5&[y]=6&t[a]b=7&k=1&k=2"),
+            r#"{"a":"1","a[b]":"2","c":["3"],"c[d]":"4","x[":"5","[y]":"6","t[a]b":"7","k":"1","k":"2"}"#
+        );
+    }
+
+    #[test]
+    fn test_typed() {
+        let url = Url::parse(
+            "https://example.com/?id=42&n=-7&f=0.25&on=true&x=null&zip=007&p=1.50&e=1e3&big=12345678901234567&z=-0&s=3a&empty=",
+        )
+        .unwrap();
+        let syntax = Syntax {
+            typed: true,
+            ..Syntax::default()
+        };
+        assert_eq!(
+            Value::Object(values(&url, syntax)).to_json(),
+            r#"{"id":42,"n":-7,"f":0.25,"on":true,"x":null,"zip":"007","p":"1.50","e":"1e3","big":"12345678901234567","z":"-0","s":"3a","empty":""}"#
+        );
+        let url = Url::parse("https://example.com/?a[]=1&a[]=x&b[c]=false").unwrap();
+        let syntax = Syntax {
+            nested: true,
+            ..syntax
+        };
+        assert_eq!(
+            Value::Object(values(&url, syntax)).to_json(),
+            r#"{"a":[1,"x"],"b":{"c":false}}"#
+        );
+    }
+
+    #[test]
+    fn test_plus_as_space() {
+        let url = Url::parse("https://api.example.com/?email=a+b@example.com&q=x+y%2Bz").unwrap();
+        assert_eq!(
+            pairs(&url, Syntax::default()),
+            vec![
+                ("email".to_string(), "a b@example.com".to_string()),
+                ("q".to_string(), "x y+z".to_string()),
+            ]
+        );
+        let literal = Syntax {
+            plus_as_space: false,
+            ..Syntax::default()
+        };
+        assert_eq!(
+            pairs(&url, literal),
+            vec![
+                ("email".to_string(), "a+b@example.com".to_string()),
+                ("q".to_string(), "x+y+z".to_string()),
+            ]
+        );
+        let legacy = Url::parse("https://example.com/?t=12:00+01:00;n=a%20b").unwrap();
+        let literal = Syntax {
+            separator: Separator::Semicolon,
+            plus_as_space: false,
+            ..Syntax::default()
+        };
+        assert_eq!(pairs(&legacy, literal)[0].1, "12:00+01:00");
+        assert_eq!(pairs(&legacy, literal)[1].1, "a b");
+    }
+}
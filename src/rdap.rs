@@ -0,0 +1,268 @@
+use std::io::{self, Write};
+use std::net::IpAddr;
+use url::{Host, Url};
+
+use crate::json::{self, Value};
+use crate::psl;
+
+// Subsets of IANA's RDAP bootstrap registries, in IANA's own format so that
+// the full files from https://data.iana.org/rdap/ can be dropped in.
+const DNS_BOOTSTRAP: &str = include_str!("../data/rdap/dns.json");
+const IPV4_BOOTSTRAP: &str = include_str!("../data/rdap/ipv4.json");
+const IPV6_BOOTSTRAP: &str = include_str!("../data/rdap/ipv6.json");
+
+const RDAP_HELP: &str = r#"kurl rdap - build the RDAP query URL for a URL's domain or address
+
+USAGE:
+    kurl rdap [OPTIONS] <URL|HOST>
+
+OPTIONS:
+    -j, --json          Output as JSON instead of the bare query URL
+    -h, --help          Show this help message
+
+Domains are looked up by their registrable domain, so
+`https://www.example.co.uk/x` queries `example.co.uk`. The RDAP server comes
+from the bundled IANA bootstrap data; TLDs and address blocks it doesn't
+list are reported as having no RDAP service, with exit code 1.
+
+EXAMPLES:
+    kurl rdap https://www.example.com/login
+    curl -sL "$(kurl rdap 192.0.2.1)"
+"#;
+
+pub fn run(args: &[String]) {
+    let mut json_output = false;
+    let mut input = None;
+    for arg in args {
+        match arg.as_str() {
+            "-h" | "--help" => {
+                println!("{}", RDAP_HELP);
+                return;
+            }
+            "-j" | "--json" => json_output = true,
+            other if input.is_none() && !other.starts_with('-') => input = Some(other),
+            other => {
+                eprintln!("Unknown option for rdap: {}", other);
+                std::process::exit(1);
+            }
+        }
+    }
+
+    let Some(input) = input else {
+        eprintln!("Usage: kurl rdap [--json] <URL|HOST>");
+        std::process::exit(1);
+    };
+    let query = Query::new(input).unwrap_or_else(|e| {
+        eprintln!("Error: {}", e);
+        std::process::exit(1);
+    });
+
+    let mut out = io::stdout().lock();
+    let _ = if json_output {
+        query.to_value().write(&mut out).and_then(|_| writeln!(out))
+    } else {
+        writeln!(out, "{}", query.url)
+    };
+}
+
+/// An RDAP lookup for one domain or IP address.
+#[derive(Debug, PartialEq)]
+struct Query {
+    /// `domain` or `ip`, as in the query path.
+    kind: &'static str,
+    object: String,
+    url: String,
+}
+
+impl Query {
+    fn new(input: &str) -> Result<Query, String> {
+        let host = if input.contains("://") {
+            let url = Url::parse(input).map_err(|e| format!("{}: {}", input, e))?;
+            url.host()
+                .map(|host| host.to_owned())
+                .ok_or_else(|| format!("{} has no host", input))?
+        } else {
+            let bare = input.trim_start_matches('[').trim_end_matches(']');
+            match bare.parse::<IpAddr>() {
+                Ok(IpAddr::V4(ip)) => Host::Ipv4(ip),
+                Ok(IpAddr::V6(ip)) => Host::Ipv6(ip),
+                Err(_) => Host::parse(input).map_err(|e| format!("{}: {}", input, e))?,
+            }
+        };
+
+        let (kind, object, server) = match host {
+            Host::Domain(domain) => {
+                let domain = domain.trim_end_matches('.');
+                let registrable = psl::list().registrable_domain(domain).ok_or_else(|| {
+                    format!("{} is a public suffix, not a registered domain", domain)
+                })?;
+                let tld = registrable.rsplit('.').next().unwrap_or(registrable);
+                let server = lookup(DNS_BOOTSTRAP, |entry| {
+                    entry.eq_ignore_ascii_case(tld).then_some(0)
+                })
+                .ok_or_else(|| format!("no RDAP service for .{} in the bootstrap data", tld))?;
+                ("domain", registrable.to_string(), server)
+            }
+            Host::Ipv4(ip) => {
+                let server = lookup(IPV4_BOOTSTRAP, |block| block_rank(block, ip.into()))
+                    .ok_or_else(|| no_address_service(ip.into()))?;
+                ("ip", ip.to_string(), server)
+            }
+            Host::Ipv6(ip) => {
+                let server = lookup(IPV6_BOOTSTRAP, |block| block_rank(block, ip.into()))
+                    .ok_or_else(|| no_address_service(ip.into()))?;
+                ("ip", ip.to_string(), server)
+            }
+        };
+
+        let mut base = server;
+        if !base.ends_with('/') {
+            base.push('/');
+        }
+        Ok(Query {
+            kind,
+            url: format!("{}{}/{}", base, kind, object),
+            object,
+        })
+    }
+
+    fn to_value(&self) -> Value {
+        Value::Object(vec![
+            ("type".to_string(), Value::from(self.kind)),
+            ("object".to_string(), Value::from(self.object.as_str())),
+            ("url".to_string(), Value::from(self.url.as_str())),
+        ])
+    }
+}
+
+fn no_address_service(ip: IpAddr) -> String {
+    format!("no RDAP service for {} in the bootstrap data", ip)
+}
+
+/// Finds the first HTTPS server of the service in a bootstrap file with the
+/// best-ranked entry, where `rank` ranks the entries that match at all. The
+/// address files rank by prefix length, so the most specific block wins.
+fn lookup(bootstrap: &str, rank: impl Fn(&str) -> Option<u32>) -> Option<String> {
+    let registry = json::parse(bootstrap).ok()?;
+    let Some(Value::Array(services)) = registry.get("services") else {
+        return None;
+    };
+
+    let mut best: Option<(u32, &Vec<Value>)> = None;
+    for service in services {
+        let Value::Array(parts) = service else {
+            continue;
+        };
+        let [Value::Array(entries), Value::Array(servers)] = parts.as_slice() else {
+            continue;
+        };
+        let service_rank = entries
+            .iter()
+            .filter_map(|entry| match entry {
+                Value::String(entry) => rank(entry),
+                _ => None,
+            })
+            .max();
+        match (service_rank, best) {
+            (Some(new), Some((old, _))) if new <= old => {}
+            (Some(new), _) => best = Some((new, servers)),
+            (None, _) => {}
+        }
+    }
+
+    let urls: Vec<&str> = best?
+        .1
+        .iter()
+        .filter_map(|server| match server {
+            Value::String(url) => Some(url.as_str()),
+            _ => None,
+        })
+        .collect();
+    urls.iter()
+        .find(|url| url.starts_with("https:"))
+        .or(urls.first())
+        .map(|url| url.to_string())
+}
+
+/// Returns the prefix length of a `network/length` block if it contains
+/// `address`.
+fn block_rank(block: &str, address: IpAddr) -> Option<u32> {
+    let (network, len) = block.split_once('/')?;
+    let len: u32 = len.parse().ok()?;
+    let (network, address, width): (u128, u128, u32) = match (network.parse().ok()?, address) {
+        (IpAddr::V4(network), IpAddr::V4(address)) => (
+            u128::from(u32::from(network)),
+            u128::from(u32::from(address)),
+            32,
+        ),
+        (IpAddr::V6(network), IpAddr::V6(address)) => {
+            (u128::from(network), u128::from(address), 128)
+        }
+        _ => return None,
+    };
+    let host_bits = width.checked_sub(len)?;
+    let net = |bits: u128| bits.checked_shr(host_bits).unwrap_or(0);
+    (net(network) == net(address)).then_some(len)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn url(input: &str) -> String {
+        Query::new(input).unwrap().url
+    }
+
+    #[test]
+    fn test_domain_queries() {
+        assert_eq!(
+            url("https://www.example.com/login"),
+            "https://rdap.verisign.com/com/v1/domain/example.com"
+        );
+        assert_eq!(
+            url("docs.Example.DEV"),
+            "https://pubapi.registry.google/rdap/domain/example.dev"
+        );
+        assert_eq!(
+            Query::new("https://www.example.invalid-tld/"),
+            Err("no RDAP service for .invalid-tld in the bootstrap data".to_string())
+        );
+        assert!(Query::new("co.uk").is_err());
+        assert!(Query::new("mailto:a@example.com").is_err());
+    }
+
+    #[test]
+    fn test_ip_queries() {
+        assert_eq!(url("http://1.1.1.1/"), "https://rdap.apnic.net/ip/1.1.1.1");
+        assert_eq!(url("8.8.8.8"), "https://rdap.arin.net/registry/ip/8.8.8.8");
+        assert_eq!(url("2001:db8::1"), "https://rdap.apnic.net/ip/2001:db8::1");
+        assert_eq!(
+            Query::new("3fff::1"),
+            Err("no RDAP service for 3fff::1 in the bootstrap data".to_string())
+        );
+        assert_eq!(
+            url("[2a00:1450::1]"),
+            "https://rdap.db.ripe.net/ip/2a00:1450::1"
+        );
+        assert_eq!(
+            Query::new("2600::1").unwrap().to_value().to_json(),
+            r#"{"type":"ip","object":"2600::1","url":"https://rdap.arin.net/registry/ip/2600::1"}"#
+        );
+    }
+
+    #[test]
+    fn test_longest_prefix_wins() {
+        let bootstrap = r#"{"services": [
+            [["10.0.0.0/8", "::/0"], ["http://wide.example/", "https://wide.example/"]],
+            [["10.1.0.0/16"], ["https://narrow.example"]]
+        ]}"#;
+        let find = |ip: &str| lookup(bootstrap, |block| block_rank(block, ip.parse().unwrap()));
+        assert_eq!(find("10.1.2.3").as_deref(), Some("https://narrow.example"));
+        assert_eq!(find("10.2.0.0").as_deref(), Some("https://wide.example/"));
+        assert_eq!(find("11.0.0.0"), None);
+        assert_eq!(
+            find("2001:db8::1").as_deref(),
+            Some("https://wide.example/")
+        );
+    }
+}
@@ -0,0 +1,586 @@
+/// A regular expression in the common subset of the usual syntaxes: literals,
+/// `.`, classes like `[a-z_]` and `[^/]`, the escapes `\d`, `\w`, and `\s`
+/// (and their negations), `^` and `$`, groups, `|`, and the quantifiers `*`,
+/// `+`, `?`, and `{n,m}`, which are greedy unless followed by `?`. Matching
+/// follows every way through the pattern at once, so it takes time linear in
+/// the text even for patterns like `(a*)*b`, which would make a backtracking
+/// matcher take exponential time on crafted URLs.
+#[derive(Debug, Clone)]
+pub struct Regex {
+    program: Vec<Inst>,
+    groups: usize,
+}
+
+#[derive(Debug, Clone)]
+enum Node {
+    Char(char),
+    Any,
+    Class {
+        ranges: Vec<(char, char)>,
+        negated: bool,
+    },
+    Start,
+    End,
+    Concat(Vec<Node>),
+    Alternate(Vec<Node>),
+    /// A group, with its capture index unless it is `(?:...)`.
+    Group(Box<Node>, Option<usize>),
+    Repeat {
+        node: Box<Node>,
+        min: usize,
+        max: Option<usize>,
+        greedy: bool,
+    },
+}
+
+/// A step of the compiled pattern.
+#[derive(Debug, Clone)]
+enum Inst {
+    Char(char),
+    Any,
+    Class {
+        ranges: Vec<(char, char)>,
+        negated: bool,
+    },
+    Start,
+    End,
+    /// Goes on at both targets, preferring the first.
+    Split(usize, usize),
+    Jump(usize),
+    /// Records the position in a capture slot: `2 * N` where group `N`
+    /// starts and `2 * N + 1` where it ends.
+    Save(usize),
+    Match,
+}
+
+/// The spans of the whole match and of each group, in chars.
+type Captures = Vec<Option<(usize, usize)>>;
+
+/// A way through the pattern: the step it waits at and its capture slots.
+struct Thread {
+    pc: usize,
+    slots: Vec<Option<usize>>,
+}
+
+impl Regex {
+    pub fn new(pattern: &str) -> Result<Regex, String> {
+        let mut parser = Parser {
+            chars: pattern.chars().collect(),
+            pos: 0,
+            groups: 0,
+        };
+        let node = parser.alternation()?;
+        if parser.pos < parser.chars.len() {
+            return Err(parser.error("unmatched ')'"));
+        }
+        let mut program = Vec::new();
+        compile(&node, &mut program);
+        program.push(Inst::Match);
+        Ok(Regex {
+            program,
+            groups: parser.groups,
+        })
+    }
+
+    /// Whether the pattern matches anywhere in `text`; anchor it with `^` and
+    /// `$` to match the whole text.
+    pub fn is_match(&self, text: &str) -> bool {
+        let chars: Vec<char> = text.chars().collect();
+        self.find_at(&chars, 0).is_some()
+    }
+
+    /// Replaces every non-overlapping match in `text` with `replacement`, in
+    /// which `$0` to `$9` (or `${N}`) stand for the match and its groups and
+    /// `$$` for a literal `$`.
+    pub fn replace_all(&self, text: &str, replacement: &str) -> String {
+        let chars: Vec<char> = text.chars().collect();
+        let mut result = String::new();
+        let mut pos = 0;
+        let mut copied = 0;
+        while pos <= chars.len() {
+            let Some(captures) = self.find_at(&chars, pos) else {
+                break;
+            };
+            let (start, end) = captures[0].unwrap_or((pos, pos));
+            result.extend(&chars[copied..start]);
+            expand(&mut result, replacement, &chars, &captures);
+            copied = end;
+            // Step past empty matches so that each position matches once.
+            pos = if end == start { end + 1 } else { end };
+            if end == start && end < chars.len() {
+                result.push(chars[end]);
+                copied = end + 1;
+            }
+        }
+        if copied < chars.len() {
+            result.extend(&chars[copied..]);
+        }
+        result
+    }
+
+    /// Finds the leftmost match starting at or after `from`, preferring
+    /// among those that start there the one a backtracking matcher would
+    /// find first.
+    fn find_at(&self, chars: &[char], from: usize) -> Option<Captures> {
+        let mut current = Vec::new();
+        let mut next = Vec::new();
+        // The position each step was last queued for, so each is queued once.
+        let mut seen = vec![usize::MAX; self.program.len()];
+        let mut matched = None;
+        for pos in from..=chars.len() {
+            if matched.is_none() {
+                let mut slots = vec![None; 2 * (self.groups + 1)];
+                slots[0] = Some(pos);
+                self.queue(&mut current, &mut seen, chars, pos, 0, slots);
+            }
+            if current.is_empty() && matched.is_some() {
+                break;
+            }
+            for thread in current.drain(..) {
+                match &self.program[thread.pc] {
+                    Inst::Match => {
+                        let mut slots = thread.slots;
+                        slots[1] = Some(pos);
+                        matched = Some(slots);
+                        // The threads after it would find worse matches.
+                        break;
+                    }
+                    inst => {
+                        if chars.get(pos).is_some_and(|&c| accepts(inst, c)) {
+                            self.queue(
+                                &mut next,
+                                &mut seen,
+                                chars,
+                                pos + 1,
+                                thread.pc + 1,
+                                thread.slots,
+                            );
+                        }
+                    }
+                }
+            }
+            std::mem::swap(&mut current, &mut next);
+        }
+        let slots = matched?;
+        Some(slots.chunks(2).map(|span| span[0].zip(span[1])).collect())
+    }
+
+    /// Follows the jumps, splits, saves, and anchors from `pc` at `pos`, and
+    /// adds the threads that reach a step reading a char, in order of
+    /// preference, to `list`.
+    fn queue(
+        &self,
+        list: &mut Vec<Thread>,
+        seen: &mut [usize],
+        chars: &[char],
+        pos: usize,
+        pc: usize,
+        slots: Vec<Option<usize>>,
+    ) {
+        let mut stack = vec![(pc, slots)];
+        while let Some((pc, mut slots)) = stack.pop() {
+            if seen[pc] == pos {
+                continue;
+            }
+            seen[pc] = pos;
+            match &self.program[pc] {
+                Inst::Jump(to) => stack.push((*to, slots)),
+                Inst::Split(first, second) => {
+                    stack.push((*second, slots.clone()));
+                    stack.push((*first, slots));
+                }
+                Inst::Save(slot) => {
+                    slots[*slot] = Some(pos);
+                    stack.push((pc + 1, slots));
+                }
+                Inst::Start => {
+                    if pos == 0 {
+                        stack.push((pc + 1, slots));
+                    }
+                }
+                Inst::End => {
+                    if pos == chars.len() {
+                        stack.push((pc + 1, slots));
+                    }
+                }
+                _ => list.push(Thread { pc, slots }),
+            }
+        }
+    }
+}
+
+/// Whether `inst`, a step that reads a char, accepts `c`.
+fn accepts(inst: &Inst, c: char) -> bool {
+    match inst {
+        Inst::Char(expected) => *expected == c,
+        Inst::Any => true,
+        Inst::Class { ranges, negated } => {
+            ranges.iter().any(|&(lo, hi)| lo <= c && c <= hi) != *negated
+        }
+        _ => false,
+    }
+}
+
+/// Appends `replacement` to `result`, expanding its group references.
+fn expand(result: &mut String, replacement: &str, chars: &[char], captures: &Captures) {
+    let mut rest = replacement.chars().peekable();
+    while let Some(c) = rest.next() {
+        if c != '$' {
+            result.push(c);
+            continue;
+        }
+        let group = match rest.peek() {
+            Some('$') => {
+                rest.next();
+                result.push('$');
+                continue;
+            }
+            Some(&digit) if digit.is_ascii_digit() => {
+                rest.next();
+                digit.to_digit(10).map(|d| d as usize)
+            }
+            Some('{') => {
+                rest.next();
+                let digits: String = rest.by_ref().take_while(|&c| c != '}').collect();
+                digits.parse().ok()
+            }
+            _ => {
+                result.push('$');
+                continue;
+            }
+        };
+        if let Some(Some((start, end))) = group.and_then(|group| captures.get(group)) {
+            result.extend(&chars[*start..*end]);
+        }
+    }
+}
+
+/// Appends the steps that match `node` to `program`.
+fn compile(node: &Node, program: &mut Vec<Inst>) {
+    match node {
+        Node::Char(c) => program.push(Inst::Char(*c)),
+        Node::Any => program.push(Inst::Any),
+        Node::Class { ranges, negated } => program.push(Inst::Class {
+            ranges: ranges.clone(),
+            negated: *negated,
+        }),
+        Node::Start => program.push(Inst::Start),
+        Node::End => program.push(Inst::End),
+        Node::Concat(nodes) => nodes.iter().for_each(|node| compile(node, program)),
+        Node::Alternate(choices) => {
+            let mut jumps = Vec::new();
+            for (i, choice) in choices.iter().enumerate() {
+                if i + 1 == choices.len() {
+                    compile(choice, program);
+                    break;
+                }
+                let split = program.len();
+                program.push(Inst::Split(split + 1, 0));
+                compile(choice, program);
+                jumps.push(program.len());
+                program.push(Inst::Jump(0));
+                program[split] = Inst::Split(split + 1, program.len());
+            }
+            for jump in jumps {
+                program[jump] = Inst::Jump(program.len());
+            }
+        }
+        Node::Group(inner, None) => compile(inner, program),
+        Node::Group(inner, Some(index)) => {
+            program.push(Inst::Save(2 * index));
+            compile(inner, program);
+            program.push(Inst::Save(2 * index + 1));
+        }
+        Node::Repeat {
+            node,
+            min,
+            max,
+            greedy,
+        } => {
+            // Greedy repetition prefers another iteration to stopping, and
+            // lazy repetition the other way around.
+            let choose = |more: usize, done: usize| {
+                if *greedy {
+                    Inst::Split(more, done)
+                } else {
+                    Inst::Split(done, more)
+                }
+            };
+            for _ in 0..*min {
+                compile(node, program);
+            }
+            match max {
+                None => {
+                    let split = program.len();
+                    program.push(Inst::Jump(0));
+                    compile(node, program);
+                    program.push(Inst::Jump(split));
+                    program[split] = choose(split + 1, program.len());
+                }
+                Some(max) => {
+                    let mut splits = Vec::new();
+                    for _ in *min..*max {
+                        splits.push(program.len());
+                        program.push(Inst::Jump(0));
+                        compile(node, program);
+                    }
+                    for split in splits {
+                        program[split] = choose(split + 1, program.len());
+                    }
+                }
+            }
+        }
+    }
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+    groups: usize,
+}
+
+impl Parser {
+    fn error(&self, message: &str) -> String {
+        format!("{} at position {} of the regex", message, self.pos)
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(c) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn alternation(&mut self) -> Result<Node, String> {
+        let mut choices = vec![self.sequence()?];
+        while self.eat('|') {
+            choices.push(self.sequence()?);
+        }
+        Ok(if choices.len() == 1 {
+            choices.remove(0)
+        } else {
+            Node::Alternate(choices)
+        })
+    }
+
+    fn sequence(&mut self) -> Result<Node, String> {
+        let mut nodes = Vec::new();
+        while let Some(c) = self.peek() {
+            if c == '|' || c == ')' {
+                break;
+            }
+            let atom = self.atom()?;
+            nodes.push(self.quantified(atom)?);
+        }
+        Ok(Node::Concat(nodes))
+    }
+
+    fn atom(&mut self) -> Result<Node, String> {
+        let c = self.peek().ok_or_else(|| self.error("unexpected end"))?;
+        self.pos += 1;
+        Ok(match c {
+            '.' => Node::Any,
+            '^' => Node::Start,
+            '$' => Node::End,
+            '[' => self.class()?,
+            '\\' => self.escape()?,
+            '(' => {
+                let index = if self.eat('?') {
+                    if !self.eat(':') {
+                        return Err(self.error("unsupported group syntax"));
+                    }
+                    None
+                } else {
+                    self.groups += 1;
+                    Some(self.groups)
+                };
+                let inner = self.alternation()?;
+                if !self.eat(')') {
+                    return Err(self.error("missing ')'"));
+                }
+                Node::Group(Box::new(inner), index)
+            }
+            '*' | '+' | '?' | '{' => {
+                self.pos -= 1;
+                return Err(self.error("nothing to repeat"));
+            }
+            c => Node::Char(c),
+        })
+    }
+
+    /// Parses the escape after a `\`.
+    fn escape(&mut self) -> Result<Node, String> {
+        let c = self.peek().ok_or_else(|| self.error("trailing '\\'"))?;
+        self.pos += 1;
+        if let Some(ranges) = shorthand(c.to_ascii_lowercase()) {
+            return Ok(Node::Class {
+                ranges,
+                negated: c.is_ascii_uppercase(),
+            });
+        }
+        match c {
+            'n' => Ok(Node::Char('\n')),
+            't' => Ok(Node::Char('\t')),
+            'r' => Ok(Node::Char('\r')),
+            c if c.is_ascii_alphanumeric() => {
+                Err(self.error(&format!("unsupported escape \\{}", c)))
+            }
+            c => Ok(Node::Char(c)),
+        }
+    }
+
+    /// Parses a class after its `[`.
+    fn class(&mut self) -> Result<Node, String> {
+        let negated = self.eat('^');
+        let mut ranges = Vec::new();
+        let mut first = true;
+        loop {
+            let c = self.peek().ok_or_else(|| self.error("missing ']'"))?;
+            self.pos += 1;
+            if c == ']' && !first {
+                break;
+            }
+            first = false;
+            let lo = if c == '\\' {
+                let escaped = self.peek().ok_or_else(|| self.error("missing ']'"))?;
+                self.pos += 1;
+                match shorthand(escaped) {
+                    Some(shorthand) => {
+                        ranges.extend(shorthand);
+                        continue;
+                    }
+                    None if escaped == 'n' => '\n',
+                    None if escaped == 't' => '\t',
+                    None => escaped,
+                }
+            } else {
+                c
+            };
+            let hi = if self.peek() == Some('-') && self.chars.get(self.pos + 1) != Some(&']') {
+                self.pos += 1;
+                let hi = self.peek().ok_or_else(|| self.error("missing ']'"))?;
+                self.pos += 1;
+                if hi < lo {
+                    return Err(self.error("invalid class range"));
+                }
+                hi
+            } else {
+                lo
+            };
+            ranges.push((lo, hi));
+        }
+        Ok(Node::Class { ranges, negated })
+    }
+
+    fn quantified(&mut self, atom: Node) -> Result<Node, String> {
+        let (min, max) = match self.peek() {
+            Some('*') => (0, None),
+            Some('+') => (1, None),
+            Some('?') => (0, Some(1)),
+            Some('{') => {
+                self.pos += 1;
+                let min = self
+                    .number()
+                    .ok_or_else(|| self.error("expected a count"))?;
+                let max = if self.eat(',') {
+                    self.number()
+                } else {
+                    Some(min)
+                };
+                if self.peek() != Some('}') || max.is_some_and(|max| max < min) {
+                    return Err(self.error("invalid repetition"));
+                }
+                (min, max)
+            }
+            _ => return Ok(atom),
+        };
+        self.pos += 1;
+        let greedy = !self.eat('?');
+        if matches!(atom, Node::Start | Node::End) {
+            return Err(self.error("nothing to repeat"));
+        }
+        Ok(Node::Repeat {
+            node: Box::new(atom),
+            min,
+            max,
+            greedy,
+        })
+    }
+
+    fn number(&mut self) -> Option<usize> {
+        let start = self.pos;
+        while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+            self.pos += 1;
+        }
+        self.chars[start..self.pos]
+            .iter()
+            .collect::<String>()
+            .parse()
+            .ok()
+    }
+}
+
+/// The ranges of `\d`, `\w`, or `\s`.
+fn shorthand(c: char) -> Option<Vec<(char, char)>> {
+    match c {
+        'd' => Some(vec![('0', '9')]),
+        'w' => Some(vec![('a', 'z'), ('A', 'Z'), ('0', '9'), ('_', '_')]),
+        's' => Some(vec![(' ', ' '), ('\t', '\r')]),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn replace(pattern: &str, text: &str, replacement: &str) -> String {
+        Regex::new(pattern).unwrap().replace_all(text, replacement)
+    }
+
+    #[test]
+    fn test_replace_all() {
+        assert_eq!(replace("a+", "caaat bat", "o"), "cot bot");
+        assert_eq!(replace(r"^v(\d+)$", "v12", "version-$1"), "version-12");
+        assert_eq!(replace(r"^v(\d+)$", "v12x", "version-$1"), "v12x");
+        assert_eq!(replace("(?:ab|c)+", "abcab-c", "_"), "_-_");
+        assert_eq!(replace("[^a-z]", "a1b-C", ""), "ab");
+        assert_eq!(
+            replace(r"(\w+)@(\w+)", "me@host", "${2}:$1 $$"),
+            "host:me $"
+        );
+        assert_eq!(replace("x*", "ab", "-"), "-a-b-");
+        assert_eq!(replace("a{2,3}", "aaaaaaa", "X"), "XXa");
+        assert_eq!(replace("<.+?>", "<a><b>", "[]"), "[][]");
+        assert_eq!(replace(r"é\.", "café.", "e"), "cafe");
+        assert_eq!(replace("(a|ab)(c|bcd)", "abcd", "$1/$2"), "a/bcd");
+    }
+
+    #[test]
+    fn test_nested_repetition_is_linear() {
+        let started = std::time::Instant::now();
+        let text = "a".repeat(5000);
+        assert!(!Regex::new("(a*)*b").unwrap().is_match(&text));
+        assert!(
+            !Regex::new("^(a|aa)+$")
+                .unwrap()
+                .is_match(&format!("{}!", text))
+        );
+        assert!(started.elapsed() < std::time::Duration::from_secs(2));
+    }
+
+    #[test]
+    fn test_invalid_patterns() {
+        for pattern in [
+            "(a", "a)", "[a", "*a", "a{3,1}", r"\q", "(?=a)", "[z-a]", "^*",
+        ] {
+            assert!(Regex::new(pattern).is_err(), "{}", pattern);
+        }
+    }
+}
@@ -0,0 +1,174 @@
+use std::io::{self, IsTerminal, Write};
+use url::Url;
+
+const RELATIVIZE_HELP: &str = r#"kurl relativize - make URLs relative to a base URL
+
+USAGE:
+    kurl relativize [OPTIONS] <BASE> <URL>...
+    <URLs> | kurl relativize [OPTIONS] --base <BASE>
+
+OPTIONS:
+        --base <URL>    Base URL, so that every argument (or, with none, every
+                        line of stdin) is a URL to relativize
+    -h, --help          Show this help message
+
+Prints the shortest reference that `kurl join BASE` resolves back to each URL:
+a relative path like `../img/a.png` where possible, otherwise one that starts
+at the path (`/docs`), the host (`//cdn.example.com/x`), or, for another
+scheme, the whole URL.
+
+EXAMPLES:
+    kurl relativize https://example.com/docs/guide/ https://example.com/docs/api/
+    cat urls.txt | kurl relativize --base https://example.com/blog/post
+"#;
+
+pub fn run(args: &[String]) {
+    let mut base = None;
+    let mut urls = Vec::new();
+
+    let mut rest = args.iter();
+    while let Some(arg) = rest.next() {
+        match arg.as_str() {
+            "-h" | "--help" => {
+                println!("{}", RELATIVIZE_HELP);
+                return;
+            }
+            "--base" => base = Some(crate::option_value(&mut rest, arg)),
+            other if other.starts_with('-') => {
+                eprintln!("Unknown option for relativize: {}", other);
+                std::process::exit(1);
+            }
+            other => urls.push(other),
+        }
+    }
+
+    if base.is_none() && !urls.is_empty() {
+        base = Some(urls.remove(0));
+    }
+    let Some(base) = base else {
+        eprintln!("Usage: kurl relativize <BASE> <URL>...");
+        std::process::exit(1);
+    };
+    let base = crate::parse_url_or_exit(base);
+
+    let buffer;
+    if urls.is_empty() {
+        if io::stdin().is_terminal() {
+            eprintln!("Usage: kurl relativize <BASE> <URL>...");
+            eprintln!("   or: <URLs> | kurl relativize --base <BASE>");
+            std::process::exit(1);
+        }
+        buffer = crate::read_stdin();
+        urls = buffer
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .collect();
+    }
+
+    let mut out = io::stdout().lock();
+    let mut failed = false;
+    for input in &urls {
+        match Url::parse(input) {
+            Ok(url) => {
+                if writeln!(out, "{}", relativize(&base, &url)).is_err() {
+                    return;
+                }
+            }
+            Err(e) => {
+                eprintln!("Failed to parse URL {}: {}", input, e);
+                failed = true;
+            }
+        }
+    }
+    if failed {
+        std::process::exit(1);
+    }
+}
+
+/// Returns the shortest non-empty reference that resolves against `base` to
+/// `url`.
+fn relativize(base: &Url, url: &Url) -> String {
+    let full = url.as_str();
+    let after_scheme = &full[url.scheme().len() + 1..];
+    let from_path = &full[url[..url::Position::BeforePath].len()..];
+    let mut candidates = vec![
+        full.to_string(),
+        after_scheme.to_string(),
+        from_path.to_string(),
+    ];
+    if let Some(relative) = base.make_relative(url) {
+        candidates.push(relative);
+    }
+    // The document's own name, for URLs that drop the base's query.
+    if let Some(segment) = url
+        .path_segments()
+        .and_then(|mut segments| segments.next_back())
+    {
+        candidates.push(format!(
+            "{}{}",
+            segment,
+            &full[url[..url::Position::AfterPath].len()..]
+        ));
+    }
+    if let Some(query_start) = full.find('?') {
+        candidates.push(full[query_start..].to_string());
+    }
+    if let Some(fragment) = url.fragment() {
+        candidates.push(format!("#{}", fragment));
+    }
+
+    candidates
+        .into_iter()
+        .filter(|candidate| !candidate.is_empty())
+        .filter(|candidate| base.join(candidate).as_ref() == Ok(url))
+        .min_by_key(String::len)
+        .unwrap_or_else(|| full.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn relative(base: &str, url: &str) -> String {
+        let (base, url) = (Url::parse(base).unwrap(), Url::parse(url).unwrap());
+        let reference = relativize(&base, &url);
+        assert_eq!(base.join(&reference).unwrap(), url, "{}", reference);
+        reference
+    }
+
+    #[test]
+    fn test_shortest_reference() {
+        let base = "https://example.com/docs/guide/intro?lang=en";
+        assert_eq!(
+            relative(base, "https://example.com/docs/guide/setup"),
+            "setup"
+        );
+        assert_eq!(relative(base, "https://example.com/docs/api/"), "../api/");
+        assert_eq!(relative(base, "https://example.com/"), "/");
+        assert_eq!(
+            relative(base, "https://example.com/docs/guide/intro?lang=de"),
+            "?lang=de"
+        );
+        assert_eq!(
+            relative(base, "https://example.com/docs/guide/intro?lang=en#b"),
+            "#b"
+        );
+        assert_eq!(
+            relative(base, "https://cdn.example.com/app.js"),
+            "//cdn.example.com/app.js"
+        );
+        assert_eq!(
+            relative(base, "http://example.com/docs/"),
+            "http://example.com/docs/"
+        );
+        assert_eq!(
+            relative(base, "mailto:docs@example.com"),
+            "mailto:docs@example.com"
+        );
+        assert_eq!(
+            relative(base, "https://example.com/docs/guide/intro"),
+            "intro"
+        );
+    }
+}
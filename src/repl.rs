@@ -0,0 +1,210 @@
+use std::io::{self, BufRead, IsTerminal, Write};
+use url::Url;
+
+use crate::{OutputOptions, edit};
+
+const REPL_HELP: &str = r#"Commands:
+    <URL>               Make URL the current result
+    set <NAME>=<VALUE>...
+                        Change components of the current result: scheme, user,
+                        password, host, port, path, query, or fragment
+    strip <NAME>...     Remove credentials, port, query, or fragment
+    show                Print the current result's components
+    json                Print the current result's components as JSON
+    history             List the results so far
+    !<N>                Make result N from the history current again
+    help                Show this help
+    quit                Leave the session (or press Ctrl-D)
+
+Each command works on the result of the one before it."#;
+
+pub fn run(args: &[String]) {
+    if let Some(arg) = args.first() {
+        if arg == "-h" || arg == "--help" {
+            println!("kurl repl - edit URLs interactively\n\nUSAGE:\n    kurl repl\n");
+            println!("{}", REPL_HELP);
+            return;
+        }
+        eprintln!("Unknown option for repl: {}", arg);
+        std::process::exit(1);
+    }
+
+    let interactive = io::stdin().is_terminal();
+    if interactive {
+        println!(
+            "kurl {} - type a URL, or `help` for commands",
+            crate::VERSION
+        );
+    }
+
+    let mut session = Session::default();
+    let mut stdin = io::stdin().lock();
+    let mut out = io::stdout().lock();
+    loop {
+        if interactive {
+            let _ = write!(out, "kurl> ");
+            let _ = out.flush();
+        }
+
+        let mut line = String::new();
+        match stdin.read_line(&mut line) {
+            Ok(0) | Err(_) => break,
+            Ok(_) => {}
+        }
+        match session.execute(line.trim()) {
+            Ok(Reply::Text(text)) => {
+                let _ = writeln!(out, "{}", text);
+            }
+            Ok(Reply::Quit) => break,
+            Err(e) => eprintln!("error: {}", e),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum Reply {
+    Text(String),
+    Quit,
+}
+
+/// Every result of a session, the last being the current one.
+#[derive(Debug, Default)]
+struct Session {
+    history: Vec<Url>,
+}
+
+impl Session {
+    fn execute(&mut self, line: &str) -> Result<Reply, String> {
+        let (command, args) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        let args: Vec<&str> = args.split_whitespace().collect();
+
+        let text = match command {
+            "" => String::new(),
+            "help" => REPL_HELP.to_string(),
+            "quit" | "exit" => return Ok(Reply::Quit),
+            "set" => {
+                let mut url = self.current()?.clone();
+                for arg in &args {
+                    let Some((name, value)) = arg.split_once('=') else {
+                        return Err(format!("expected NAME=VALUE, got {}", arg));
+                    };
+                    edit::set(&mut url, name, value)?;
+                }
+                self.push(url)
+            }
+            "strip" => {
+                let mut url = self.current()?.clone();
+                for arg in &args {
+                    edit::strip(&mut url, arg)?;
+                }
+                self.push(url)
+            }
+            "show" | "json" => {
+                let options = OutputOptions {
+                    json_output: command == "json",
+                    ..Default::default()
+                };
+                let mut buffer = Vec::new();
+                let mut records = crate::Records::new(&mut buffer, &options);
+                let _ = records.write(self.current()?, &[]);
+                String::from_utf8_lossy(&buffer).trim_end().to_string()
+            }
+            "history" => self
+                .history
+                .iter()
+                .enumerate()
+                .map(|(i, url)| format!("{:>4}  {}", i + 1, url))
+                .collect::<Vec<_>>()
+                .join("\n"),
+            recall if recall.starts_with('!') => {
+                let url = recall[1..]
+                    .parse::<usize>()
+                    .ok()
+                    .and_then(|n| self.history.get(n.checked_sub(1)?))
+                    .ok_or_else(|| format!("no result {} in history", &recall[1..]))?;
+                self.push(url.clone())
+            }
+            _ => {
+                let url = Url::parse(line)
+                    .map_err(|e| format!("not a command or URL ({}); try `help`", e))?;
+                self.push(url)
+            }
+        };
+        Ok(Reply::Text(text))
+    }
+
+    fn current(&self) -> Result<&Url, String> {
+        self.history
+            .last()
+            .ok_or_else(|| "no current URL; enter one first".to_string())
+    }
+
+    fn push(&mut self, url: Url) -> String {
+        let text = url.to_string();
+        self.history.push(url);
+        text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run_lines(session: &mut Session, lines: &[&str]) -> Vec<Result<Reply, String>> {
+        lines.iter().map(|line| session.execute(line)).collect()
+    }
+
+    fn text(s: &str) -> Result<Reply, String> {
+        Ok(Reply::Text(s.to_string()))
+    }
+
+    #[test]
+    fn test_transforms_chain_on_last_result() {
+        let mut session = Session::default();
+        let replies = run_lines(
+            &mut session,
+            &[
+                "http://u:p@old.example.com/a?b=c#d",
+                "set scheme=https host=new.example.com",
+                "strip query fragment credentials",
+                "!1",
+                "history",
+                "quit",
+            ],
+        );
+        assert_eq!(
+            replies,
+            vec![
+                text("http://u:p@old.example.com/a?b=c#d"),
+                text("https://u:p@new.example.com/a?b=c#d"),
+                text("https://new.example.com/a"),
+                text("http://u:p@old.example.com/a?b=c#d"),
+                text(
+                    "   1  http://u:p@old.example.com/a?b=c#d\n   \
+                     2  https://u:p@new.example.com/a?b=c#d\n   \
+                     3  https://new.example.com/a\n   \
+                     4  http://u:p@old.example.com/a?b=c#d"
+                ),
+                Ok(Reply::Quit),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_errors_leave_the_session_unchanged() {
+        let mut session = Session::default();
+        assert!(session.execute("strip query").is_err());
+        assert!(session.execute("what is this").is_err());
+        session.execute("https://example.com/").unwrap();
+        assert!(session.execute("set port=99999").is_err());
+        assert!(session.execute("set host").is_err());
+        assert!(session.execute("!7").is_err());
+        assert_eq!(session.history.len(), 1);
+        assert_eq!(
+            session.execute("json"),
+            text(
+                r#"{"scheme":"https","host":"example.com","path":"/","site":"https://example.com","registrable_domain":"example.com","public_suffix":"com","domain":"example","suffix":"com","safe_url":"https://example.com/"}"#
+            )
+        );
+    }
+}
@@ -0,0 +1,273 @@
+use std::io::{self, IsTerminal};
+use url::Url;
+
+use crate::OutputOptions;
+use crate::edit;
+use crate::glob::Glob;
+use crate::json::Value;
+use crate::query::{self, Edit};
+use crate::regex::Regex;
+use crate::transform::{self, TransformOptions};
+
+const REWRITE_HELP: &str = r#"kurl rewrite - apply a file of rewrite rules to URLs
+
+USAGE:
+    kurl rewrite [OPTIONS] --rules <FILE> [<URL>...]
+    <URLs> | kurl rewrite [OPTIONS] --rules <FILE>
+
+OPTIONS:
+        --rules <FILE>      TOML file of [[rule]] tables (repeatable)
+    -j, --json              Print each result's components as JSON
+        --dry-run           Report how many URLs would change without printing them
+        --show-diff         Print a diff of the URLs that would change
+        --verify-idempotent Fail, printing nothing, unless a second pass leaves
+                            every result unchanged
+    -h, --help              Show this help message
+
+Each rule matches on any of:
+    host = "*.old.example.com"      Glob, as in `kurl --match-host`,
+                                    ignoring case
+    path = '^/blog/\d+'             Regex, as in `kurl set --when`
+and then, in this order:
+    set_scheme = "https"
+    replace_path_prefix = { from = "/blog", to = "/posts" }
+    remove_params = ["sessionid", "ref"]
+    add_params = { source = "migrated" }
+
+A rule without matchers applies to every URL. Rules test the input URL and
+every matching rule applies, in file order.
+
+EXAMPLES:
+    kurl rewrite --rules migration.toml < inventory.txt
+    kurl rewrite --rules migration.toml --show-diff < inventory.txt
+"#;
+
+/// One `[[rule]]` table from a rules file.
+#[derive(Debug, Default)]
+struct Rule {
+    host: Option<Glob>,
+    path: Option<Regex>,
+    set_scheme: Option<String>,
+    replace_path_prefix: Option<(String, String)>,
+    remove_params: Vec<String>,
+    add_params: Vec<(String, String)>,
+}
+
+pub fn run(args: &[String]) {
+    let mut rules = Vec::new();
+    let mut urls = Vec::new();
+    let mut options = TransformOptions::default();
+    let mut output = OutputOptions::default();
+
+    let mut rest = args.iter();
+    while let Some(arg) = rest.next() {
+        match arg.as_str() {
+            "-h" | "--help" => {
+                println!("{}", REWRITE_HELP);
+                return;
+            }
+            "--rules" => {
+                let path = crate::option_value(&mut rest, arg);
+                let loaded = std::fs::read_to_string(path)
+                    .map_err(|e| e.to_string())
+                    .and_then(|text| parse_rules(&text))
+                    .unwrap_or_else(|e| {
+                        eprintln!("Failed to load rules {}: {}", path, e);
+                        std::process::exit(1);
+                    });
+                rules.extend(loaded);
+            }
+            other if options.accept(other) => {}
+            other if output.accept(other, &mut rest) => {}
+            other if other.starts_with('-') => {
+                eprintln!("Unknown option for rewrite: {}", other);
+                std::process::exit(1);
+            }
+            other => urls.push(other),
+        }
+    }
+
+    if rules.is_empty() {
+        eprintln!("Usage: kurl rewrite --rules <FILE> [<URL>...]");
+        std::process::exit(1);
+    }
+
+    let mut buffer = String::new();
+    if urls.is_empty() {
+        if io::stdin().is_terminal() {
+            eprintln!("Usage: kurl rewrite --rules <FILE> [<URL>...]");
+            std::process::exit(1);
+        }
+        buffer = crate::read_stdin();
+        urls.push("-");
+    }
+    let inputs = crate::batch_inputs(&urls, &buffer);
+
+    transform::run(&inputs, &options, &output, |url| apply_rules(url, &rules));
+}
+
+fn parse_rules(text: &str) -> Result<Vec<Rule>, String> {
+    let config = crate::config::parse(text)?;
+    let tables = match config.get("rule") {
+        None => return Ok(Vec::new()),
+        Some(Value::Array(tables)) => tables,
+        Some(_) => return Err("rule must be an array of tables, as in [[rule]]".to_string()),
+    };
+    tables
+        .iter()
+        .enumerate()
+        .map(|(i, table)| parse_rule(table).map_err(|e| format!("rule {}: {}", i + 1, e)))
+        .collect()
+}
+
+fn parse_rule(table: &Value) -> Result<Rule, String> {
+    let Value::Object(fields) = table else {
+        return Err("expected a table".to_string());
+    };
+    let string = |key: &str, value: &Value| match value {
+        Value::String(text) => Ok(text.clone()),
+        _ => Err(format!("{} must be a string", key)),
+    };
+
+    let mut rule = Rule::default();
+    for (key, value) in fields {
+        match key.as_str() {
+            "host" => {
+                let pattern = string(key, value)?.to_lowercase();
+                rule.host = Some(
+                    Glob::new(&pattern, None).map_err(|e| format!("invalid host glob: {}", e))?,
+                );
+            }
+            "path" => {
+                let pattern = string(key, value)?;
+                rule.path =
+                    Some(Regex::new(&pattern).map_err(|e| format!("invalid path regex: {}", e))?);
+            }
+            "set_scheme" => rule.set_scheme = Some(string(key, value)?),
+            "replace_path_prefix" => {
+                let (Some(from), Some(to)) = (value.get("from"), value.get("to")) else {
+                    return Err("replace_path_prefix needs from and to".to_string());
+                };
+                rule.replace_path_prefix = Some((string("from", from)?, string("to", to)?));
+            }
+            "remove_params" => {
+                let Value::Array(names) = value else {
+                    return Err("remove_params must be an array of names".to_string());
+                };
+                rule.remove_params = names
+                    .iter()
+                    .map(|name| string("remove_params", name))
+                    .collect::<Result<_, _>>()?;
+            }
+            "add_params" => {
+                let Value::Object(pairs) = value else {
+                    return Err("add_params must be a table".to_string());
+                };
+                rule.add_params = pairs
+                    .iter()
+                    .map(|(name, value)| Ok((name.clone(), string(name, value)?)))
+                    .collect::<Result<_, String>>()?;
+            }
+            other => return Err(format!("unknown key {}", other)),
+        }
+    }
+    Ok(rule)
+}
+
+/// Applies every rule that matches `url`, in order.
+fn apply_rules(url: &Url, rules: &[Rule]) -> Result<Url, String> {
+    rules
+        .iter()
+        .filter(|rule| rule.matches(url))
+        .try_fold(url.clone(), |result, rule| rule.apply(&result))
+}
+
+impl Rule {
+    fn matches(&self, url: &Url) -> bool {
+        let host = url.host_str().unwrap_or_default();
+        self.host
+            .as_ref()
+            .is_none_or(|pattern| pattern.matches(host))
+            && self
+                .path
+                .as_ref()
+                .is_none_or(|pattern| pattern.is_match(url.path()))
+    }
+
+    fn apply(&self, url: &Url) -> Result<Url, String> {
+        let mut url = url.clone();
+        if let Some(scheme) = &self.set_scheme {
+            edit::set(&mut url, "scheme", scheme)?;
+        }
+        if let Some((from, to)) = &self.replace_path_prefix {
+            let path = url.path().to_string();
+            edit::strip_path_prefix(&mut url, from);
+            if url.path() != path {
+                edit::prefix_path(&mut url, to)?;
+            }
+        }
+        let edits: Vec<Edit> = self
+            .remove_params
+            .iter()
+            .map(|name| Edit::Remove(name))
+            .chain(
+                self.add_params
+                    .iter()
+                    .map(|(name, value)| Edit::Add(name, value)),
+            )
+            .collect();
+        if !edits.is_empty() {
+            url = query::apply(&url, &edits);
+        }
+        Ok(url)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rules() {
+        let rules = parse_rules(
+            r#"
+            [[rule]]
+            host = "*.Old.example.com"
+            path = '^/blog/'
+            set_scheme = "https"
+            replace_path_prefix = { from = "/blog", to = "/posts" }
+            remove_params = ["sessionid"]
+            add_params = { source = "old blog" }
+
+            [[rule]]
+            path = '^/blog/'
+            remove_params = ["ref"]
+            "#,
+        )
+        .unwrap();
+        let rewritten = |url: &str| {
+            apply_rules(&Url::parse(url).unwrap(), &rules)
+                .unwrap()
+                .to_string()
+        };
+        assert_eq!(
+            rewritten("http://www.old.example.com/blog/7?sessionid=1&ref=x&id=2"),
+            "https://www.old.example.com/posts/7?id=2&source=old+blog"
+        );
+        assert_eq!(
+            rewritten("http://old.example.com/blog/7?ref=x"),
+            "http://old.example.com/blog/7"
+        );
+
+        for text in [
+            "rule = 1",
+            "[[rule]]\nhst = \"a.com\"",
+            "[[rule]]\npath = \"(\"",
+            "[[rule]]\nhost = \"{a\"",
+            "[[rule]]\nreplace_path_prefix = { from = \"/a\" }",
+            "[[rule]]\nadd_params = [\"a\"]",
+        ] {
+            assert!(parse_rules(text).is_err(), "{}", text);
+        }
+    }
+}
@@ -0,0 +1,255 @@
+use std::net::Ipv6Addr;
+use std::ops::Range;
+
+/// The sub-delims of RFC 3986, allowed as data in every component.
+const SUB_DELIMS: &[u8] = b"!$&'()*+,;=";
+
+/// Checks that `input` is a URI reference by the grammar of RFC 3986: an
+/// absolute URI, or a relative reference like `//host/a` or `a/b`. The URL
+/// Standard that browsers follow accepts much more and fixes it quietly,
+/// turning `\` into `/`, dropping tabs and newlines, and percent-encoding
+/// spaces, non-ASCII characters, and stray `%` signs.
+pub fn check(input: &str) -> Result<(), String> {
+    reference(input).map_err(|e| format!("not valid RFC 3986: {}", e))
+}
+
+fn reference(input: &str) -> Result<(), String> {
+    let end =
+        |from: usize, stops: &[char]| input[from..].find(stops).map_or(input.len(), |i| from + i);
+    // A `:` before any `/`, `?`, or `#` ends a scheme; relative references
+    // can't have one in their first segment.
+    let mut at = 0;
+    let colon = end(0, &[':', '/', '?', '#']);
+    if input[colon..].starts_with(':') {
+        scheme(input, colon)?;
+        at = colon + 1;
+    }
+    if input[at..].starts_with("//") {
+        let authority_end = end(at + 2, &['/', '?', '#']);
+        authority(input, at + 2..authority_end)?;
+        at = authority_end;
+    }
+    let path_end = end(at, &['?', '#']);
+    chars(input, at..path_end, b":@/", "path")?;
+    at = path_end;
+    if input[at..].starts_with('?') {
+        let query_end = end(at, &['#']);
+        chars(input, at + 1..query_end, b":@/?", "query")?;
+        at = query_end;
+    }
+    if at < input.len() {
+        chars(input, at + 1..input.len(), b":@/?", "fragment")?;
+    }
+    Ok(())
+}
+
+/// Checks the scheme that ends at the `:` at byte `colon`.
+fn scheme(input: &str, colon: usize) -> Result<(), String> {
+    if colon == 0 {
+        return Err("the scheme before the ':' at column 1 is empty".to_string());
+    }
+    for (i, c) in input[..colon].char_indices() {
+        let allowed = if i == 0 {
+            c.is_ascii_alphabetic()
+        } else {
+            c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.')
+        };
+        if !allowed {
+            return Err(disallowed(input, i, "scheme"));
+        }
+    }
+    Ok(())
+}
+
+/// Checks `[userinfo@]host[:port]`, where the host is a registered name, an
+/// IPv4 address, or a bracketed IPv6 or IPvFuture literal.
+fn authority(input: &str, range: Range<usize>) -> Result<(), String> {
+    let mut host = range.start;
+    if let Some(at) = input[range.clone()].find('@') {
+        chars(input, range.start..range.start + at, b":", "userinfo")?;
+        host += at + 1;
+    }
+    let port = if input[host..].starts_with('[') {
+        let Some(close) = input[host..range.end].find(']') else {
+            return Err(format!(
+                "the '[' at column {} has no closing ']'",
+                column(input, host)
+            ));
+        };
+        if !ip_literal(&input[host + 1..host + close]) {
+            return Err(format!(
+                "{} at column {} isn't an IPv6 address",
+                &input[host..=host + close],
+                column(input, host)
+            ));
+        }
+        let after = host + close + 1;
+        if after < range.end && !input[after..].starts_with(':') {
+            return Err(disallowed(input, after, "host"));
+        }
+        after
+    } else {
+        let colon = input[host..range.end]
+            .find(':')
+            .map_or(range.end, |i| host + i);
+        chars(input, host..colon, b"", "host")?;
+        colon
+    };
+    if port < range.end
+        && let Some(i) = input[port + 1..range.end].find(|c: char| !c.is_ascii_digit())
+    {
+        return Err(disallowed(input, port + 1 + i, "port"));
+    }
+    Ok(())
+}
+
+/// Whether `text`, from between brackets, is an IPv6 address or an IPvFuture
+/// literal like `v1.fe80::a+en1`. Zone IDs aren't part of RFC 3986.
+fn ip_literal(text: &str) -> bool {
+    match text.strip_prefix(['v', 'V']) {
+        Some(future) => future.split_once('.').is_some_and(|(version, rest)| {
+            !version.is_empty()
+                && version.bytes().all(|b| b.is_ascii_hexdigit())
+                && !rest.is_empty()
+                && rest
+                    .bytes()
+                    .all(|b| unreserved(b) || SUB_DELIMS.contains(&b) || b == b':')
+        }),
+        None => text.parse::<Ipv6Addr>().is_ok(),
+    }
+}
+
+/// Checks that `range` of `input` holds only unreserved characters,
+/// sub-delims, `%XX` escapes, and the `extra` characters its component
+/// allows.
+fn chars(input: &str, range: Range<usize>, extra: &[u8], component: &str) -> Result<(), String> {
+    let bytes = input.as_bytes();
+    let mut i = range.start;
+    while i < range.end {
+        let b = bytes[i];
+        if b == b'%' {
+            let escape = bytes.get(i + 1..i + 3);
+            if !escape.is_some_and(|hex| hex.iter().all(u8::is_ascii_hexdigit)) {
+                return Err(format!(
+                    "the '%' at column {} isn't followed by two hex digits",
+                    column(input, i)
+                ));
+            }
+            i += 3;
+        } else if unreserved(b) || SUB_DELIMS.contains(&b) || extra.contains(&b) {
+            i += 1;
+        } else {
+            return Err(disallowed(input, i, component));
+        }
+    }
+    Ok(())
+}
+
+fn unreserved(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || matches!(b, b'-' | b'.' | b'_' | b'~')
+}
+
+fn disallowed(input: &str, at: usize, component: &str) -> String {
+    let c = input[at..].chars().next().unwrap_or_default();
+    format!(
+        "{:?} at column {} isn't allowed in the {}",
+        c,
+        column(input, at),
+        component
+    )
+}
+
+/// The 1-based column, in characters, of byte `at`.
+fn column(input: &str, at: usize) -> usize {
+    input[..at].chars().count() + 1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check() {
+        for valid in [
+            "https://user:pw@example.com:8080/a/b;c=d?q=1&r=/?x#frag/?",
+            "https://example.com",
+            "https://example.com:/",
+            "http://[::1]:80/",
+            "http://[v1.fe80::a+en1]/",
+            "http://192.168.0.1/",
+            "file:///etc/hosts",
+            "mailto:a@example.com",
+            "urn:isbn:0451450523",
+            "https://example.com/caf%C3%A9",
+            "//cdn.example.com/lib.js",
+            "example.com/a",
+            "?q=1",
+            "",
+        ] {
+            assert_eq!(check(valid), Ok(()), "{}", valid);
+        }
+    }
+
+    #[test]
+    fn test_check_rejects_what_browsers_fix() {
+        for (invalid, problem) in [
+            (
+                "https://example.com\\a",
+                "'\\\\' at column 20 isn't allowed in the host",
+            ),
+            (
+                "https://example.com/a b",
+                "' ' at column 22 isn't allowed in the path",
+            ),
+            (
+                " https://example.com/",
+                "' ' at column 1 isn't allowed in the scheme",
+            ),
+            (
+                "https://example.com/\ta",
+                "'\\t' at column 21 isn't allowed in the path",
+            ),
+            (
+                "https://example.com/café",
+                "'é' at column 24 isn't allowed in the path",
+            ),
+            (
+                "https://example.com/?q=100%",
+                "the '%' at column 27 isn't followed by two hex digits",
+            ),
+            (
+                "https://example.com/{id}",
+                "'{' at column 21 isn't allowed in the path",
+            ),
+            (
+                "https://example.com/#a#b",
+                "'#' at column 23 isn't allowed in the fragment",
+            ),
+            (
+                "https://a@b@example.com/",
+                "'@' at column 12 isn't allowed in the host",
+            ),
+            (
+                "https://example.com:80a/",
+                "'a' at column 23 isn't allowed in the port",
+            ),
+            ("http://[::1/", "the '[' at column 8 has no closing ']'"),
+            (
+                "http://[fe80::1%25en0]/",
+                "[fe80::1%25en0] at column 8 isn't an IPv6 address",
+            ),
+            (":path", "the scheme before the ':' at column 1 is empty"),
+            (
+                "1http://example.com/",
+                "'1' at column 1 isn't allowed in the scheme",
+            ),
+        ] {
+            assert_eq!(
+                check(invalid),
+                Err(format!("not valid RFC 3986: {}", problem)),
+                "{}",
+                invalid
+            );
+        }
+    }
+}
@@ -0,0 +1,339 @@
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use url::Url;
+
+use crate::glob::Glob;
+use crate::json::Value;
+use crate::{OutputOptions, Records, extract};
+
+const SCAN_HELP: &str = r#"kurl scan - find the URLs referenced in a source tree
+
+USAGE:
+    kurl scan [OPTIONS] [<PATH>...]
+
+OPTIONS:
+    -j, --json          Print each URL as a JSON record with its `file`,
+                        `line`, and `column`
+        --get <FIELD>   Print only FIELD of each URL, as with `kurl --get`
+    -h, --help          Show this help message
+
+Each PATH (the current directory by default) is a file, or a directory that
+is scanned recursively. In a git work tree, files that git ignores are
+skipped, following .gitignore and the other exclude files exactly as git
+does; elsewhere, the .gitignore files in the scanned directories are read
+for the same patterns, and .git directories are skipped. Binary files are
+skipped.
+
+EXAMPLES:
+    kurl scan
+    kurl scan src config --json
+    kurl scan --get host | sort | uniq -c
+"#;
+
+pub fn run(args: &[String]) {
+    let mut output = OutputOptions::default();
+    let mut paths = Vec::new();
+
+    let mut rest = args.iter();
+    while let Some(arg) = rest.next() {
+        match arg.as_str() {
+            "-h" | "--help" => {
+                println!("{}", SCAN_HELP);
+                return;
+            }
+            other if output.accept(other, &mut rest) => {}
+            other if other.starts_with('-') => {
+                eprintln!("Unknown option for scan: {}", other);
+                std::process::exit(1);
+            }
+            path => paths.push(path),
+        }
+    }
+    if paths.is_empty() {
+        paths.push(".");
+    }
+
+    let mut out = io::stdout().lock();
+    let mut records = Records::new(&mut out, &output);
+    let _ = each_file(&paths, |name, text| write_urls(&mut records, name, text));
+}
+
+/// Calls `f` with the name and text of each text file in `paths`, which are
+/// files or directories to search with `files_in`, stopping at its first
+/// error.
+pub fn each_file(
+    paths: &[&str],
+    mut f: impl FnMut(&str, &str) -> io::Result<()>,
+) -> io::Result<()> {
+    for path in paths {
+        let files = if Path::new(path).is_dir() {
+            files_in(Path::new(path))
+        } else {
+            vec![PathBuf::from(path)]
+        };
+        for file in files {
+            // Unreadable and binary files have no URL literals to report.
+            let Ok(text) = std::fs::read_to_string(&file) else {
+                continue;
+            };
+            if text.contains('\0') {
+                continue;
+            }
+            let name = file
+                .strip_prefix(".")
+                .unwrap_or(&file)
+                .display()
+                .to_string();
+            f(&name, &text)?;
+        }
+    }
+    Ok(())
+}
+
+/// Lists the files under `dir` that git doesn't ignore, or, if `dir` isn't
+/// in a git work tree, those its .gitignore files don't.
+fn files_in(dir: &Path) -> Vec<PathBuf> {
+    let listed = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args([
+            "ls-files",
+            "-z",
+            "--cached",
+            "--others",
+            "--exclude-standard",
+        ])
+        .output();
+    match listed {
+        Ok(output) if output.status.success() => String::from_utf8_lossy(&output.stdout)
+            .split('\0')
+            .filter(|name| !name.is_empty())
+            .map(|name| dir.join(name))
+            // Deleted but not yet staged files are still listed.
+            .filter(|path| path.is_file())
+            .collect(),
+        _ => {
+            let mut files = Vec::new();
+            walk(dir, &mut files);
+            files
+        }
+    }
+}
+
+/// Collects every file under `dir` in sorted order, skipping .git directories
+/// and what the .gitignore files under `dir` exclude.
+fn walk(dir: &Path, files: &mut Vec<PathBuf>) {
+    walk_ignoring(dir, &mut Vec::new(), files);
+}
+
+fn walk_ignoring(dir: &Path, rules: &mut Vec<IgnoreRule>, files: &mut Vec<PathBuf>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    let inherited = rules.len();
+    if let Ok(text) = std::fs::read_to_string(dir.join(".gitignore")) {
+        rules.extend(text.lines().filter_map(|line| IgnoreRule::parse(line, dir)));
+    }
+    let mut paths: Vec<PathBuf> = entries.filter_map(|e| Some(e.ok()?.path())).collect();
+    paths.sort();
+    for path in paths {
+        let is_dir = path.is_dir();
+        if ignored(rules, &path, is_dir) {
+            continue;
+        }
+        if is_dir {
+            if path.file_name().is_some_and(|name| name != ".git") {
+                walk_ignoring(&path, rules, files);
+            }
+        } else if path.is_file() {
+            files.push(path);
+        }
+    }
+    rules.truncate(inherited);
+}
+
+/// A pattern from a .gitignore file.
+struct IgnoreRule {
+    /// The directory holding the .gitignore.
+    base: PathBuf,
+    glob: Glob,
+    /// Whether the pattern is matched against the path from `base`, as when
+    /// it has a `/` before its end, rather than against the name alone.
+    anchored: bool,
+    /// A `!` pattern, which includes what earlier ones excluded.
+    negated: bool,
+    /// A pattern ending in `/`, which only matches directories.
+    dirs_only: bool,
+}
+
+impl IgnoreRule {
+    /// Reads a line of the .gitignore in `base`, or `None` for blank lines
+    /// and comments.
+    fn parse(line: &str, base: &Path) -> Option<IgnoreRule> {
+        let line = line.trim_end();
+        if line.is_empty() || line.starts_with('#') {
+            return None;
+        }
+        let (negated, line) = match line.strip_prefix('!') {
+            Some(rest) => (true, rest),
+            None => (false, line.strip_prefix('\\').unwrap_or(line)),
+        };
+        let (dirs_only, line) = match line.strip_suffix('/') {
+            Some(rest) => (true, rest),
+            None => (false, line),
+        };
+        let anchored = line.contains('/');
+        let line = line.strip_prefix('/').unwrap_or(line);
+        Some(IgnoreRule {
+            base: base.to_path_buf(),
+            glob: Glob::new(line, Some('/')).ok()?,
+            anchored,
+            negated,
+            dirs_only,
+        })
+    }
+
+    fn matches(&self, path: &Path, is_dir: bool) -> bool {
+        if self.dirs_only && !is_dir {
+            return false;
+        }
+        let Ok(relative) = path.strip_prefix(&self.base) else {
+            return false;
+        };
+        let text = if self.anchored {
+            let parts: Vec<_> = relative.iter().map(|part| part.to_string_lossy()).collect();
+            parts.join("/")
+        } else {
+            relative
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default()
+        };
+        self.glob.matches(&text)
+    }
+}
+
+/// Whether the last of `rules` to match `path` excludes it.
+fn ignored(rules: &[IgnoreRule], path: &Path, is_dir: bool) -> bool {
+    rules
+        .iter()
+        .rev()
+        .find(|rule| rule.matches(path, is_dir))
+        .is_some_and(|rule| !rule.negated)
+}
+
+/// Writes each URL in `text` with where it was found: `file:line:column: url`,
+/// or a record with `file`, `line`, and `column` fields in JSON or `--get`
+/// output, where URLs that don't parse are left out.
+fn write_urls<W: Write>(records: &mut Records<'_, W>, file: &str, text: &str) -> io::Result<()> {
+    let plain = !records.options.json_output && records.options.get.is_empty();
+    for (i, line) in text.lines().enumerate() {
+        for (offset, found) in extract::find_urls(line) {
+            let column = line[..offset].chars().count() + 1;
+            if plain {
+                writeln!(records.writer, "{}:{}:{}: {}", file, i + 1, column, found)?;
+                continue;
+            }
+            let Ok(url) = Url::parse(found) else {
+                continue;
+            };
+            let extra = [
+                ("file", Value::from(file)),
+                ("line", Value::from(i + 1)),
+                ("column", Value::from(column)),
+            ];
+            records.write(&url, &extra)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEXT: &str = "const API: &str = \"https://api.example.com/v1\";\n\
+                        // see http://[bad and https://docs.example.com/x?a=1\n";
+
+    fn scan(options: &OutputOptions) -> String {
+        let mut buffer = Vec::new();
+        let mut records = Records::new(&mut buffer, options);
+        write_urls(&mut records, "src/api.rs", TEXT).unwrap();
+        String::from_utf8(buffer).unwrap()
+    }
+
+    #[test]
+    fn test_plain_locations() {
+        assert_eq!(
+            scan(&OutputOptions::default()),
+            "src/api.rs:1:20: https://api.example.com/v1\n\
+             src/api.rs:2:8: http://[bad\n\
+             src/api.rs:2:24: https://docs.example.com/x?a=1\n"
+        );
+    }
+
+    #[test]
+    fn test_records_skip_unparsable() {
+        let options = OutputOptions {
+            get: ["file", "line", "column", "host"]
+                .map(String::from)
+                .to_vec(),
+            ..Default::default()
+        };
+        assert_eq!(
+            scan(&options),
+            "src/api.rs\t1\t20\tapi.example.com\nsrc/api.rs\t2\t24\tdocs.example.com\n"
+        );
+    }
+
+    #[test]
+    fn test_walk_skips_git_directory() {
+        let dir = std::env::temp_dir().join(format!("kurl-scan-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join(".git")).unwrap();
+        std::fs::create_dir_all(dir.join("b")).unwrap();
+        std::fs::write(dir.join(".git/config"), "").unwrap();
+        std::fs::write(dir.join("b/c.txt"), "").unwrap();
+        std::fs::write(dir.join("a.txt"), "").unwrap();
+
+        let mut files = Vec::new();
+        walk(&dir, &mut files);
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(files, vec![dir.join("a.txt"), dir.join("b/c.txt")]);
+    }
+
+    #[test]
+    fn test_walk_follows_gitignore() {
+        let dir = std::env::temp_dir().join(format!("kurl-scan-ignore-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("build")).unwrap();
+        std::fs::create_dir_all(dir.join("src/gen")).unwrap();
+        std::fs::write(
+            dir.join(".gitignore"),
+            "# output\nbuild/\n*.log\n!keep.log\n",
+        )
+        .unwrap();
+        std::fs::write(dir.join("src/.gitignore"), "/gen\n**/tmp/*.txt\n").unwrap();
+        for file in [
+            "build/a.txt",
+            "src/gen/b.txt",
+            "src/c.txt",
+            "d.log",
+            "keep.log",
+        ] {
+            std::fs::write(dir.join(file), "").unwrap();
+        }
+
+        let mut files = Vec::new();
+        walk(&dir, &mut files);
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(
+            files,
+            vec![
+                dir.join(".gitignore"),
+                dir.join("keep.log"),
+                dir.join("src/.gitignore"),
+                dir.join("src/c.txt"),
+            ]
+        );
+    }
+}
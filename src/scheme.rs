@@ -0,0 +1,191 @@
+use std::io::{self, Write};
+use url::Url;
+
+use crate::json::Value;
+
+const REGISTRY: &str = include_str!("../data/uri_schemes.tsv");
+
+/// Schemes the URL parser gives special handling, such as default ports and
+/// path normalization; any other scheme is parsed generically.
+pub const SPECIAL: &[&str] = &["http", "https", "ws", "wss", "ftp", "file"];
+
+const SCHEME_HELP: &str = r#"kurl scheme - look up a URL scheme
+
+USAGE:
+    kurl scheme [OPTIONS] <NAME>
+
+OPTIONS:
+    -j, --json          Output as JSON instead of formatted text
+    -h, --help          Show this help message
+
+Reports whether NAME is in the IANA URI scheme registry and with what status
+(permanent, provisional, or historical), whether the WHATWG URL Standard
+treats it as special, its usual port, and what it is used for. The bundled
+registry covers the commonly seen schemes, so an unlisted one may still be
+registered.
+"#;
+
+pub fn run(args: &[String]) {
+    let mut json_output = false;
+    let mut name = None;
+    for arg in args {
+        match arg.as_str() {
+            "-h" | "--help" => {
+                println!("{}", SCHEME_HELP);
+                return;
+            }
+            "-j" | "--json" => json_output = true,
+            other if name.is_none() && !other.starts_with('-') => name = Some(other),
+            other => {
+                eprintln!("Unknown option for scheme: {}", other);
+                std::process::exit(1);
+            }
+        }
+    }
+
+    let Some(name) = name else {
+        eprintln!("Usage: kurl scheme [--json] <NAME>");
+        std::process::exit(1);
+    };
+    let name = name.strip_suffix(':').unwrap_or(name);
+    let Some(info) = lookup(name) else {
+        eprintln!("Invalid scheme name: {}", name);
+        std::process::exit(1);
+    };
+
+    let mut out = io::stdout().lock();
+    let _ = if json_output {
+        info.write(&mut out).and_then(|_| writeln!(out))
+    } else {
+        write_pretty(&mut out, &info)
+    };
+}
+
+/// Describes the scheme `name`, or returns `None` if it isn't a valid scheme
+/// name at all.
+fn lookup(name: &str) -> Option<Value> {
+    let valid = name.starts_with(|c: char| c.is_ascii_alphabetic())
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'));
+    if !valid {
+        return None;
+    }
+    let name = name.to_ascii_lowercase();
+
+    let entry = entry(&name);
+    let special = SPECIAL.contains(&name.as_str());
+    let port = default_port(&name).map(Value::from);
+
+    Some(Value::Object(vec![
+        ("scheme".to_string(), Value::from(name.as_str())),
+        ("registered".to_string(), Value::from(entry.is_some())),
+        (
+            "status".to_string(),
+            entry.as_ref().map_or(Value::Null, |c| Value::from(c[1])),
+        ),
+        ("special".to_string(), Value::from(special)),
+        ("default_port".to_string(), port.unwrap_or(Value::Null)),
+        (
+            "usage".to_string(),
+            entry.as_ref().map_or(Value::Null, |c| Value::from(c[3])),
+        ),
+    ]))
+}
+
+/// The registry columns for the lowercase scheme `name`.
+fn entry(name: &str) -> Option<Vec<&'static str>> {
+    REGISTRY
+        .lines()
+        .filter(|line| !line.starts_with('#'))
+        .map(|line| line.split('\t').collect::<Vec<_>>())
+        .find(|columns| columns[0] == name)
+}
+
+/// The port the lowercase scheme `name` is usually served on, if any.
+pub fn default_port(name: &str) -> Option<u16> {
+    // Special schemes' ports come from the URL parser, so they always agree
+    // with what it drops when parsing.
+    if SPECIAL.contains(&name) {
+        Url::parse(&format!("{}://example.com/", name))
+            .ok()
+            .and_then(|url| url.port_or_known_default())
+    } else {
+        entry(name).and_then(|columns| columns[2].parse().ok())
+    }
+}
+
+fn write_pretty<W: Write>(writer: &mut W, info: &Value) -> io::Result<()> {
+    let field = |name| info.get(name).unwrap_or(&Value::Null);
+    let yes_no = |value: &Value| {
+        if value == &Value::Bool(true) {
+            "yes"
+        } else {
+            "no"
+        }
+    };
+
+    writeln!(writer, "  scheme\t: {}", field("scheme"))?;
+    match field("status") {
+        Value::Null => writeln!(writer, "  registered\t: no")?,
+        status => writeln!(writer, "  registered\t: yes ({})", status)?,
+    }
+    writeln!(writer, "  special\t: {}", yes_no(field("special")))?;
+    if let port @ Value::Number(_) = field("default_port") {
+        writeln!(writer, "  port\t\t: {}", port)?;
+    }
+    if let usage @ Value::String(_) = field("usage") {
+        writeln!(writer, "  usage\t\t: {}", usage)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_registry_is_well_formed() {
+        let mut previous = "";
+        for line in REGISTRY.lines().filter(|line| !line.starts_with('#')) {
+            let columns: Vec<&str> = line.split('\t').collect();
+            assert_eq!(columns.len(), 4, "{}", line);
+            assert!(
+                matches!(columns[1], "permanent" | "provisional" | "historical"),
+                "{}",
+                line
+            );
+            assert!(columns[2] == "-" || columns[2].parse::<u16>().is_ok());
+            assert!(previous < columns[0], "{} is out of order", columns[0]);
+            previous = columns[0];
+        }
+    }
+
+    #[test]
+    fn test_lookup() {
+        assert_eq!(
+            lookup("HTTPS").unwrap().to_json(),
+            r#"{"scheme":"https","registered":true,"status":"permanent","special":true,"default_port":443,"usage":"Hypertext Transfer Protocol over TLS"}"#
+        );
+        let ssh = lookup("ssh").unwrap();
+        assert_eq!(ssh.get("special"), Some(&Value::Bool(false)));
+        assert_eq!(ssh.get("default_port"), Some(&Value::from(22u16)));
+        assert_eq!(
+            lookup("x-made-up").unwrap().to_json(),
+            r#"{"scheme":"x-made-up","registered":false,"status":null,"special":false,"default_port":null,"usage":null}"#
+        );
+        assert!(lookup("1http").is_none());
+        assert!(lookup("").is_none());
+    }
+
+    #[test]
+    fn test_pretty() {
+        let mut output = Vec::new();
+        write_pretty(&mut output, &lookup("file").unwrap()).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "  scheme\t: file\n  registered\t: yes (permanent)\n  special\t: yes\n  \
+             usage\t\t: Files on the local machine or a shared filesystem\n"
+        );
+    }
+}
@@ -0,0 +1,424 @@
+use std::io::{self, IsTerminal, Write};
+use url::{Host, Url};
+
+use crate::homograph;
+use crate::json::Value;
+use crate::lint;
+use crate::psl;
+
+const SCORE_HELP: &str = r#"kurl score - rate how much URLs look like phishing
+
+USAGE:
+    kurl score [OPTIONS] [<URL>...]
+    <URLs> | kurl score [OPTIONS]
+
+OPTIONS:
+    -j, --json          Print a JSON object per URL with `url`, `score`, and
+                        the `signals` it triggered
+        --threshold <N> Exit 1 if any URL scores N or more
+        --list-signals  List the signals and what each adds to the score
+    -h, --help          Show this help message
+
+Adds up the weights of the signals a URL triggers, such as credentials before
+the host, an IP address for a host, or a brand name in a subdomain, to a
+score from 0 to 100, and prints it with the signals and why each fired. The
+score is a heuristic for triage, not a verdict: plenty of harmless URLs
+trigger a signal or two, and a phishing URL on a plain domain triggers none.
+
+EXAMPLES:
+    kurl score 'http://paypal.com.account-verify.example.top/login'
+    kurl score --threshold 50 < links.txt
+    kurl score --json "$url" | jq .score
+"#;
+
+/// Highest score a URL can get, however many signals it triggers.
+const MAX_SCORE: u32 = 100;
+
+/// Subdomains with more labels than this are `deep-subdomain`.
+const MAX_SUBDOMAIN_LABELS: usize = 3;
+
+/// Domain labels with at least this many hyphens are `hyphenated-domain`.
+const MIN_HYPHENS: usize = 3;
+
+/// Often-impersonated brands, as they appear in domain labels.
+const BRANDS: &[&str] = &[
+    "adobe",
+    "amazon",
+    "apple",
+    "binance",
+    "chase",
+    "coinbase",
+    "docusign",
+    "dropbox",
+    "facebook",
+    "google",
+    "icloud",
+    "instagram",
+    "linkedin",
+    "microsoft",
+    "netflix",
+    "office365",
+    "outlook",
+    "paypal",
+    "steam",
+    "wellsfargo",
+    "whatsapp",
+];
+
+/// TLDs where phishing and malware domains are far above their share of
+/// registrations, by abuse reports, or that are file extensions.
+const SUSPICIOUS_TLDS: &[&str] = &[
+    "buzz", "cam", "cf", "click", "country", "ga", "gq", "icu", "kim", "link", "ml", "monster",
+    "mov", "rest", "tk", "top", "work", "xyz", "zip",
+];
+
+/// Something about a URL that phishing URLs tend to have.
+pub struct Signal {
+    pub name: &'static str,
+    pub weight: u32,
+    pub summary: &'static str,
+}
+
+pub const SIGNALS: &[Signal] = &[
+    Signal {
+        name: "credentials",
+        weight: 25,
+        summary: "A username or password, which can pose as the host, as in paypal.com@evil.example",
+    },
+    Signal {
+        name: "ip-host",
+        weight: 20,
+        summary: "An IP address for a host, rather than a name",
+    },
+    Signal {
+        name: "deep-subdomain",
+        weight: 15,
+        summary: "More than 3 labels before the registrable domain",
+    },
+    Signal {
+        name: "brand-in-subdomain",
+        weight: 25,
+        summary: "A well-known brand in the subdomain of some other domain",
+    },
+    Signal {
+        name: "punycode",
+        weight: 15,
+        summary: "An xn-- label, so the host shows as Unicode",
+    },
+    Signal {
+        name: "homograph",
+        weight: 30,
+        summary: "A label that mixes scripts or passes for ASCII",
+    },
+    Signal {
+        name: "suspicious-tld",
+        weight: 15,
+        summary: "A TLD that is much abused or is a file extension, like zip",
+    },
+    Signal {
+        name: "hyphenated-domain",
+        weight: 10,
+        summary: "A domain label of 3 or more hyphens, like secure-login-verify",
+    },
+    Signal {
+        name: "non-standard-port",
+        weight: 10,
+        summary: "A port other than the scheme's default",
+    },
+    Signal {
+        name: "insecure-scheme",
+        weight: 10,
+        summary: "Unencrypted http to a host other than this machine",
+    },
+];
+
+/// A signal that a URL triggered, and what triggered it.
+#[derive(Debug, PartialEq)]
+pub struct Hit {
+    pub signal: &'static str,
+    pub detail: String,
+}
+
+impl Hit {
+    fn new(signal: &'static str, detail: String) -> Hit {
+        Hit { signal, detail }
+    }
+
+    fn weight(&self) -> u32 {
+        SIGNALS
+            .iter()
+            .find(|signal| signal.name == self.signal)
+            .map_or(0, |signal| signal.weight)
+    }
+
+    fn to_value(&self) -> Value {
+        Value::Object(vec![
+            ("name".to_string(), Value::from(self.signal)),
+            ("weight".to_string(), Value::from(self.weight() as usize)),
+            ("detail".to_string(), Value::from(self.detail.as_str())),
+        ])
+    }
+}
+
+pub fn run(args: &[String]) {
+    let mut json_output = false;
+    let mut threshold = None;
+    let mut urls = Vec::new();
+    let mut rest = args.iter();
+    while let Some(arg) = rest.next() {
+        match arg.as_str() {
+            "-h" | "--help" => {
+                println!("{}", SCORE_HELP);
+                return;
+            }
+            "-j" | "--json" => json_output = true,
+            "--threshold" => {
+                let value = crate::option_value(&mut rest, arg);
+                threshold = Some(value.parse::<u32>().unwrap_or_else(|_| {
+                    eprintln!(
+                        "Invalid --threshold: {} (expected a score from 0 to 100)",
+                        value
+                    );
+                    std::process::exit(1);
+                }));
+            }
+            "--list-signals" => {
+                for signal in SIGNALS {
+                    println!("{:<20}+{:<4}{}", signal.name, signal.weight, signal.summary);
+                }
+                return;
+            }
+            other if other.starts_with('-') => {
+                eprintln!("Unknown option for score: {}", other);
+                std::process::exit(1);
+            }
+            other => urls.push(other),
+        }
+    }
+
+    let buffer;
+    if urls.is_empty() {
+        if io::stdin().is_terminal() {
+            eprintln!("Usage: kurl score [--json] [--threshold N] <URL>...");
+            std::process::exit(1);
+        }
+        buffer = crate::read_stdin();
+        urls = buffer
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .collect();
+    }
+
+    let mut out = io::stdout().lock();
+    let mut failed = false;
+    let mut flagged = false;
+    for input in urls {
+        let url = match Url::parse(input) {
+            Ok(url) => url,
+            Err(e) => {
+                eprintln!("Failed to parse URL {}: {}", input, e);
+                failed = true;
+                continue;
+            }
+        };
+        let hits = signals(&url);
+        let score = score(&hits);
+        flagged |= threshold.is_some_and(|threshold| score >= threshold);
+        let written = if json_output {
+            let signals = hits.iter().map(Hit::to_value).collect();
+            Value::Object(vec![
+                ("url".to_string(), Value::from(url.as_str())),
+                ("score".to_string(), Value::from(score as usize)),
+                ("signals".to_string(), Value::Array(signals)),
+            ])
+            .write(&mut out)
+            .and_then(|_| writeln!(out))
+        } else {
+            writeln!(out, "{}: {}", url, score).and_then(|_| {
+                hits.iter().try_for_each(|hit| {
+                    writeln!(out, "  +{:<3} {}: {}", hit.weight(), hit.signal, hit.detail)
+                })
+            })
+        };
+        if written.is_err() {
+            return;
+        }
+    }
+    if failed || flagged {
+        std::process::exit(1);
+    }
+}
+
+/// The sum of the weights of `hits`, up to `MAX_SCORE`.
+pub fn score(hits: &[Hit]) -> u32 {
+    hits.iter().map(Hit::weight).sum::<u32>().min(MAX_SCORE)
+}
+
+/// The signals `url` triggers, in the order of `SIGNALS`.
+pub fn signals(url: &Url) -> Vec<Hit> {
+    let mut hits = Vec::new();
+    if !url.username().is_empty() || url.password().is_some() {
+        let detail = if url.username().contains('.') {
+            format!(
+                "{} before the @ looks like a host, but the host is {}",
+                url.username(),
+                url.host_str().unwrap_or_default()
+            )
+        } else {
+            "has a username or password before the host".to_string()
+        };
+        hits.push(Hit::new("credentials", detail));
+    }
+    match url.host() {
+        Some(Host::Ipv4(ip)) => hits.push(Hit::new("ip-host", format!("host is {}", ip))),
+        Some(Host::Ipv6(ip)) => hits.push(Hit::new("ip-host", format!("host is [{}]", ip))),
+        _ => {}
+    }
+
+    if let (Some(Host::Domain(host)), Some(parts)) = (url.host(), psl::parts(url)) {
+        let registrable = psl::registrable_domain(url).unwrap_or(host);
+        if let Some(subdomain) = parts.subdomain {
+            let labels = subdomain.split('.').count();
+            if labels > MAX_SUBDOMAIN_LABELS {
+                hits.push(Hit::new(
+                    "deep-subdomain",
+                    format!("{} labels before {}", labels, registrable),
+                ));
+            }
+            let brand = BRANDS.iter().find(|&&brand| {
+                parts.domain != Some(brand)
+                    && subdomain.split(['.', '-', '_']).any(|token| token == brand)
+            });
+            if let Some(brand) = brand {
+                hits.push(Hit::new(
+                    "brand-in-subdomain",
+                    format!(
+                        "{} names {}, but the domain is {}",
+                        subdomain, brand, registrable
+                    ),
+                ));
+            }
+        }
+        let punycode: Vec<&str> = host
+            .split('.')
+            .filter(|label| label.starts_with("xn--"))
+            .collect();
+        if !punycode.is_empty() {
+            hits.push(Hit::new(
+                "punycode",
+                format!(
+                    "{} shows as {}",
+                    punycode.join(", "),
+                    idna::domain_to_unicode(host).0
+                ),
+            ));
+        }
+        for homograph in homograph::check(host) {
+            hits.push(Hit::new("homograph", homograph.to_string()));
+        }
+        let tld = host
+            .trim_end_matches('.')
+            .rsplit('.')
+            .next()
+            .unwrap_or_default();
+        if SUSPICIOUS_TLDS.contains(&tld) {
+            hits.push(Hit::new("suspicious-tld", format!(".{}", tld)));
+        }
+        // Counted in Unicode, where punycode's own hyphens are gone.
+        if let Some(domain) = parts.domain.map(|domain| idna::domain_to_unicode(domain).0) {
+            let hyphens = domain.matches('-').count();
+            if hyphens >= MIN_HYPHENS {
+                hits.push(Hit::new(
+                    "hyphenated-domain",
+                    format!("{} has {} hyphens", domain, hyphens),
+                ));
+            }
+        }
+    }
+
+    if let Some(port) = url.port() {
+        hits.push(Hit::new("non-standard-port", format!("port {}", port)));
+    }
+    if url.scheme() == "http" && !lint::is_local(url) {
+        hits.push(Hit::new(
+            "insecure-scheme",
+            "uses http, not https".to_string(),
+        ));
+    }
+    hits
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hits(url: &str) -> Vec<(&'static str, String)> {
+        signals(&Url::parse(url).unwrap())
+            .into_iter()
+            .map(|hit| (hit.signal, hit.detail))
+            .collect()
+    }
+
+    #[test]
+    fn test_signals() {
+        assert_eq!(
+            hits("https://paypal.com.secure.login.account.evil.example/"),
+            [
+                ("deep-subdomain", "5 labels before evil.example".to_string()),
+                (
+                    "brand-in-subdomain",
+                    "paypal.com.secure.login.account names paypal, but the domain is evil.example"
+                        .to_string()
+                ),
+            ]
+        );
+        assert_eq!(
+            hits("http://www.paypal.com@192.168.0.1:8080/"),
+            [
+                (
+                    "credentials",
+                    "www.paypal.com before the @ looks like a host, but the host is 192.168.0.1"
+                        .to_string()
+                ),
+                ("ip-host", "host is 192.168.0.1".to_string()),
+                ("non-standard-port", "port 8080".to_string()),
+                ("insecure-scheme", "uses http, not https".to_string()),
+            ]
+        );
+        assert_eq!(
+            hits("https://secure-login-verify-account.zip/")
+                .iter()
+                .map(|(name, _)| *name)
+                .collect::<Vec<_>>(),
+            ["suspicious-tld", "hyphenated-domain"]
+        );
+        let names: Vec<&str> = hits("https://xn--pypal-4ve.com/")
+            .iter()
+            .map(|(name, _)| *name)
+            .collect();
+        assert_eq!(names, ["punycode", "homograph"]);
+
+        assert_eq!(hits("https://www.paypal.com/signin"), []);
+        assert_eq!(hits("https://login.apple.com/"), []);
+        assert_eq!(
+            hits("http://localhost:3000/"),
+            [("non-standard-port", "port 3000".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_score() {
+        let url = Url::parse("http://user:pw@10.0.0.1:8080/").unwrap();
+        assert_eq!(score(&signals(&url)), 65);
+        let url = Url::parse("http://a.b.c.paypal.login.xn--pypal-4ve.top:81/").unwrap();
+        assert_eq!(score(&signals(&url)), MAX_SCORE);
+        assert_eq!(score(&[]), 0);
+        assert_eq!(
+            Hit::new("ip-host", "host is 10.0.0.1".to_string())
+                .to_value()
+                .to_json(),
+            r#"{"name":"ip-host","weight":20,"detail":"host is 10.0.0.1"}"#
+        );
+    }
+}
@@ -0,0 +1,364 @@
+use std::io::{self, BufRead, Write};
+use url::Url;
+
+use crate::assertion;
+use crate::json::{self, Value};
+
+const SERVE_HELP: &str = r#"kurl serve - answer URL requests from a long-running process
+
+USAGE:
+    kurl serve --stdio
+    kurl serve --socket <PATH>
+
+OPTIONS:
+        --stdio         Read requests from stdin and write responses to stdout
+        --socket <PATH> Listen on a Unix domain socket, or on Windows the
+                        named pipe \\.\pipe\PATH, serving clients
+                        concurrently
+    -h, --help          Show this help message
+
+Each request is one line of JSON, {"op": ..., "url": ..., "options": {...}},
+with an optional "id" that is echoed back. Each response is one line of JSON
+holding either a "result" or an "error". Socket clients speak the same
+protocol, one connection per client.
+
+OPERATIONS:
+    parse               URL components, as printed by --json
+                        options: "base" to resolve a relative URL against
+    assert              Compare components to options.expect, as kurl assert does
+    capabilities        What this build supports, as kurl capabilities --json
+
+EXAMPLES:
+    echo '{"id":1,"op":"parse","url":"https://example.com/?q=1"}' | kurl serve --stdio
+    kurl serve --socket /tmp/kurl.sock &
+"#;
+
+pub fn run(args: &[String]) {
+    let mut stdio = false;
+    let mut socket = None;
+
+    let mut rest = args.iter();
+    while let Some(arg) = rest.next() {
+        match arg.as_str() {
+            "-h" | "--help" => {
+                println!("{}", SERVE_HELP);
+                return;
+            }
+            "--stdio" => stdio = true,
+            "--socket" => socket = Some(crate::option_value(&mut rest, arg)),
+            other => {
+                eprintln!("Unknown option for serve: {}", other);
+                std::process::exit(1);
+            }
+        }
+    }
+
+    match (stdio, socket) {
+        (true, None) => {
+            let stdin = io::stdin().lock();
+            let mut out = io::stdout().lock();
+            let _ = serve(stdin, &mut out);
+        }
+        (false, Some(path)) => listen(path),
+        _ => {
+            eprintln!("Usage: kurl serve --stdio | --socket <PATH>");
+            std::process::exit(1);
+        }
+    }
+}
+
+#[cfg(unix)]
+fn listen(path: &str) {
+    use std::os::unix::net::{UnixListener, UnixStream};
+
+    // A socket file left behind by a server that exited without cleaning up
+    // refuses connections; anything else at the path is left alone.
+    if UnixStream::connect(path).is_err()
+        && std::fs::metadata(path).is_ok_and(|m| {
+            use std::os::unix::fs::FileTypeExt;
+            m.file_type().is_socket()
+        })
+    {
+        let _ = std::fs::remove_file(path);
+    }
+
+    let listener = UnixListener::bind(path).unwrap_or_else(|e| {
+        eprintln!("Failed to listen on {}: {}", path, e);
+        std::process::exit(1);
+    });
+    for stream in listener.incoming() {
+        let Ok(mut stream) = stream else {
+            continue;
+        };
+        std::thread::spawn(move || {
+            if let Ok(reader) = stream.try_clone() {
+                let _ = serve(io::BufReader::new(reader), &mut stream);
+            }
+        });
+    }
+}
+
+/// Serves clients of the named pipe `path`, taken as a name under
+/// `\\.\pipe\` unless it is a full pipe path already. Each client gets a
+/// pipe instance of its own, as each Unix socket client gets a stream.
+#[cfg(windows)]
+fn listen(path: &str) {
+    use std::ffi::{OsStr, c_void};
+    use std::os::windows::ffi::OsStrExt;
+    use std::os::windows::io::{FromRawHandle, RawHandle};
+
+    // std has no named pipe servers, so these few kernel32 calls stand in.
+    const PIPE_ACCESS_DUPLEX: u32 = 0x0000_0003;
+    const PIPE_REJECT_REMOTE_CLIENTS: u32 = 0x0000_0008;
+    const PIPE_UNLIMITED_INSTANCES: u32 = 255;
+    const ERROR_PIPE_CONNECTED: i32 = 535;
+    #[link(name = "kernel32")]
+    unsafe extern "system" {
+        fn CreateNamedPipeW(
+            name: *const u16,
+            open_mode: u32,
+            pipe_mode: u32,
+            max_instances: u32,
+            out_buffer_size: u32,
+            in_buffer_size: u32,
+            default_timeout: u32,
+            security_attributes: *mut c_void,
+        ) -> RawHandle;
+        fn ConnectNamedPipe(pipe: RawHandle, overlapped: *mut c_void) -> i32;
+    }
+
+    let name = if path.starts_with(r"\\") {
+        path.to_string()
+    } else {
+        format!(r"\\.\pipe\{}", path)
+    };
+    let wide: Vec<u16> = OsStr::new(&name).encode_wide().chain([0]).collect();
+    loop {
+        // Byte mode, blocking, local clients only.
+        let pipe = unsafe {
+            CreateNamedPipeW(
+                wide.as_ptr(),
+                PIPE_ACCESS_DUPLEX,
+                PIPE_REJECT_REMOTE_CLIENTS,
+                PIPE_UNLIMITED_INSTANCES,
+                4096,
+                4096,
+                0,
+                std::ptr::null_mut(),
+            )
+        };
+        if pipe as isize == -1 {
+            eprintln!(
+                "Failed to listen on {}: {}",
+                name,
+                io::Error::last_os_error()
+            );
+            std::process::exit(1);
+        }
+        // A client that connected between the two calls counts as well.
+        let connected = unsafe { ConnectNamedPipe(pipe, std::ptr::null_mut()) } != 0
+            || io::Error::last_os_error().raw_os_error() == Some(ERROR_PIPE_CONNECTED);
+        // SAFETY: `pipe` is a valid handle that nothing else owns, so the
+        // file closes it when dropped.
+        let mut stream = unsafe { std::fs::File::from_raw_handle(pipe) };
+        if !connected {
+            continue;
+        }
+        std::thread::spawn(move || {
+            if let Ok(reader) = stream.try_clone() {
+                let _ = serve(io::BufReader::new(reader), &mut stream);
+            }
+        });
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+fn listen(_path: &str) {
+    eprintln!("kurl serve --socket needs Unix sockets or Windows named pipes; use --stdio");
+    std::process::exit(1);
+}
+
+/// Answers each request line as it arrives, flushing so that the client can
+/// wait for one response before sending the next request.
+fn serve<R: BufRead, W: Write>(reader: R, writer: &mut W) -> io::Result<()> {
+    for line in reader.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        respond(&line).write(writer)?;
+        writeln!(writer)?;
+        writer.flush()?;
+    }
+    Ok(())
+}
+
+/// Handles one request line, returning the response object.
+pub fn respond(line: &str) -> Value {
+    let (id, result) = match json::parse(line) {
+        Ok(request) => (request.get("id").cloned(), handle(&request)),
+        Err(e) => (None, Err(format!("invalid request: {}", e))),
+    };
+
+    let mut fields = Vec::new();
+    if let Some(id) = id {
+        fields.push(("id".to_string(), id));
+    }
+    match result {
+        Ok(result) => fields.push(("result".to_string(), result)),
+        Err(error) => fields.push(("error".to_string(), Value::from(error))),
+    }
+    Value::Object(fields)
+}
+
+fn handle(request: &Value) -> Result<Value, String> {
+    let empty = Value::Object(Vec::new());
+    let options = request.get("options").unwrap_or(&empty);
+
+    match string_field(request, "op")? {
+        "parse" => Ok(crate::components(
+            &request_url(request, options)?,
+            crate::OUTPUT_VERSION,
+            crate::query::Syntax::default(),
+        )),
+        "assert" => {
+            let expected = match options.get("expect") {
+                Some(expected @ Value::Object(_)) => expected,
+                _ => return Err("options.expect must be a JSON object".to_string()),
+            };
+            let url = request_url(request, options)?;
+            let mismatches = assertion::compare(
+                expected,
+                &crate::components(&url, crate::OUTPUT_VERSION, crate::query::Syntax::default()),
+            );
+            Ok(Value::Object(vec![
+                ("ok".to_string(), Value::from(mismatches.is_empty())),
+                (
+                    "mismatches".to_string(),
+                    Value::Array(
+                        mismatches
+                            .iter()
+                            .map(ToString::to_string)
+                            .map(Value::from)
+                            .collect(),
+                    ),
+                ),
+            ]))
+        }
+        "capabilities" => Ok(crate::capabilities::capabilities()),
+        other => Err(format!("unknown op: {}", other)),
+    }
+}
+
+fn string_field<'a>(value: &'a Value, key: &str) -> Result<&'a str, String> {
+    match value.get(key) {
+        Some(Value::String(s)) => Ok(s),
+        Some(_) => Err(format!("{} must be a string", key)),
+        None => Err(format!("missing {}", key)),
+    }
+}
+
+fn request_url(request: &Value, options: &Value) -> Result<Url, String> {
+    let input = string_field(request, "url")?;
+    let result = match options.get("base") {
+        Some(_) => {
+            let base = Url::parse(string_field(options, "base")?)
+                .map_err(|e| format!("failed to parse base URL: {}", e))?;
+            base.join(input)
+        }
+        None => Url::parse(input),
+    };
+    result.map_err(|e| format!("failed to parse URL: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn respond_json(line: &str) -> String {
+        respond(line).to_json()
+    }
+
+    #[test]
+    fn test_parse_request() {
+        assert_eq!(
+            respond_json(r#"{"id":7,"op":"parse","url":"HTTPS://Example.com:443/a"}"#),
+            r#"{"id":7,"result":{"scheme":"https","host":"example.com","path":"/a","site":"https://example.com","registrable_domain":"example.com","public_suffix":"com","domain":"example","suffix":"com","safe_url":"https://example.com/a"}}"#
+        );
+        assert_eq!(
+            respond_json(
+                r#"{"op":"parse","url":"../b?x=1","options":{"base":"http://a.test/c/d"}}"#
+            ),
+            r#"{"result":{"scheme":"http","host":"a.test","path":"/b","site":"http://a.test","registrable_domain":"a.test","public_suffix":"test","domain":"a","suffix":"test","safe_url":"http://a.test/b?x=1","query":{"x":"1"}}}"#
+        );
+    }
+
+    #[test]
+    fn test_assert_request() {
+        assert_eq!(
+            respond_json(
+                r#"{"op":"assert","url":"https://example.com/","options":{"expect":{"port":8080}}}"#
+            ),
+            r#"{"result":{"ok":false,"mismatches":["port: expected 8080, got nothing"]}}"#
+        );
+    }
+
+    #[test]
+    fn test_errors_keep_the_id() {
+        assert_eq!(
+            respond_json(r#"{"id":"a","op":"parse","url":"not a url"}"#),
+            r#"{"id":"a","error":"failed to parse URL: relative URL without a base"}"#
+        );
+        assert_eq!(
+            respond_json(r#"{"id":1,"op":"fly"}"#),
+            r#"{"id":1,"error":"unknown op: fly"}"#
+        );
+        assert!(respond_json("{").starts_with(r#"{"error":"invalid request: "#));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_socket_clients() {
+        use std::io::BufReader;
+        use std::os::unix::net::UnixStream;
+
+        let path = std::env::temp_dir().join(format!("kurl-serve-{}.sock", std::process::id()));
+        let path_str = path.to_str().unwrap().to_string();
+        std::thread::spawn(move || listen(&path_str));
+
+        let mut client = (0..100)
+            .find_map(|_| {
+                std::thread::sleep(std::time::Duration::from_millis(10));
+                UnixStream::connect(&path).ok()
+            })
+            .unwrap();
+        let mut second = UnixStream::connect(&path).unwrap();
+
+        writeln!(
+            second,
+            r#"{{"id":2,"op":"parse","url":"https://b.example/"}}"#
+        )
+        .unwrap();
+        writeln!(client, r#"{{"id":1,"op":"fly"}}"#).unwrap();
+        let mut response = String::new();
+        BufReader::new(&second).read_line(&mut response).unwrap();
+        assert!(response.starts_with(r#"{"id":2,"result":{"scheme":"https""#));
+        response.clear();
+        BufReader::new(&client).read_line(&mut response).unwrap();
+        assert_eq!(response, "{\"id\":1,\"error\":\"unknown op: fly\"}\n");
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_serve_answers_each_line() {
+        let input = "{\"op\":\"capabilities\"}\n\n{\"op\":\"parse\"}\n";
+        let mut output = Vec::new();
+        serve(input.as_bytes(), &mut output).unwrap();
+
+        let output = String::from_utf8(output).unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with(r#"{"result":{"version":"#));
+        assert_eq!(lines[1], r#"{"error":"missing url"}"#);
+    }
+}
@@ -0,0 +1,237 @@
+use std::io::{self, IsTerminal};
+use url::Url;
+
+use crate::OutputOptions;
+use crate::condition::Condition;
+use crate::edit;
+use crate::transform::{self, TransformOptions};
+
+const SET_HELP: &str = r#"kurl set - change components of URLs
+
+USAGE:
+    kurl set [OPTIONS] <NAME>=<VALUE>... [<URL>...]
+    <URLs> | kurl set [OPTIONS] <NAME>=<VALUE>...
+    kurl set [OPTIONS] --when <CONDITION> <NAME>=<VALUE>... [--when ...] [<URL>...]
+
+OPTIONS:
+        --when <CONDITION>
+                        Apply the assignments after it, up to the next --when,
+                        only to URLs matching CONDITION
+        --set-<NAME> <VALUE>
+                        Same as NAME=VALUE
+    -j, --json          Print each result's components as JSON
+        --dry-run       Report how many URLs would change without printing them
+        --show-diff     Print a diff of the URLs that would change
+        --verify-idempotent
+                        Fail, printing nothing, unless a second pass leaves
+                        every result unchanged
+    -h, --help          Show this help message
+
+NAME is one of scheme, user, password, host, port, path, query, or fragment.
+Values are percent-encoded as their component requires, and an empty value
+removes an optional component, as in `fragment=`. Assignments apply in order,
+to each URL argument or, with none, to each line of stdin.
+
+A CONDITION compares a component with `==`, `!=`, `=~` (matches a regex), or
+`!~`, as in `host == "old.example.com"` or `path =~ '^/v1/'`, and comparisons
+combine with `&&`, `||`, and parentheses. `query.NAME` tests a parameter, and
+missing components compare as empty. Conditions test the input URL, so one
+group's changes never decide whether another applies.
+
+EXAMPLES:
+    kurl set scheme=https host=api.example.com port=443 http://old.example.com:8080/v1
+    cat urls.txt | kurl set fragment= query=
+    kurl set --when 'host == "old.example.com"' --set-host new.example.com --set-scheme https \
+        --when 'path =~ "^/v1/"' --set-port 8443 < urls.txt
+"#;
+
+/// Assignments that apply to the URLs matching `when`, or to every URL.
+struct Group<'a> {
+    when: Option<Condition>,
+    assignments: Vec<(&'a str, &'a str)>,
+}
+
+pub fn run(args: &[String]) {
+    let mut groups = vec![Group {
+        when: None,
+        assignments: Vec::new(),
+    }];
+    let mut urls = Vec::new();
+    let mut options = TransformOptions::default();
+    let mut output = OutputOptions::default();
+
+    let mut rest = args.iter();
+    while let Some(arg) = rest.next() {
+        match arg.as_str() {
+            "-h" | "--help" => {
+                println!("{}", SET_HELP);
+                return;
+            }
+            "--when" => {
+                let Some(text) = rest.next() else {
+                    eprintln!("Missing value for --when");
+                    std::process::exit(1);
+                };
+                let when = Condition::parse(text).unwrap_or_else(|e| {
+                    eprintln!("Invalid --when condition: {}", e);
+                    std::process::exit(1);
+                });
+                groups.push(Group {
+                    when: Some(when),
+                    assignments: Vec::new(),
+                });
+            }
+            other if options.accept(other) => {}
+            other if output.accept(other, &mut rest) => {}
+            other
+                if other
+                    .strip_prefix("--set-")
+                    .is_some_and(|name| edit::SETTABLE.contains(&name)) =>
+            {
+                let Some(value) = rest.next() else {
+                    eprintln!("Missing value for {}", other);
+                    std::process::exit(1);
+                };
+                let group = groups.last_mut().expect("there is always a group");
+                group.assignments.push((&other["--set-".len()..], value));
+            }
+            other => match assignment(other) {
+                Some(assignment) => {
+                    let group = groups.last_mut().expect("there is always a group");
+                    group.assignments.push(assignment);
+                }
+                None if other.starts_with('-') => {
+                    eprintln!("Unknown option for set: {}", other);
+                    std::process::exit(1);
+                }
+                None if other
+                    .split_once('=')
+                    .is_some_and(|(name, _)| name.bytes().all(|b| b.is_ascii_alphabetic())) =>
+                {
+                    eprintln!(
+                        "Unknown component in {} (expected {})",
+                        other,
+                        edit::SETTABLE.join(", ")
+                    );
+                    std::process::exit(1);
+                }
+                None => urls.push(other),
+            },
+        }
+    }
+
+    if groups.iter().all(|group| group.assignments.is_empty()) {
+        eprintln!("Usage: kurl set <NAME>=<VALUE>... [<URL>...]");
+        eprintln!("Components are: {}", edit::SETTABLE.join(", "));
+        std::process::exit(1);
+    }
+    if groups[1..].iter().any(|group| group.assignments.is_empty()) {
+        eprintln!("Every --when needs assignments after it");
+        std::process::exit(1);
+    }
+
+    let mut buffer = String::new();
+    if urls.is_empty() {
+        if io::stdin().is_terminal() {
+            eprintln!("Usage: kurl set <NAME>=<VALUE>... [<URL>...]");
+            std::process::exit(1);
+        }
+        buffer = crate::read_stdin();
+        urls.push("-");
+    }
+    let inputs = crate::batch_inputs(&urls, &buffer);
+
+    transform::run(&inputs, &options, &output, |url| apply_groups(url, &groups));
+}
+
+/// Splits a `NAME=VALUE` argument naming a settable component. URLs and
+/// anything else return `None`, even if they contain `=`.
+fn assignment(arg: &str) -> Option<(&str, &str)> {
+    let (name, value) = arg.split_once('=')?;
+    edit::SETTABLE.contains(&name).then_some((name, value))
+}
+
+/// Applies the groups whose conditions `url` matches, in order.
+fn apply_groups(url: &Url, groups: &[Group]) -> Result<Url, String> {
+    groups
+        .iter()
+        .filter(|group| group.when.as_ref().is_none_or(|when| when.matches(url)))
+        .try_fold(url.clone(), |result, group| {
+            apply(&result, &group.assignments)
+        })
+}
+
+fn apply(url: &Url, assignments: &[(&str, &str)]) -> Result<Url, String> {
+    let mut url = url.clone();
+    for (name, value) in assignments {
+        edit::set(&mut url, name, value)?;
+    }
+    Ok(url)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_assignments_and_urls() {
+        assert_eq!(assignment("host=a.example"), Some(("host", "a.example")));
+        assert_eq!(assignment("fragment="), Some(("fragment", "")));
+        assert_eq!(assignment("query=a=b"), Some(("query", "a=b")));
+        assert_eq!(assignment("https://a.example/?host=b"), None);
+        assert_eq!(assignment("colour=red"), None);
+    }
+
+    #[test]
+    fn test_apply_in_order() {
+        let url = Url::parse("http://old.example.com:8080/v1#top").unwrap();
+        let assignments = [
+            ("scheme", "https"),
+            ("host", "api.example.com"),
+            ("port", "443"),
+            ("fragment", ""),
+        ];
+        assert_eq!(
+            apply(&url, &assignments).unwrap().as_str(),
+            "https://api.example.com/v1"
+        );
+
+        let mailto = Url::parse("mailto:a@example.com").unwrap();
+        assert!(apply(&mailto, &[("host", "b.example.com")]).is_err());
+    }
+
+    #[test]
+    fn test_when_groups() {
+        let groups = [
+            Group {
+                when: None,
+                assignments: vec![("fragment", "")],
+            },
+            Group {
+                when: Some(Condition::parse(r#"host == "old.example.com""#).unwrap()),
+                assignments: vec![("host", "new.example.com"), ("scheme", "https")],
+            },
+            Group {
+                when: Some(Condition::parse("host == new.example.com").unwrap()),
+                assignments: vec![("path", "/moved")],
+            },
+        ];
+        let apply = |url: &str| {
+            apply_groups(&Url::parse(url).unwrap(), &groups)
+                .unwrap()
+                .to_string()
+        };
+        assert_eq!(
+            apply("http://old.example.com/a#top"),
+            "https://new.example.com/a"
+        );
+        assert_eq!(
+            apply("https://new.example.com/a"),
+            "https://new.example.com/moved"
+        );
+        assert_eq!(
+            apply("https://other.example/a#x"),
+            "https://other.example/a"
+        );
+    }
+}
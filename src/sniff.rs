@@ -0,0 +1,223 @@
+use url::Url;
+
+use crate::accesslog;
+use crate::json::{self, Value};
+
+/// How many non-blank lines `detect` looks at.
+const SAMPLE_LINES: usize = 20;
+/// The percentage of sampled lines that must be requests for `detect` to
+/// call the text an access log, which may hold malformed probe lines.
+const ACCESSLOG_PERCENT: usize = 80;
+
+/// Guesses the `--input` type of `text` from its first lines: `html` or
+/// `bookmarks` for markup, `har` for a HAR archive, `ndjson` for lines of
+/// JSON objects, `url` for one bare URL per line, `accesslog` for Apache or
+/// nginx logs (most of whose lines are requests), and `csv` for lines with a consistent number of fields. Anything
+/// else is read as a URL list, so its errors point at the offending lines.
+pub fn detect(text: &str) -> &'static str {
+    let trimmed = text.trim_start();
+    if trimmed.starts_with('<') {
+        let head: String = trimmed.chars().take(1024).collect();
+        return if head.to_ascii_uppercase().contains("NETSCAPE-BOOKMARK-FILE") {
+            "bookmarks"
+        } else {
+            "html"
+        };
+    }
+
+    let sample: Vec<&str> = text
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .take(SAMPLE_LINES)
+        .collect();
+    if trimmed.starts_with('{') {
+        if json::parse(text).is_ok_and(|value| har_entries(&value).is_some()) {
+            return "har";
+        }
+        if sample.iter().all(|line| line.starts_with('{')) {
+            return "ndjson";
+        }
+    }
+    if sample
+        .iter()
+        .all(|line| !line.contains(char::is_whitespace) && is_url(line))
+    {
+        return "url";
+    }
+    let requests = sample
+        .iter()
+        .filter(|line| accesslog::parse_line(line).is_some())
+        .count();
+    if requests > 0 && requests * 100 >= sample.len() * ACCESSLOG_PERCENT {
+        return "accesslog";
+    }
+    let widths: Vec<usize> = sample.iter().map(|line| csv_fields(line).len()).collect();
+    if widths.first().is_some_and(|&width| width >= 2) && widths.windows(2).all(|w| w[0] == w[1]) {
+        return "csv";
+    }
+    "url"
+}
+
+/// Lists the URLs in `text` of an input type that has no reader of its own,
+/// with the line (or, for HAR, the entry) each came from.
+pub fn urls(input: &str, text: &str) -> Result<Vec<(usize, String)>, String> {
+    let mut urls = Vec::new();
+    match input {
+        "ndjson" => {
+            for (i, line) in text.lines().enumerate() {
+                if line.trim().is_empty() {
+                    continue;
+                }
+                let value = json::parse(line).map_err(|e| format!("line {}: {}", i + 1, e))?;
+                let mut found = Vec::new();
+                strings(&value, &mut found);
+                urls.extend(found.into_iter().map(|url| (i + 1, url)));
+            }
+        }
+        "har" => {
+            let value = json::parse(text)?;
+            let entries = har_entries(&value).ok_or("not a HAR archive (no log.entries)")?;
+            for (i, entry) in entries.iter().enumerate() {
+                match entry.get("request").and_then(|request| request.get("url")) {
+                    Some(Value::String(url)) => urls.push((i + 1, url.clone())),
+                    _ => eprintln!("Entry {}: no request.url", i + 1),
+                }
+            }
+        }
+        "csv" => {
+            for (i, line) in text.lines().enumerate() {
+                urls.extend(
+                    csv_fields(line)
+                        .into_iter()
+                        .filter(|field| is_url(field))
+                        .map(|field| (i + 1, field.trim().to_string())),
+                );
+            }
+        }
+        other => unreachable!("no URL reader for {}", other),
+    }
+    Ok(urls)
+}
+
+fn har_entries(value: &Value) -> Option<&Vec<Value>> {
+    match value.get("log")?.get("entries")? {
+        Value::Array(entries) => Some(entries),
+        _ => None,
+    }
+}
+
+/// Whether `text` is an absolute URL with a host, which is what the fields of
+/// structured input must be to count as URLs.
+fn is_url(text: &str) -> bool {
+    Url::parse(text.trim()).is_ok_and(|url| url.has_host())
+}
+
+/// Collects the strings in `value` that are URLs, in document order.
+fn strings(value: &Value, found: &mut Vec<String>) {
+    match value {
+        Value::String(text) if is_url(text) => found.push(text.clone()),
+        Value::Array(items) => items.iter().for_each(|item| strings(item, found)),
+        Value::Object(fields) => fields.iter().for_each(|(_, field)| strings(field, found)),
+        _ => {}
+    }
+}
+
+/// Splits one CSV record on commas outside double quotes, unquoting fields.
+/// Quoted fields that span lines aren't supported.
+fn csv_fields(line: &str) -> Vec<String> {
+    let mut fields = vec![String::new()];
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        let field = fields.last_mut().expect("there is always a field");
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(String::new()),
+            c => field.push(c),
+        }
+    }
+    fields
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect() {
+        assert_eq!(detect("https://a.com/\n\nhttps://b.com/x\n"), "url");
+        assert_eq!(detect("  <html><a href=\"/x\">x</a>"), "html");
+        assert_eq!(
+            detect("<!DOCTYPE NETSCAPE-Bookmark-file-1>\n<DL>"),
+            "bookmarks"
+        );
+        assert_eq!(
+            detect("{\"log\": {\"entries\": [{\"request\": {\"url\": \"https://a.com/\"}}]}}"),
+            "har"
+        );
+        assert_eq!(
+            detect("{\"u\": \"https://a.com/\"}\n{\"u\": \"https://b.com/\"}\n"),
+            "ndjson"
+        );
+        assert_eq!(
+            detect(
+                "127.0.0.1 - - [10/Oct/2024:13:55:36 +0000] \"GET /a?b=c HTTP/1.1\" 200 512 \"-\" \"curl\"\n"
+            ),
+            "accesslog"
+        );
+        let request = "10.0.0.1 - - [10/Oct/2024:13:55:36 +0000] \"GET / HTTP/1.1\" 200 5\n";
+        let probe = "10.0.0.2 - - [10/Oct/2024:13:55:37 +0000] \"-\" 400 0\n";
+        assert_eq!(
+            detect(&format!(
+                "{}{}{}",
+                request.repeat(2),
+                probe,
+                request.repeat(2)
+            )),
+            "accesslog"
+        );
+        assert_eq!(detect(&format!("{}{}", request, probe)), "url");
+        assert_eq!(
+            detect("title,url\n\"Docs, v2\",https://a.com/docs\nHome,https://a.com/\n"),
+            "csv"
+        );
+        assert_eq!(detect("not a url\nhttps://a.com/\n"), "url");
+    }
+
+    #[test]
+    fn test_urls() {
+        assert_eq!(
+            urls(
+                "ndjson",
+                "{\"u\": \"https://a.com/\", \"n\": [\"x\", \"https://b.com/\"]}\n\n{\"u\": \"mailto:a@b.c\"}\n"
+            ),
+            Ok(vec![
+                (1, "https://a.com/".to_string()),
+                (1, "https://b.com/".to_string())
+            ])
+        );
+        assert!(urls("ndjson", "{\"u\": 1\n").is_err());
+        assert_eq!(
+            urls(
+                "har",
+                "{\"log\": {\"entries\": [{\"request\": {\"url\": \"https://a.com/x\"}}, {}]}}"
+            ),
+            Ok(vec![(1, "https://a.com/x".to_string())])
+        );
+        assert_eq!(
+            urls(
+                "csv",
+                "title,url\n\"Docs, \"\"v2\"\"\",https://a.com/docs\nHome,https://a.com/\n"
+            ),
+            Ok(vec![
+                (2, "https://a.com/docs".to_string()),
+                (3, "https://a.com/".to_string())
+            ])
+        );
+    }
+}
@@ -0,0 +1,81 @@
+use url::Url;
+
+use crate::json::Value;
+use crate::query;
+
+/// Size measures of a URL, for finding pathological ones in large crawls.
+#[derive(Debug, PartialEq)]
+pub struct Stats {
+    /// Bytes in the whole URL, as printed.
+    pub length: usize,
+    /// Non-empty path segments.
+    pub path_depth: usize,
+    pub query_params: usize,
+    /// Characters in the longest decoded query value.
+    pub longest_value: usize,
+    /// Bytes written as `%XX` escapes anywhere in the URL.
+    pub encoded_bytes: usize,
+}
+
+impl Stats {
+    pub fn of(url: &Url, syntax: query::Syntax) -> Stats {
+        let pairs = query::pairs(url, syntax);
+        let escapes = url
+            .as_str()
+            .as_bytes()
+            .windows(3)
+            .filter(|w| w[0] == b'%' && w[1].is_ascii_hexdigit() && w[2].is_ascii_hexdigit())
+            .count();
+        Stats {
+            length: url.as_str().len(),
+            path_depth: url
+                .path_segments()
+                .map_or(0, |segments| segments.filter(|s| !s.is_empty()).count()),
+            query_params: pairs.len(),
+            longest_value: pairs
+                .iter()
+                .map(|(_, value)| value.chars().count())
+                .max()
+                .unwrap_or(0),
+            encoded_bytes: escapes,
+        }
+    }
+
+    pub fn to_value(&self) -> Value {
+        Value::Object(vec![
+            ("length".to_string(), Value::from(self.length)),
+            ("path_depth".to_string(), Value::from(self.path_depth)),
+            ("query_params".to_string(), Value::from(self.query_params)),
+            ("longest_value".to_string(), Value::from(self.longest_value)),
+            ("encoded_bytes".to_string(), Value::from(self.encoded_bytes)),
+        ])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stats(url: &str) -> Stats {
+        Stats::of(&Url::parse(url).unwrap(), query::Syntax::default())
+    }
+
+    #[test]
+    fn test_stats() {
+        assert_eq!(
+            stats("https://example.com/a/b//c/?q=caf%C3%A9+au+lait&page=2&q"),
+            Stats {
+                length: 56,
+                path_depth: 3,
+                query_params: 3,
+                longest_value: 12,
+                encoded_bytes: 2,
+            }
+        );
+        assert_eq!(
+            stats("https://example.com").to_value().to_json(),
+            r#"{"length":20,"path_depth":0,"query_params":0,"longest_value":0,"encoded_bytes":0}"#
+        );
+        assert_eq!(stats("mailto:a@example.com").path_depth, 0);
+    }
+}
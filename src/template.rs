@@ -0,0 +1,288 @@
+use std::io::{self, IsTerminal, Write};
+use url::Url;
+
+use crate::edit;
+use crate::json::Value;
+
+const MATCH_HELP: &str = r#"kurl match - extract template variables from URLs
+
+USAGE:
+    kurl match [OPTIONS] <TEMPLATE> <URL>...
+    <URLs> | kurl match [OPTIONS] <TEMPLATE>
+
+OPTIONS:
+    -h, --help          Show this help message
+
+TEMPLATE is a URL with `{name}` variables, which match a non-empty value
+without `/`, `?`, `#`, or `&`, and `{+name}` variables, which match anything,
+as in RFC 6570. A variable used twice must match the same value both times.
+The template is parsed like any URL, so host case and default ports don't
+matter.
+
+Prints the percent-decoded variables of each matching URL as a JSON object,
+one line per URL, and exits 1 if any URL doesn't match.
+
+EXAMPLES:
+    kurl match 'https://api.example.com/users/{id}/posts/{post}' https://api.example.com/users/42/posts/7
+    cat access.txt | kurl match 'https://{tenant}.example.com/{+path}'
+"#;
+
+/// A part of a parsed template.
+#[derive(Debug, PartialEq)]
+enum Part {
+    Literal(String),
+    Variable { name: String, reserved: bool },
+}
+
+pub fn run(args: &[String]) {
+    let mut positionals = Vec::new();
+    for arg in args {
+        match arg.as_str() {
+            "-h" | "--help" => {
+                println!("{}", MATCH_HELP);
+                return;
+            }
+            other if other.starts_with('-') => {
+                eprintln!("Unknown option for match: {}", other);
+                std::process::exit(1);
+            }
+            other => positionals.push(other),
+        }
+    }
+
+    let Some((template, mut urls)) = positionals.split_first().map(|(t, u)| (*t, u.to_vec()))
+    else {
+        eprintln!("Usage: kurl match <TEMPLATE> <URL>...");
+        std::process::exit(1);
+    };
+    let parts = parse(template).unwrap_or_else(|e| {
+        eprintln!("Invalid template {}: {}", template, e);
+        std::process::exit(1);
+    });
+
+    let buffer;
+    if urls.is_empty() {
+        if io::stdin().is_terminal() {
+            eprintln!("Usage: kurl match <TEMPLATE> <URL>...");
+            eprintln!("   or: <URLs> | kurl match <TEMPLATE>");
+            std::process::exit(1);
+        }
+        buffer = crate::read_stdin();
+        urls = buffer
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .collect();
+    }
+
+    let mut out = io::stdout().lock();
+    let mut matched_all = true;
+    for input in urls {
+        let url = match Url::parse(input) {
+            Ok(url) => url,
+            Err(e) => {
+                eprintln!("Failed to parse URL {}: {}", input, e);
+                matched_all = false;
+                continue;
+            }
+        };
+        match captures(&parts, url.as_str()) {
+            Some(variables) => {
+                let object = Value::Object(
+                    variables
+                        .into_iter()
+                        .map(|(name, value)| (name, Value::from(value.as_str())))
+                        .collect(),
+                );
+                if object.write(&mut out).and_then(|_| writeln!(out)).is_err() {
+                    return;
+                }
+            }
+            None => matched_all = false,
+        }
+    }
+    if !matched_all {
+        std::process::exit(1);
+    }
+}
+
+/// Splits a template into literals and variables. Variables are swapped for
+/// placeholders while the template is parsed as a URL, so the literals come
+/// out serialized the way matching URLs are.
+fn parse(template: &str) -> Result<Vec<Part>, String> {
+    let mut variables = Vec::new();
+    let mut placeholders = String::new();
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        let end = start + rest[start..].find('}').ok_or("unclosed {")?;
+        let spec = &rest[start + 1..end];
+        let (name, reserved) = match spec.strip_prefix('+') {
+            Some(name) => (name, true),
+            None => (spec, false),
+        };
+        if name.is_empty()
+            || !name
+                .bytes()
+                .all(|b| b.is_ascii_alphanumeric() || b == b'_' || b == b'.')
+        {
+            return Err(format!("invalid variable {{{}}}", spec));
+        }
+        placeholders.push_str(&rest[..start]);
+        placeholders.push_str(&format!("kurlvar{}x", variables.len()));
+        variables.push((name.to_string(), reserved));
+        rest = &rest[end + 1..];
+    }
+    if rest.contains('}') {
+        return Err("unmatched }".to_string());
+    }
+    placeholders.push_str(rest);
+
+    let serialized = Url::parse(&placeholders)
+        .map_err(|e| e.to_string())?
+        .to_string();
+    let mut parts = Vec::new();
+    let mut rest = serialized.as_str();
+    while let Some(start) = rest.find("kurlvar") {
+        let digits = &rest[start + "kurlvar".len()..];
+        let digits = &digits[..digits.bytes().take_while(u8::is_ascii_digit).count()];
+        let after = start + "kurlvar".len() + digits.len();
+        let variable = digits.parse().ok().and_then(|i: usize| variables.get(i));
+        let (Some((name, reserved)), Some("x")) = (variable, rest.get(after..after + 1)) else {
+            return Err("a variable lost its place while the template was parsed".to_string());
+        };
+        if start > 0 {
+            parts.push(Part::Literal(rest[..start].to_string()));
+        }
+        parts.push(Part::Variable {
+            name: name.clone(),
+            reserved: *reserved,
+        });
+        rest = &rest[after + 1..];
+    }
+    if !rest.is_empty() {
+        parts.push(Part::Literal(rest.to_string()));
+    }
+    Ok(parts)
+}
+
+/// Matches `text` against the template, returning its variables in template
+/// order, decoded.
+fn captures(parts: &[Part], text: &str) -> Option<Vec<(String, String)>> {
+    let mut variables = Vec::new();
+    if !match_parts(parts, text, &mut variables) {
+        return None;
+    }
+    let mut decoded: Vec<(String, String)> = Vec::new();
+    for (name, value) in variables {
+        if !decoded.iter().any(|(seen, _)| *seen == name) {
+            decoded.push((name, edit::percent_decode(value)));
+        }
+    }
+    Some(decoded)
+}
+
+/// Backtracks over the ways of splitting `text` between the variables,
+/// trying the shortest values first.
+fn match_parts<'t>(parts: &[Part], text: &'t str, variables: &mut Vec<(String, &'t str)>) -> bool {
+    let Some((part, rest)) = parts.split_first() else {
+        return text.is_empty();
+    };
+    match part {
+        Part::Literal(literal) => text
+            .strip_prefix(literal.as_str())
+            .is_some_and(|text| match_parts(rest, text, variables)),
+        Part::Variable { name, reserved } => {
+            let earlier = variables
+                .iter()
+                .find(|(seen, _)| seen == name)
+                .map(|(_, value)| *value);
+            if let Some(value) = earlier {
+                return text
+                    .strip_prefix(value)
+                    .is_some_and(|text| match_parts(rest, text, variables));
+            }
+            if text.is_empty() {
+                return false;
+            }
+            let ends = text.char_indices().map(|(i, _)| i).skip(1);
+            for end in ends.chain([text.len()]) {
+                let value = &text[..end];
+                // Longer values would contain the delimiter too.
+                if !reserved && value.ends_with(['/', '?', '#', '&']) {
+                    return false;
+                }
+                variables.push((name.clone(), value));
+                if match_parts(rest, &text[end..], variables) {
+                    return true;
+                }
+                variables.pop();
+            }
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matched(template: &str, url: &str) -> Option<String> {
+        let url = Url::parse(url).unwrap();
+        let variables = captures(&parse(template).unwrap(), url.as_str())?;
+        Some(
+            variables
+                .iter()
+                .map(|(name, value)| format!("{}={}", name, value))
+                .collect::<Vec<_>>()
+                .join(" "),
+        )
+    }
+
+    #[test]
+    fn test_match() {
+        let template = "https://API.example.com:443/users/{id}/posts/{post}";
+        assert_eq!(
+            matched(template, "https://api.example.com/users/42/posts/7").as_deref(),
+            Some("id=42 post=7")
+        );
+        assert_eq!(
+            matched(template, "https://api.example.com/users/42/posts/"),
+            None
+        );
+        assert_eq!(
+            matched(template, "https://api.example.com/users/4/2/posts/7"),
+            None
+        );
+        assert_eq!(
+            matched(
+                "https://{tenant}.example.com/{+path}?q={q}",
+                "https://acme.example.com/docs/a%20b/c?q=caf%C3%A9"
+            )
+            .as_deref(),
+            Some("tenant=acme path=docs/a b/c q=café")
+        );
+        assert_eq!(
+            matched("https://a.com/{x}.{ext}", "https://a.com/app.min.js").as_deref(),
+            Some("x=app ext=min.js")
+        );
+        assert_eq!(
+            matched("https://a.com/{x}/{x}", "https://a.com/1/1").as_deref(),
+            Some("x=1")
+        );
+        assert_eq!(matched("https://a.com/{x}/{x}", "https://a.com/1/2"), None);
+        assert_eq!(matched("https://a.com/{x}", "https://a.com/"), None);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        for template in [
+            "https://a.com/{id",
+            "https://a.com/id}",
+            "https://a.com/{}",
+            "https://a.com/{a-b}",
+            "not a url {x}",
+        ] {
+            assert!(parse(template).is_err(), "{}", template);
+        }
+    }
+}
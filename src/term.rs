@@ -0,0 +1,152 @@
+use std::env;
+use std::fs::File;
+use std::io::{self, Write};
+use std::process::{Command, Stdio};
+
+use unicode_width::UnicodeWidthChar;
+
+/// Values are only wrapped when at least this many columns are left for them.
+const MIN_VALUE_WIDTH: usize = 20;
+
+/// Characters after which a value is best broken, so URLs and query strings
+/// wrap at their own seams.
+const BREAK_AFTER: &[char] = &['/', '&', '?', '=', ',', ';', ' ', '-', '.'];
+
+/// Columns of the terminal: `COLUMNS` if set, else what `stty size` reports.
+pub fn columns() -> Option<usize> {
+    env_size("COLUMNS").or_else(|| stty_size().map(|(_, columns)| columns))
+}
+
+/// Rows of the terminal: `LINES` if set, else what `stty size` reports.
+pub fn rows() -> Option<usize> {
+    env_size("LINES").or_else(|| stty_size().map(|(rows, _)| rows))
+}
+
+fn env_size(name: &str) -> Option<usize> {
+    env::var(name)
+        .ok()?
+        .trim()
+        .parse()
+        .ok()
+        .filter(|&size| size > 0)
+}
+
+/// Rows and columns of the controlling terminal, asked of stty, which works
+/// wherever there is a `/dev/tty`.
+fn stty_size() -> Option<(usize, usize)> {
+    let tty = File::open("/dev/tty").ok()?;
+    let output = Command::new("stty")
+        .arg("size")
+        .stdin(tty)
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+    let text = String::from_utf8(output.stdout).ok()?;
+    let mut sizes = text.split_whitespace().map(|size| size.parse().ok());
+    Some((sizes.next()??, sizes.next()??))
+}
+
+/// Writes `prefix` and `value` as one line, or, when `width` is given and the
+/// value doesn't fit, as several, each continuation starting with `↪` right
+/// before the value's column so the value reads as one block.
+pub fn write_field<W: Write>(
+    writer: &mut W,
+    prefix: &str,
+    value: &str,
+    width: Option<usize>,
+) -> io::Result<()> {
+    let column = display_column(prefix);
+    let lines = match width {
+        Some(width) if width >= column + MIN_VALUE_WIDTH => split(value, width - column),
+        _ => vec![value],
+    };
+    writeln!(writer, "{}{}", prefix, lines[0])?;
+    for line in &lines[1..] {
+        writeln!(writer, "{:>width$} {}", "↪", line, width = column - 1)?;
+    }
+    Ok(())
+}
+
+/// The column that text printed after `prefix` starts at, with tab stops
+/// every 8 columns and wide characters taking two.
+fn display_column(prefix: &str) -> usize {
+    prefix.chars().fold(0, |column, c| match c {
+        '\t' => (column / 8 + 1) * 8,
+        c => column + c.width().unwrap_or(0),
+    })
+}
+
+/// Splits `value` into lines of at most `width` columns, breaking after the
+/// last URL delimiter in the line when there is one in its second half, and
+/// never inside a wide character.
+fn split(value: &str, width: usize) -> Vec<&str> {
+    let mut lines = Vec::new();
+    let mut rest = value;
+    loop {
+        let mut used = 0;
+        let mut end = rest.len();
+        let mut seam = None;
+        for (i, c) in rest.char_indices() {
+            let c_width = c.width().unwrap_or(0);
+            if used + c_width > width {
+                end = i;
+                break;
+            }
+            used += c_width;
+            if BREAK_AFTER.contains(&c) && used * 2 >= width {
+                seam = Some(i + c.len_utf8());
+            }
+        }
+        if end == rest.len() {
+            lines.push(rest);
+            return lines;
+        }
+        let end = seam.unwrap_or(end);
+        lines.push(&rest[..end]);
+        rest = &rest[end..];
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn field(prefix: &str, value: &str, width: usize) -> String {
+        let mut out = Vec::new();
+        write_field(&mut out, prefix, value, Some(width)).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_split() {
+        assert_eq!(split("short", 20), vec!["short"]);
+        assert_eq!(
+            split("aaaaaaaaaaaaaaaaaaaaaaaaa", 10),
+            vec!["aaaaaaaaaa", "aaaaaaaaaa", "aaaaa"]
+        );
+        assert_eq!(
+            split("/docs/guide/install", 10),
+            vec!["/docs/", "guide/", "install"]
+        );
+        assert_eq!(
+            split("日本語のテキスト", 5),
+            vec!["日本", "語の", "テキ", "スト"]
+        );
+        assert_eq!(split("🎉🎉🎉a", 4), vec!["🎉🎉", "🎉a"]);
+    }
+
+    #[test]
+    fn test_write_field() {
+        assert_eq!(field("  path\t\t: ", "/short", 80), "  path\t\t: /short\n");
+        let path = "/a/very/long/path/that/does/not/fit/on/one/line";
+        assert_eq!(
+            field("  path\t\t: ", path, 40),
+            "  path\t\t: /a/very/long/path/\n                ↪ that/does/not/fit/on/\n                ↪ one/line\n"
+        );
+        // Too little room to be worth wrapping.
+        assert_eq!(
+            field("  path\t\t: ", path, 30),
+            format!("  path\t\t: {}\n", path)
+        );
+    }
+}
@@ -0,0 +1,216 @@
+use std::io::{self, Write};
+
+use crate::json::Value;
+use crate::psl;
+
+const TLD_HELP: &str = r#"kurl tld - look up a top-level domain
+
+USAGE:
+    kurl tld [OPTIONS] <NAME>
+
+OPTIONS:
+    -j, --json          Output as JSON instead of formatted text
+    -h, --help          Show this help message
+
+NAME is a TLD, with or without its leading dot, or a domain whose last label
+is looked up, so `kurl tld login.example.zip` checks `zip`. Reports whether
+the TLD is delegated, its type in the IANA root zone database
+(country-code, generic, sponsored, generic-restricted, or infrastructure, or
+reserved for special use), and the Public Suffix List rules under it.
+"#;
+
+/// Sponsored TLDs, run for a specific community by a sponsoring organization.
+const SPONSORED: &[&str] = &[
+    "aero", "asia", "cat", "coop", "edu", "gov", "int", "jobs", "mil", "mobi", "museum", "post",
+    "tel", "travel", "xxx",
+];
+/// Generic TLDs with registration restricted to eligible registrants.
+const GENERIC_RESTRICTED: &[&str] = &["biz", "name", "pro"];
+/// Internationalized country-code TLDs, in ASCII form. Two-letter ASCII TLDs
+/// are country codes by definition.
+const IDN_COUNTRY_CODES: &[&str] = &[
+    "xn--3e0b707e",
+    "xn--45brj9c",
+    "xn--80ao21a",
+    "xn--90a3ac",
+    "xn--90ais",
+    "xn--clchc0ea0b2g2a9gcd",
+    "xn--d1alf",
+    "xn--e1a4c",
+    "xn--fiqs8s",
+    "xn--fiqz9s",
+    "xn--fzc2c9e2c",
+    "xn--h2brj9c",
+    "xn--j1amh",
+    "xn--j6w193g",
+    "xn--kprw13d",
+    "xn--kpry57d",
+    "xn--l1acc",
+    "xn--lgbbat1ad8j",
+    "xn--mgbaam7a8h",
+    "xn--mgbayh7gpa",
+    "xn--mgbc0a9azcg",
+    "xn--mgberp4a5d4ar",
+    "xn--mgbtx2b",
+    "xn--node",
+    "xn--o3cw4h",
+    "xn--p1ai",
+    "xn--pgbs0dh",
+    "xn--qxam",
+    "xn--wgbh1c",
+    "xn--xkc2al3hye2a",
+    "xn--y9a3aq",
+    "xn--yfro4i67o",
+    "xn--ygbi2ammx",
+];
+/// Names reserved by RFC 2606, RFC 6761, and RFC 7686, which are never
+/// delegated.
+const SPECIAL_USE: &[&str] = &["example", "invalid", "local", "localhost", "onion", "test"];
+
+pub fn run(args: &[String]) {
+    let mut json_output = false;
+    let mut name = None;
+    for arg in args {
+        match arg.as_str() {
+            "-h" | "--help" => {
+                println!("{}", TLD_HELP);
+                return;
+            }
+            "-j" | "--json" => json_output = true,
+            other if name.is_none() && !other.starts_with('-') => name = Some(other),
+            other => {
+                eprintln!("Unknown option for tld: {}", other);
+                std::process::exit(1);
+            }
+        }
+    }
+
+    let Some(name) = name else {
+        eprintln!("Usage: kurl tld [--json] <NAME>");
+        std::process::exit(1);
+    };
+    let Some(info) = lookup(name) else {
+        eprintln!("Invalid TLD: {}", name);
+        std::process::exit(1);
+    };
+
+    let mut out = io::stdout().lock();
+    let _ = if json_output {
+        info.write(&mut out).and_then(|_| writeln!(out))
+    } else {
+        write_pretty(&mut out, &info)
+    };
+}
+
+/// Describes the last label of `name`, or returns `None` if it isn't a valid
+/// domain label.
+fn lookup(name: &str) -> Option<Value> {
+    let name = name.trim_end_matches('.');
+    let label = name.rsplit('.').next()?;
+    if label.is_empty() {
+        return None;
+    }
+    let ascii = url::quirks::domain_to_ascii(label);
+    if ascii.is_empty() || ascii.contains('.') {
+        return None;
+    }
+
+    let rules = psl::list().rules_under(&ascii);
+    let kind = if SPECIAL_USE.contains(&ascii.as_str()) {
+        "special-use"
+    } else if ascii == "arpa" {
+        "infrastructure"
+    } else if ascii.len() == 2 && ascii.bytes().all(|b| b.is_ascii_alphabetic())
+        || IDN_COUNTRY_CODES.contains(&ascii.as_str())
+    {
+        "country-code"
+    } else if SPONSORED.contains(&ascii.as_str()) {
+        "sponsored"
+    } else if GENERIC_RESTRICTED.contains(&ascii.as_str()) {
+        "generic-restricted"
+    } else {
+        "generic"
+    };
+    // Every delegated TLD has at least one rule in the list.
+    let exists = !rules.is_empty();
+
+    Some(Value::Object(vec![
+        ("tld".to_string(), Value::from(ascii.as_str())),
+        (
+            "unicode".to_string(),
+            Value::from(url::quirks::domain_to_unicode(&ascii)),
+        ),
+        ("exists".to_string(), Value::from(exists)),
+        (
+            "type".to_string(),
+            if exists || kind == "special-use" {
+                Value::from(kind)
+            } else {
+                Value::Null
+            },
+        ),
+        (
+            "rules".to_string(),
+            Value::Array(rules.into_iter().map(Value::from).collect()),
+        ),
+    ]))
+}
+
+fn write_pretty<W: Write>(writer: &mut W, info: &Value) -> io::Result<()> {
+    let field = |name| info.get(name).unwrap_or(&Value::Null);
+
+    let (tld, unicode) = (field("tld"), field("unicode"));
+    if tld == unicode {
+        writeln!(writer, "  tld\t\t: {}", tld)?;
+    } else {
+        writeln!(writer, "  tld\t\t: {} ({})", tld, unicode)?;
+    }
+    let exists = field("exists") == &Value::Bool(true);
+    writeln!(writer, "  exists\t: {}", if exists { "yes" } else { "no" })?;
+    if let kind @ Value::String(_) = field("type") {
+        writeln!(writer, "  type\t\t: {}", kind)?;
+    }
+    if let Value::Array(rules) = field("rules") {
+        writeln!(writer, "  rules\t\t: {}", rules.len())?;
+        for rule in rules {
+            writeln!(writer, "    {}", rule)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kind(name: &str) -> Value {
+        lookup(name).unwrap().get("type").unwrap().clone()
+    }
+
+    #[test]
+    fn test_types() {
+        assert_eq!(kind("com"), Value::from("generic"));
+        assert_eq!(kind(".UK"), Value::from("country-code"));
+        assert_eq!(kind("рф"), Value::from("country-code"));
+        assert_eq!(kind("xn--p1ai"), Value::from("country-code"));
+        assert_eq!(kind("edu"), Value::from("sponsored"));
+        assert_eq!(kind("biz"), Value::from("generic-restricted"));
+        assert_eq!(kind("arpa"), Value::from("infrastructure"));
+        assert_eq!(kind("login.example.zip"), Value::from("generic"));
+        assert_eq!(kind("localhost"), Value::from("special-use"));
+        assert_eq!(kind("notatld"), Value::Null);
+        assert!(lookup("").is_none());
+        assert!(lookup("..").is_none());
+    }
+
+    #[test]
+    fn test_pretty() {
+        let mut output = Vec::new();
+        write_pretty(&mut output, &lookup("ck").unwrap()).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "  tld\t\t: ck\n  exists\t: yes\n  type\t\t: country-code\n  rules\t\t: 2\n    \
+             !www.ck\n    *.ck\n"
+        );
+    }
+}
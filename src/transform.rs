@@ -0,0 +1,299 @@
+use std::io::{self, Write};
+use url::Url;
+
+use crate::{Origin, OutputOptions, Records};
+
+/// Flags shared by the commands that rewrite URLs in batch.
+#[derive(Debug, Clone, Default)]
+pub struct TransformOptions {
+    /// Report what would change instead of printing the transformed URLs.
+    pub dry_run: bool,
+    /// Print a unified diff of the URLs that would change; implies `dry_run`.
+    pub show_diff: bool,
+    /// Apply the transform a second time and fail if that changes anything.
+    pub verify_idempotent: bool,
+}
+
+impl TransformOptions {
+    /// Applies `arg` if it is a shared transform option.
+    pub fn accept(&mut self, arg: &str) -> bool {
+        match arg {
+            "--dry-run" => self.dry_run = true,
+            "--show-diff" => {
+                self.dry_run = true;
+                self.show_diff = true;
+            }
+            "--verify-idempotent" => self.verify_idempotent = true,
+            _ => return false,
+        }
+        true
+    }
+}
+
+/// What a batch transform did, or would do in a dry run.
+#[derive(Debug, Default, PartialEq)]
+pub struct Summary {
+    pub total: usize,
+    pub changed: usize,
+    pub failed: usize,
+    /// URLs that a second application of the transform changed again.
+    pub not_idempotent: usize,
+}
+
+/// Applies `transform` to every input URL and prints the results, exiting
+/// non-zero if any input failed to parse or to transform. Problems are
+/// reported by each input's origin, the argument or stdin line it came from.
+///
+/// With `verify_idempotent`, nothing is printed unless every URL passes, so a
+/// broken transform never produces partial output.
+pub fn run(
+    inputs: &[(Origin, &str)],
+    options: &TransformOptions,
+    output: &OutputOptions,
+    transform: impl Fn(&Url) -> Result<Url, String>,
+) {
+    let mut out = io::stdout().lock();
+    let result = if options.verify_idempotent {
+        let mut buffer = Vec::new();
+        write_transformed(&mut buffer, inputs, options, output, transform).and_then(|summary| {
+            if summary.not_idempotent == 0 {
+                out.write_all(&buffer)?;
+            }
+            Ok(summary)
+        })
+    } else {
+        write_transformed(&mut out, inputs, options, output, transform)
+    };
+    let Ok(summary) = result else {
+        return;
+    };
+
+    if summary.not_idempotent > 0 {
+        eprintln!(
+            "Transform is not idempotent for {} of {} URL(s); no output written",
+            summary.not_idempotent, summary.total
+        );
+        std::process::exit(1);
+    }
+    if options.dry_run {
+        eprintln!(
+            "{} of {} URL(s) would change",
+            summary.changed, summary.total
+        );
+    }
+    if summary.failed > 0 {
+        std::process::exit(1);
+    }
+}
+
+fn write_transformed<W: Write>(
+    writer: &mut W,
+    inputs: &[(Origin, &str)],
+    options: &TransformOptions,
+    output: &OutputOptions,
+    transform: impl Fn(&Url) -> Result<Url, String>,
+) -> io::Result<Summary> {
+    let mut summary = Summary::default();
+    let mut records = Records::new(writer, output);
+    let mut diff_started = false;
+
+    for &(origin, input) in inputs {
+        let url = match Url::parse(input) {
+            Ok(url) => url,
+            Err(e) => {
+                eprintln!("Failed to parse URL {}: {}", origin, e);
+                summary.failed += 1;
+                continue;
+            }
+        };
+        summary.total += 1;
+
+        let transformed = match transform(&url) {
+            Ok(transformed) => transformed,
+            Err(e) => {
+                eprintln!("Failed to transform URL {}: {}", origin, e);
+                summary.failed += 1;
+                continue;
+            }
+        };
+        let changed = transformed != url;
+        if changed {
+            summary.changed += 1;
+        }
+
+        if options.verify_idempotent {
+            let second = match transform(&transformed) {
+                Ok(again) if again == transformed => None,
+                Ok(again) => Some(format!("changed {} to {}", transformed, again)),
+                Err(e) => Some(format!("failed on {}: {}", transformed, e)),
+            };
+            if let Some(problem) = second {
+                eprintln!("Second pass {} {}", origin, problem);
+                summary.not_idempotent += 1;
+                continue;
+            }
+        }
+
+        if options.dry_run {
+            if changed && options.show_diff {
+                if !diff_started {
+                    writeln!(records.writer, "--- before\n+++ after")?;
+                    diff_started = true;
+                }
+                // Hunks are numbered by stdin line; arguments say which
+                // they are after the range.
+                match origin {
+                    Origin::Argument(n) => {
+                        writeln!(records.writer, "@@ -{0} +{0} @@ argument {0}", n)?
+                    }
+                    Origin::Line(n) | Origin::Entry(n) => {
+                        writeln!(records.writer, "@@ -{0} +{0} @@", n)?
+                    }
+                }
+                writeln!(records.writer, "-{}", url)?;
+                writeln!(records.writer, "+{}", transformed)?;
+            }
+        } else if output.json_output {
+            records.write(&transformed, &[])?;
+        } else {
+            writeln!(records.writer, "{}", transformed)?;
+        }
+    }
+
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strip_fragment(url: &Url) -> Result<Url, String> {
+        let mut url = url.clone();
+        url.set_fragment(None);
+        Ok(url)
+    }
+
+    /// Numbers `inputs` as the lines of stdin.
+    fn lines<'a>(inputs: &[&'a str]) -> Vec<(Origin, &'a str)> {
+        let numbered = inputs.iter().enumerate();
+        numbered
+            .map(|(i, &input)| (Origin::Line(i + 1), input))
+            .collect()
+    }
+
+    fn transform_to_string(inputs: &[&str], options: &TransformOptions) -> (String, Summary) {
+        let mut output = Vec::new();
+        let summary = write_transformed(
+            &mut output,
+            &lines(inputs),
+            options,
+            &OutputOptions::default(),
+            strip_fragment,
+        )
+        .unwrap();
+        (String::from_utf8(output).unwrap(), summary)
+    }
+
+    #[test]
+    fn test_transform_prints_results() {
+        let (output, summary) = transform_to_string(
+            &["https://a.com/#top", "https://b.com/"],
+            &TransformOptions::default(),
+        );
+        assert_eq!(output, "https://a.com/\nhttps://b.com/\n");
+        assert_eq!(
+            summary,
+            Summary {
+                total: 2,
+                changed: 1,
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn test_dry_run_writes_nothing() {
+        let options = TransformOptions {
+            dry_run: true,
+            ..Default::default()
+        };
+        let (output, summary) = transform_to_string(&["https://a.com/#top", "nope"], &options);
+        assert_eq!(output, "");
+        assert_eq!(summary.changed, 1);
+        assert_eq!(summary.failed, 1);
+    }
+
+    #[test]
+    fn test_verify_idempotent() {
+        let append_param = |url: &Url| {
+            let mut url = url.clone();
+            url.query_pairs_mut().append_pair("v", "2");
+            Ok(url)
+        };
+        let options = TransformOptions {
+            verify_idempotent: true,
+            ..Default::default()
+        };
+
+        let mut output = Vec::new();
+        let summary = write_transformed(
+            &mut output,
+            &lines(&["https://a.com/", "https://b.com/"]),
+            &options,
+            &OutputOptions::default(),
+            append_param,
+        )
+        .unwrap();
+        assert_eq!(summary.not_idempotent, 2);
+        assert!(output.is_empty());
+
+        let (output, summary) = transform_to_string(&["https://a.com/#x"], &options);
+        assert_eq!(summary.not_idempotent, 0);
+        assert_eq!(output, "https://a.com/\n");
+    }
+
+    #[test]
+    fn test_show_diff_lists_changed_urls() {
+        let mut options = TransformOptions::default();
+        assert!(options.accept("--show-diff"));
+        assert!(options.dry_run);
+
+        let (output, _) = transform_to_string(
+            &["https://a.com/", "https://b.com/x#frag", "https://c.com/#y"],
+            &options,
+        );
+        assert_eq!(
+            output,
+            "--- before\n+++ after\n\
+             @@ -2 +2 @@\n-https://b.com/x#frag\n+https://b.com/x\n\
+             @@ -3 +3 @@\n-https://c.com/#y\n+https://c.com/\n"
+        );
+    }
+
+    #[test]
+    fn test_show_diff_numbers_by_origin() {
+        let options = TransformOptions {
+            dry_run: true,
+            show_diff: true,
+            ..Default::default()
+        };
+        let stdin = "https://a.com/#x\n\nhttps://b.com/#y\n";
+        let args = ["https://c.com/#z", "-"];
+        let mut output = Vec::new();
+        write_transformed(
+            &mut output,
+            &crate::batch_inputs(&args, stdin),
+            &options,
+            &OutputOptions::default(),
+            strip_fragment,
+        )
+        .unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "--- before\n+++ after\n\
+             @@ -1 +1 @@ argument 1\n-https://c.com/#z\n+https://c.com/\n\
+             @@ -1 +1 @@\n-https://a.com/#x\n+https://a.com/\n\
+             @@ -3 +3 @@\n-https://b.com/#y\n+https://b.com/\n"
+        );
+    }
+}
@@ -0,0 +1,384 @@
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, List, ListItem, ListState, Paragraph, Wrap};
+use ratatui::{DefaultTerminal, Frame};
+use url::Url;
+
+use crate::edit;
+
+const TUI_HELP: &str = r#"kurl tui - inspect and edit a URL in a full-screen tree
+
+USAGE:
+    kurl tui <URL>
+
+KEYS:
+    Up/Down, j/k        Move between components, path segments, and query parameters
+    Enter, e            Edit the selected part; Enter saves, Esc cancels
+    d                   Delete the selected path segment or query parameter
+    a                   Add a query parameter
+    q, Esc              Quit, printing the edited URL
+
+Editing a part to be empty removes it.
+"#;
+
+pub fn run(args: &[String]) {
+    let mut url_arg = None;
+    for arg in args {
+        match arg.as_str() {
+            "-h" | "--help" => {
+                println!("{}", TUI_HELP);
+                return;
+            }
+            other => url_arg = Some(other),
+        }
+    }
+    let Some(url_arg) = url_arg else {
+        eprintln!("Usage: kurl tui <URL>");
+        std::process::exit(1);
+    };
+
+    let mut inspector = Inspector::new(crate::parse_url_or_exit(url_arg));
+    let mut terminal = ratatui::try_init().unwrap_or_else(|e| {
+        eprintln!("Failed to start the terminal UI: {}", e);
+        std::process::exit(1);
+    });
+    let result = inspector.event_loop(&mut terminal);
+    ratatui::restore();
+
+    if let Err(e) = result {
+        eprintln!("Terminal UI failed: {}", e);
+        std::process::exit(1);
+    }
+    println!("{}", inspector.url);
+}
+
+/// What a row of the tree stands for.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Node {
+    Component(&'static str),
+    Segment(usize),
+    Param(usize),
+}
+
+#[derive(Debug, PartialEq)]
+struct Row {
+    node: Node,
+    label: String,
+    value: String,
+}
+
+/// The URL being edited, and where the cursor is in its tree.
+struct Inspector {
+    url: Url,
+    selected: usize,
+    /// The text being typed, while editing the selected row.
+    editing: Option<String>,
+    /// The outcome of the last edit, shown in the status line.
+    message: Option<String>,
+}
+
+impl Inspector {
+    fn new(url: Url) -> Inspector {
+        Inspector {
+            url,
+            selected: 0,
+            editing: None,
+            message: None,
+        }
+    }
+
+    /// Every component, with path segments and query parameters nested
+    /// under their component.
+    fn rows(&self) -> Vec<Row> {
+        let url = &self.url;
+        let mut rows = Vec::new();
+        for &name in edit::SETTABLE {
+            let value = match name {
+                "scheme" => url.scheme().to_string(),
+                "user" => url.username().to_string(),
+                "password" => url.password().unwrap_or("").to_string(),
+                "host" => url.host_str().unwrap_or("").to_string(),
+                "port" => url.port().map(|p| p.to_string()).unwrap_or_default(),
+                "path" => url.path().to_string(),
+                "query" => url.query().unwrap_or("").to_string(),
+                _ => url.fragment().unwrap_or("").to_string(),
+            };
+            rows.push(Row {
+                node: Node::Component(name),
+                label: name.to_string(),
+                value,
+            });
+
+            if name == "path" {
+                for (i, segment) in url.path_segments().into_iter().flatten().enumerate() {
+                    rows.push(Row {
+                        node: Node::Segment(i),
+                        label: format!("  [{}]", i),
+                        value: edit::percent_decode(segment),
+                    });
+                }
+            }
+            if name == "query" {
+                for (i, (key, value)) in url.query_pairs().enumerate() {
+                    rows.push(Row {
+                        node: Node::Param(i),
+                        label: format!("  {}", key),
+                        value: value.into_owned(),
+                    });
+                }
+            }
+        }
+        rows
+    }
+
+    /// The text an edit of `node` starts from. Parameters are edited as
+    /// `key=value` so that either half can change.
+    fn edit_text(&self, node: Node) -> String {
+        match node {
+            Node::Param(i) => self
+                .url
+                .query_pairs()
+                .nth(i)
+                .map(|(k, v)| format!("{}={}", k, v))
+                .unwrap_or_default(),
+            _ => self
+                .rows()
+                .into_iter()
+                .find(|row| row.node == node)
+                .map(|row| row.value)
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Replaces the part of the URL that `node` stands for; an empty value
+    /// removes a segment or parameter.
+    fn commit(&mut self, node: Node, value: &str) -> Result<(), String> {
+        let mut url = self.url.clone();
+        match node {
+            Node::Component(name) => edit::set(&mut url, name, value)?,
+            Node::Segment(i) => {
+                let mut segments: Vec<String> = url
+                    .path_segments()
+                    .ok_or("this URL has no path segments")?
+                    .map(edit::percent_decode)
+                    .collect();
+                if value.is_empty() {
+                    segments.remove(i);
+                } else {
+                    segments[i] = value.to_string();
+                }
+                url.path_segments_mut()
+                    .map_err(|()| "this URL has no path segments")?
+                    .clear()
+                    .extend(&segments);
+            }
+            Node::Param(i) => {
+                let mut pairs: Vec<(String, String)> = url.query_pairs().into_owned().collect();
+                if value.is_empty() {
+                    pairs.remove(i);
+                } else {
+                    let (key, value) = value.split_once('=').unwrap_or((value, ""));
+                    pairs[i] = (key.to_string(), value.to_string());
+                }
+                url.query_pairs_mut().clear().extend_pairs(&pairs);
+                if pairs.is_empty() {
+                    url.set_query(None);
+                }
+            }
+        }
+        self.url = url;
+        Ok(())
+    }
+
+    /// Appends a placeholder parameter and starts editing it; saving it
+    /// empty removes it again.
+    fn add_param(&mut self) {
+        let index = self.url.query_pairs().count();
+        self.url.query_pairs_mut().append_pair("key", "");
+        if let Some(row) = self
+            .rows()
+            .iter()
+            .position(|r| r.node == Node::Param(index))
+        {
+            self.selected = row;
+            self.editing = Some(String::new());
+        }
+    }
+
+    /// Handles a key press, returning false once the user quits.
+    fn key(&mut self, code: KeyCode) -> bool {
+        let rows = self.rows();
+        let node = rows[self.selected.min(rows.len() - 1)].node;
+
+        if let Some(buffer) = &mut self.editing {
+            match code {
+                KeyCode::Enter => {
+                    let value = std::mem::take(buffer);
+                    self.editing = None;
+                    self.message = self.commit(node, &value).err();
+                }
+                KeyCode::Esc => self.editing = None,
+                KeyCode::Backspace => {
+                    buffer.pop();
+                }
+                KeyCode::Char(c) => buffer.push(c),
+                _ => {}
+            }
+            return true;
+        }
+
+        match code {
+            KeyCode::Char('q') | KeyCode::Esc => return false,
+            KeyCode::Up | KeyCode::Char('k') => self.selected = self.selected.saturating_sub(1),
+            KeyCode::Down | KeyCode::Char('j') => {
+                self.selected = (self.selected + 1).min(rows.len() - 1)
+            }
+            KeyCode::Enter | KeyCode::Char('e') => {
+                self.message = None;
+                self.editing = Some(self.edit_text(node));
+            }
+            KeyCode::Char('d') if !matches!(node, Node::Component(_)) => {
+                self.message = self.commit(node, "").err();
+            }
+            KeyCode::Char('a') => self.add_param(),
+            _ => {}
+        }
+        self.selected = self.selected.min(self.rows().len() - 1);
+        true
+    }
+
+    fn event_loop(&mut self, terminal: &mut DefaultTerminal) -> std::io::Result<()> {
+        loop {
+            terminal.draw(|frame| self.draw(frame))?;
+            if let Event::Key(key) = event::read()?
+                && key.kind == KeyEventKind::Press
+                && !self.key(key.code)
+            {
+                return Ok(());
+            }
+        }
+    }
+
+    fn draw(&self, frame: &mut Frame) {
+        let [top, middle, bottom] = Layout::vertical([
+            Constraint::Length(3),
+            Constraint::Min(1),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+
+        let url = Paragraph::new(self.url.as_str())
+            .wrap(Wrap { trim: false })
+            .block(Block::bordered().title(" URL "));
+        frame.render_widget(url, top);
+
+        let items: Vec<ListItem> = self
+            .rows()
+            .into_iter()
+            .map(|row| {
+                ListItem::new(Line::from(vec![
+                    Span::styled(
+                        format!("{:<12}", row.label),
+                        Style::new().add_modifier(Modifier::BOLD),
+                    ),
+                    Span::raw(row.value),
+                ]))
+            })
+            .collect();
+        let list = List::new(items)
+            .block(Block::bordered().title(" Components "))
+            .highlight_style(Style::new().add_modifier(Modifier::REVERSED));
+        let mut state = ListState::default().with_selected(Some(self.selected));
+        frame.render_stateful_widget(list, middle, &mut state);
+
+        let status = match (&self.editing, &self.message) {
+            (Some(buffer), _) => format!("edit: {}▏", buffer),
+            (None, Some(message)) => format!("error: {}", message),
+            (None, None) => "↑↓ move  enter edit  d delete  a add param  q quit".to_string(),
+        };
+        frame.render_widget(Paragraph::new(status), bottom);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn inspector(url: &str) -> Inspector {
+        Inspector::new(Url::parse(url).unwrap())
+    }
+
+    #[test]
+    fn test_rows_nest_segments_and_params() {
+        let rows = inspector("https://example.com/a/b?x=1&y=2").rows();
+        let nodes: Vec<Node> = rows.iter().map(|r| r.node).collect();
+        assert_eq!(
+            nodes,
+            vec![
+                Node::Component("scheme"),
+                Node::Component("user"),
+                Node::Component("password"),
+                Node::Component("host"),
+                Node::Component("port"),
+                Node::Component("path"),
+                Node::Segment(0),
+                Node::Segment(1),
+                Node::Component("query"),
+                Node::Param(0),
+                Node::Param(1),
+                Node::Component("fragment"),
+            ]
+        );
+        assert_eq!(rows[10].label, "  y");
+        assert_eq!(rows[10].value, "2");
+    }
+
+    #[test]
+    fn test_commit_edits_reserialize_the_url() {
+        let mut inspector = inspector("https://example.com/a/b?x=1&y=2");
+        inspector.commit(Node::Segment(1), "c d").unwrap();
+        assert_eq!(inspector.rows()[7].value, "c d");
+        inspector.commit(Node::Param(0), "q=a&b").unwrap();
+        inspector
+            .commit(Node::Component("host"), "kurl.dev")
+            .unwrap();
+        assert_eq!(
+            inspector.url.as_str(),
+            "https://kurl.dev/a/c%20d?q=a%26b&y=2"
+        );
+
+        inspector.commit(Node::Segment(0), "").unwrap();
+        inspector.commit(Node::Param(1), "").unwrap();
+        inspector.commit(Node::Param(0), "").unwrap();
+        assert_eq!(inspector.url.as_str(), "https://kurl.dev/c%20d");
+        assert!(inspector.commit(Node::Component("port"), "x").is_err());
+    }
+
+    #[test]
+    fn test_keys_edit_the_selected_row() {
+        let mut inspector = inspector("https://example.com/");
+        for _ in 0..3 {
+            inspector.key(KeyCode::Down);
+        }
+        inspector.key(KeyCode::Enter);
+        assert_eq!(inspector.editing.as_deref(), Some("example.com"));
+        for _ in 0.."com".len() {
+            inspector.key(KeyCode::Backspace);
+        }
+        for c in "org".chars() {
+            inspector.key(KeyCode::Char(c));
+        }
+        inspector.key(KeyCode::Enter);
+        assert_eq!(inspector.url.as_str(), "https://example.org/");
+
+        inspector.key(KeyCode::Char('a'));
+        for c in "page=2".chars() {
+            inspector.key(KeyCode::Char(c));
+        }
+        inspector.key(KeyCode::Enter);
+        assert_eq!(inspector.url.as_str(), "https://example.org/?page=2");
+        assert!(!inspector.key(KeyCode::Char('q')));
+    }
+}
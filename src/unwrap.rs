@@ -0,0 +1,269 @@
+use std::io::{self, IsTerminal};
+use url::Url;
+
+use crate::OutputOptions;
+use crate::transform::{self, TransformOptions};
+
+const UNWRAP_HELP: &str = r#"kurl unwrap - recover the original URL from cache and translate links
+
+USAGE:
+    kurl unwrap [OPTIONS] [<URL>...]
+    <URLs> | kurl unwrap [OPTIONS]
+
+OPTIONS:
+    -j, --json              Print each result's components as JSON
+        --dry-run           Report how many URLs would change without printing them
+        --show-diff         Print a diff of the URLs that would change
+        --verify-idempotent Fail, printing nothing, unless a second pass leaves
+                            every result unchanged
+    -h, --help              Show this help message
+
+Recognizes these wrappers and prints the URL inside:
+
+    Google cache        webcache.googleusercontent.com/search?q=cache:...
+    Google Translate    translate.google.com/translate?u=...,
+                        translate.googleusercontent.com/translate_c?u=...,
+                        and proxied hosts like example-com.translate.goog
+    Bing cache          cc.bingj.com/cache.aspx?...&url=...
+
+Wrappers inside wrappers are unwrapped all the way, and other URLs print
+unchanged. Targets without a scheme, as in some cache links, get https://.
+Bing cache links that only carry a document ID, with no `url` or `u`
+parameter, name no original URL and fail.
+
+EXAMPLES:
+    kurl unwrap 'https://webcache.googleusercontent.com/search?q=cache:abc123:example.com/post+kurl'
+    kurl unwrap 'https://example-com.translate.goog/docs?_x_tr_sl=de&_x_tr_tl=en'
+    cat links.txt | kurl unwrap --show-diff
+"#;
+
+/// How many wrappers deep `unwrap` looks before giving up.
+const MAX_DEPTH: usize = 8;
+
+pub fn run(args: &[String]) {
+    let mut urls = Vec::new();
+    let mut options = TransformOptions::default();
+    let mut output = OutputOptions::default();
+
+    let mut rest = args.iter();
+    while let Some(arg) = rest.next() {
+        match arg.as_str() {
+            "-h" | "--help" => {
+                println!("{}", UNWRAP_HELP);
+                return;
+            }
+            other if options.accept(other) => {}
+            other if output.accept(other, &mut rest) => {}
+            other if other.starts_with('-') => {
+                eprintln!("Unknown option for unwrap: {}", other);
+                std::process::exit(1);
+            }
+            other => urls.push(other),
+        }
+    }
+
+    let mut buffer = String::new();
+    if urls.is_empty() {
+        if io::stdin().is_terminal() {
+            eprintln!("Usage: kurl unwrap [<URL>...]");
+            std::process::exit(1);
+        }
+        buffer = crate::read_stdin();
+        urls.push("-");
+    }
+    let inputs = crate::batch_inputs(&urls, &buffer);
+
+    transform::run(&inputs, &options, &output, unwrap);
+}
+
+/// Unwraps `url` until it is no longer a cache or translate link.
+pub fn unwrap(url: &Url) -> Result<Url, String> {
+    let mut url = url.clone();
+    for _ in 0..MAX_DEPTH {
+        match inner(&url)? {
+            Some(inner) => url = inner,
+            None => return Ok(url),
+        }
+    }
+    Err(format!("more than {} wrappers deep", MAX_DEPTH))
+}
+
+/// The URL that `url` wraps, if it is a wrapper.
+fn inner(url: &Url) -> Result<Option<Url>, String> {
+    let Some(host) = url.host_str() else {
+        return Ok(None);
+    };
+    let param = |name: &str| {
+        url.query_pairs()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.into_owned())
+    };
+
+    let target = if host == "webcache.googleusercontent.com" && url.path() == "/search" {
+        let Some(cached) = param("q").and_then(|q| q.strip_prefix("cache:").map(str::to_string))
+        else {
+            return Ok(None);
+        };
+        cache_target(&cached).to_string()
+    } else if (is_google_translate(host) && url.path() == "/translate")
+        || (host == "translate.googleusercontent.com" && url.path() == "/translate_c")
+    {
+        match param("u") {
+            Some(target) => target,
+            None => return Ok(None),
+        }
+    } else if let Some(proxied) = host.strip_suffix(".translate.goog") {
+        return translate_proxy(url, proxied).map(Some);
+    } else if host == "cc.bingj.com" && url.path().eq_ignore_ascii_case("/cache.aspx") {
+        match param("url").or_else(|| param("u")) {
+            Some(target) => target,
+            None => return Err(format!("{} names no original URL", url)),
+        }
+    } else {
+        return Ok(None);
+    };
+
+    parse_target(&target).map(Some)
+}
+
+/// Whether `host` is a Google Translate host, such as translate.google.com
+/// or translate.google.co.uk.
+fn is_google_translate(host: &str) -> bool {
+    host.strip_prefix("translate.google.")
+        .is_some_and(|suffix| !suffix.is_empty() && !suffix.contains(".google"))
+}
+
+/// The URL in a Google cache query, which is `KEY:URL SEARCH TERMS` or
+/// `URL SEARCH TERMS` once `cache:` is removed.
+fn cache_target(cached: &str) -> &str {
+    let cached = match cached.split_once(':') {
+        Some((key, rest))
+            if !rest.starts_with("//")
+                && !key.contains('.')
+                && key
+                    .bytes()
+                    .all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_') =>
+        {
+            rest
+        }
+        _ => cached,
+    };
+    cached.split(' ').next().unwrap_or_default()
+}
+
+/// The original of a `*.translate.goog` URL, whose first label is the host
+/// with `-` for `.` and `--` for `-`, and whose query has `_x_tr_*`
+/// parameters added.
+fn translate_proxy(url: &Url, proxied: &str) -> Result<Url, String> {
+    let host = proxied
+        .split("--")
+        .map(|part| part.replace('-', "."))
+        .collect::<Vec<_>>()
+        .join("-");
+    let mut original = url.clone();
+    original
+        .set_host(Some(&host))
+        .map_err(|e| format!("cannot read host {:?} from {}: {}", host, url, e))?;
+    let pairs: Vec<(String, String)> = url
+        .query_pairs()
+        .filter(|(key, _)| !key.starts_with("_x_tr_"))
+        .map(|(key, value)| (key.into_owned(), value.into_owned()))
+        .collect();
+    if pairs.is_empty() {
+        original.set_query(None);
+    } else {
+        original.query_pairs_mut().clear().extend_pairs(pairs);
+    }
+    Ok(original)
+}
+
+/// Parses a wrapped URL, assuming https:// when it has no scheme.
+fn parse_target(target: &str) -> Result<Url, String> {
+    let error = |e: url::ParseError| format!("cannot parse wrapped URL {:?}: {}", target, e);
+    match Url::parse(target) {
+        Ok(url) if !crate::looks_schemeless(&url) => Ok(url),
+        Ok(_) | Err(url::ParseError::RelativeUrlWithoutBase) => {
+            Url::parse(&format!("https://{}", target.trim_start_matches('/'))).map_err(error)
+        }
+        Err(e) => Err(error(e)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn unwrapped(url: &str) -> Result<String, String> {
+        unwrap(&Url::parse(url).unwrap()).map(String::from)
+    }
+
+    #[test]
+    fn test_unwrap() {
+        for (wrapped, original) in [
+            (
+                "https://webcache.googleusercontent.com/search?q=cache:a1B2c3D4e5F6J:https://example.com/post%3Fid%3D7+kurl&cd=1&hl=en",
+                "https://example.com/post?id=7",
+            ),
+            (
+                "https://webcache.googleusercontent.com/search?q=cache:example.com/post",
+                "https://example.com/post",
+            ),
+            (
+                "http://webcache.googleusercontent.com/search?q=cache:http://example.com:8080/",
+                "http://example.com:8080/",
+            ),
+            (
+                "https://translate.google.com/translate?sl=auto&tl=en&u=https://example.de/seite%3Fa%3D1",
+                "https://example.de/seite?a=1",
+            ),
+            (
+                "https://translate.google.co.uk/translate?u=example.fr/page",
+                "https://example.fr/page",
+            ),
+            (
+                "https://translate.google.com/translate?u=example.fr:8080/page",
+                "https://example.fr:8080/page",
+            ),
+            (
+                "https://translate.googleusercontent.com/translate_c?depth=1&u=https://example.jp/",
+                "https://example.jp/",
+            ),
+            (
+                "https://my--site-example-com.translate.goog/docs?id=7&_x_tr_sl=de&_x_tr_tl=en&_x_tr_hl=en",
+                "https://my-site.example.com/docs?id=7",
+            ),
+            (
+                "https://example-com.translate.goog/?_x_tr_sl=auto",
+                "https://example.com/",
+            ),
+            (
+                "https://cc.bingj.com/cache.aspx?d=123&w=abc&url=https%3a%2f%2fexample.com%2fa",
+                "https://example.com/a",
+            ),
+            (
+                "https://translate.google.com/translate?u=https://webcache.googleusercontent.com/search%3Fq%3Dcache:example.com/",
+                "https://example.com/",
+            ),
+            (
+                "https://example.com/search?q=cache:x",
+                "https://example.com/search?q=cache:x",
+            ),
+            (
+                "https://translate.google.com/",
+                "https://translate.google.com/",
+            ),
+        ] {
+            assert_eq!(unwrapped(wrapped), Ok(original.to_string()), "{}", wrapped);
+        }
+    }
+
+    #[test]
+    fn test_unwrap_errors() {
+        assert!(
+            unwrapped("http://cc.bingj.com/cache.aspx?q=kurl&d=4852700434798340&w=kcNGmdSl")
+                .unwrap_err()
+                .contains("names no original URL")
+        );
+        assert!(unwrapped("https://translate.google.com/translate?u=https://[::1").is_err());
+    }
+}
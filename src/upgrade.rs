@@ -0,0 +1,122 @@
+use std::io::{self, IsTerminal};
+use url::Url;
+
+use crate::OutputOptions;
+use crate::edit;
+use crate::transform::{self, TransformOptions};
+
+const TO_HTTPS_HELP: &str = r#"kurl to-https - switch URLs to their TLS schemes
+
+USAGE:
+    kurl to-https [OPTIONS] [<URL>...]
+    <URLs> | kurl to-https [OPTIONS]
+
+OPTIONS:
+        --reverse           Switch https to http and wss to ws instead
+    -j, --json              Print each result's components as JSON
+        --dry-run           Report how many URLs would change without printing them
+        --show-diff         Print a diff of the URLs that would change
+        --verify-idempotent Fail, printing nothing, unless a second pass leaves
+                            every result unchanged
+    -h, --help              Show this help message
+
+Switches http to https and ws to wss, leaving other schemes alone. Ports
+move with the scheme: http://host:443 becomes https://host rather than
+https://host:443, and a port that is neither scheme's default, like 8080,
+stays. To switch to any other scheme, with the same port handling, use
+`kurl --set-scheme NAME`.
+
+EXAMPLES:
+    kurl to-https http://example.com/login ws://example.com:80/socket
+    cat urls.txt | kurl to-https --show-diff
+    kurl to-https --reverse https://localhost:8443/
+"#;
+
+/// Schemes and their TLS counterparts.
+const PAIRS: &[(&str, &str)] = &[("http", "https"), ("ws", "wss")];
+
+pub fn run(args: &[String]) {
+    let mut reverse = false;
+    let mut urls = Vec::new();
+    let mut options = TransformOptions::default();
+    let mut output = OutputOptions::default();
+
+    let mut rest = args.iter();
+    while let Some(arg) = rest.next() {
+        match arg.as_str() {
+            "-h" | "--help" => {
+                println!("{}", TO_HTTPS_HELP);
+                return;
+            }
+            "--reverse" => reverse = true,
+            other if options.accept(other) => {}
+            other if output.accept(other, &mut rest) => {}
+            other if other.starts_with('-') => {
+                eprintln!("Unknown option for to-https: {}", other);
+                std::process::exit(1);
+            }
+            other => urls.push(other),
+        }
+    }
+
+    let mut buffer = String::new();
+    if urls.is_empty() {
+        if io::stdin().is_terminal() {
+            eprintln!("Usage: kurl to-https [--reverse] [<URL>...]");
+            std::process::exit(1);
+        }
+        buffer = crate::read_stdin();
+        urls.push("-");
+    }
+    let inputs = crate::batch_inputs(&urls, &buffer);
+
+    transform::run(&inputs, &options, &output, |url| to_https(url, reverse));
+}
+
+/// Switches `url` to its TLS scheme, or away from it when `reverse`.
+fn to_https(url: &Url, reverse: bool) -> Result<Url, String> {
+    let mut url = url.clone();
+    let target = PAIRS.iter().find_map(|&(plain, tls)| match reverse {
+        false if url.scheme() == plain => Some(tls),
+        true if url.scheme() == tls => Some(plain),
+        _ => None,
+    });
+    if let Some(target) = target {
+        edit::switch_scheme(&mut url, target)?;
+    }
+    Ok(url)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn switched(url: &str, reverse: bool) -> String {
+        to_https(&Url::parse(url).unwrap(), reverse)
+            .unwrap()
+            .to_string()
+    }
+
+    #[test]
+    fn test_to_https() {
+        assert_eq!(
+            switched("http://example.com/login?next=/", false),
+            "https://example.com/login?next=/"
+        );
+        assert_eq!(
+            switched("http://example.com:443/", false),
+            "https://example.com/"
+        );
+        assert_eq!(
+            switched("ws://example.com:8080/socket", false),
+            "wss://example.com:8080/socket"
+        );
+        assert_eq!(
+            switched("https://example.com/", false),
+            "https://example.com/"
+        );
+        assert_eq!(switched("ftp://example.com/", false), "ftp://example.com/");
+        assert_eq!(switched("wss://example.com:80/", true), "ws://example.com/");
+        assert_eq!(switched("http://example.com/", true), "http://example.com/");
+    }
+}
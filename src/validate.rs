@@ -0,0 +1,239 @@
+use std::io::{self, IsTerminal, Write};
+use url::{ParseError, Url};
+
+use crate::json::Value;
+
+const VALIDATE_HELP: &str = r#"kurl validate - check that URLs parse, for scripts and hooks
+
+USAGE:
+    kurl validate [OPTIONS] [<URL>...]
+    <URLs> | kurl validate [OPTIONS]
+
+OPTIONS:
+    -q, --quiet         Print nothing; only set the exit status
+        --strict-rfc3986
+                        Also fail URLs that parse only because the URL Standard
+                        fixes them, such as ones with a backslash or a space
+    -j, --json          Print a JSON object per URL with `input`, `valid`, and
+                        either the parsed `url` or the `error`, its
+                        `category`, and its exit `code`
+    -h, --help          Show this help message
+
+Prints each URL that parses, as kurl normalizes it, and reports each one that
+doesn't on stderr. Exits 0 if every URL parses, or else with the code of the
+first failure's category:
+
+    2   missing-scheme   relative, with no scheme, like example.com/a
+    3   invalid-host     an empty host, or a domain IDNA or the URL Standard
+                         rejects, like exa mple.com
+    4   invalid-ip       a malformed IPv4 or IPv6 address, like [::1
+    5   invalid-port     a port that isn't a number up to 65535
+    6   invalid-url      anything else, such as a URL too long to hold
+    7   not-rfc3986      with --strict-rfc3986, a URL outside the RFC 3986
+                         grammar, like https://example.com/a b
+
+Usage errors exit 1.
+
+EXAMPLES:
+    kurl validate -q "$url" || echo "not a URL: $url" >&2
+    kurl validate < links.txt > valid.txt
+    kurl validate --json 'https://example.com:99999/'
+"#;
+
+/// Why a URL failed to parse, with the exit code `kurl validate` gives it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Category {
+    MissingScheme,
+    InvalidHost,
+    InvalidIp,
+    InvalidPort,
+    Other,
+    NotRfc3986,
+}
+
+impl Category {
+    pub fn of(error: ParseError) -> Category {
+        match error {
+            ParseError::RelativeUrlWithoutBase | ParseError::RelativeUrlWithCannotBeABaseBase => {
+                Category::MissingScheme
+            }
+            ParseError::EmptyHost | ParseError::IdnaError | ParseError::InvalidDomainCharacter => {
+                Category::InvalidHost
+            }
+            ParseError::InvalidIpv4Address | ParseError::InvalidIpv6Address => Category::InvalidIp,
+            ParseError::InvalidPort => Category::InvalidPort,
+            _ => Category::Other,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Category::MissingScheme => "missing-scheme",
+            Category::InvalidHost => "invalid-host",
+            Category::InvalidIp => "invalid-ip",
+            Category::InvalidPort => "invalid-port",
+            Category::Other => "invalid-url",
+            Category::NotRfc3986 => "not-rfc3986",
+        }
+    }
+
+    pub fn code(self) -> i32 {
+        match self {
+            Category::MissingScheme => 2,
+            Category::InvalidHost => 3,
+            Category::InvalidIp => 4,
+            Category::InvalidPort => 5,
+            Category::Other => 6,
+            Category::NotRfc3986 => 7,
+        }
+    }
+}
+
+pub fn run(args: &[String]) {
+    let mut quiet = false;
+    let mut strict = false;
+    let mut json_output = false;
+    let mut urls = Vec::new();
+    for arg in args {
+        match arg.as_str() {
+            "-h" | "--help" => {
+                println!("{}", VALIDATE_HELP);
+                return;
+            }
+            "-q" | "--quiet" => quiet = true,
+            "--strict-rfc3986" => strict = true,
+            "-j" | "--json" => json_output = true,
+            other if other.starts_with('-') => {
+                eprintln!("Unknown option for validate: {}", other);
+                std::process::exit(1);
+            }
+            other => urls.push(other),
+        }
+    }
+
+    let buffer;
+    if urls.is_empty() {
+        if io::stdin().is_terminal() {
+            eprintln!("Usage: kurl validate [-q] [--strict-rfc3986] [--json] <URL>...");
+            std::process::exit(1);
+        }
+        buffer = crate::read_stdin();
+        urls = buffer
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .collect();
+    }
+
+    let mut out = io::stdout().lock();
+    let mut first_failure = None;
+    for input in urls {
+        let result = validate(input, strict);
+        if let Err((_, category)) = result {
+            first_failure.get_or_insert(category);
+        }
+        if quiet {
+            continue;
+        }
+        let written = match (&result, json_output) {
+            (_, true) => report(input, &result)
+                .write(&mut out)
+                .and_then(|_| writeln!(out)),
+            (Ok(url), false) => writeln!(out, "{}", url),
+            (Err((e, category)), false) => {
+                eprintln!("{}: {} ({})", input, e, category.name());
+                Ok(())
+            }
+        };
+        if written.is_err() {
+            break;
+        }
+    }
+    if let Some(category) = first_failure {
+        std::process::exit(category.code());
+    }
+}
+
+/// Parses `input`, or says why it failed and in which category.
+fn validate(input: &str, strict: bool) -> Result<Url, (String, Category)> {
+    if strict {
+        crate::rfc3986::check(input).map_err(|e| (e, Category::NotRfc3986))?;
+    }
+    Url::parse(input).map_err(|e| (e.to_string(), Category::of(e)))
+}
+
+/// The `--json` object for `input`, which was validated as `result`.
+fn report(input: &str, result: &Result<Url, (String, Category)>) -> Value {
+    let mut fields = vec![("input".to_string(), Value::from(input))];
+    match result {
+        Ok(url) => {
+            fields.push(("valid".to_string(), Value::Bool(true)));
+            fields.push(("url".to_string(), Value::from(url.as_str())));
+        }
+        Err((e, category)) => {
+            fields.push(("valid".to_string(), Value::Bool(false)));
+            fields.push(("error".to_string(), Value::from(e.as_str())));
+            fields.push(("category".to_string(), Value::from(category.name())));
+            fields.push(("code".to_string(), Value::from(category.code() as usize)));
+        }
+    }
+    Value::Object(fields)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn category(input: &str) -> Option<Category> {
+        Url::parse(input).err().map(Category::of)
+    }
+
+    #[test]
+    fn test_category() {
+        assert_eq!(category("https://example.com/a b"), None);
+        assert_eq!(category("example.com/a"), Some(Category::MissingScheme));
+        assert_eq!(category(""), Some(Category::MissingScheme));
+        assert_eq!(
+            category("http://exa mple.com/"),
+            Some(Category::InvalidHost)
+        );
+        assert_eq!(category("http://:80/"), Some(Category::InvalidHost));
+        assert_eq!(category("http://[::1/"), Some(Category::InvalidIp));
+        assert_eq!(category("http://256.0.0.1/"), Some(Category::InvalidIp));
+        assert_eq!(
+            category("http://example.com:99999/"),
+            Some(Category::InvalidPort)
+        );
+        let codes: Vec<i32> = [
+            Category::MissingScheme,
+            Category::InvalidHost,
+            Category::InvalidIp,
+            Category::InvalidPort,
+            Category::Other,
+            Category::NotRfc3986,
+        ]
+        .iter()
+        .map(|category| category.code())
+        .collect();
+        assert_eq!(codes, [2, 3, 4, 5, 6, 7]);
+    }
+
+    #[test]
+    fn test_report() {
+        let input = "https://example.com:99999/";
+        assert_eq!(
+            report(input, &validate(input, false)).to_json(),
+            r#"{"input":"https://example.com:99999/","valid":false,"error":"invalid port number","category":"invalid-port","code":5}"#
+        );
+        let input = "HTTPS://Example.com";
+        assert_eq!(
+            report(input, &validate(input, false)).to_json(),
+            r#"{"input":"HTTPS://Example.com","valid":true,"url":"https://example.com/"}"#
+        );
+        let input = "https://example.com/a b";
+        assert_eq!(
+            report(input, &validate(input, true)).to_json(),
+            r#"{"input":"https://example.com/a b","valid":false,"error":"not valid RFC 3986: ' ' at column 22 isn't allowed in the path","category":"not-rfc3986","code":7}"#
+        );
+    }
+}